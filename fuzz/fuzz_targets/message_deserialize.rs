@@ -0,0 +1,46 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+#![no_main]
+
+use snarkos::{Client, Message};
+use snarkvm::dpc::testnet2::Testnet2;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|buffer: &[u8]| {
+    // Decode the message, followed by any deferred fields, as a peer handler would.
+    if let Ok(message) = Message::<Testnet2, Client<Testnet2>>::deserialize(buffer) {
+        match message {
+            Message::BlockResponse(block) | Message::UnconfirmedBlock(_, _, block) => {
+                let _ = block.deserialize_blocking();
+            }
+            Message::ChallengeResponse(block_header) | Message::Ping(_, _, _, _, _, block_header) => {
+                let _ = block_header.deserialize_blocking();
+            }
+            Message::Pong(_, block_locators) => {
+                let _ = block_locators.deserialize_blocking();
+            }
+            Message::PoolRequest(_, block_template) => {
+                let _ = block_template.deserialize_blocking();
+            }
+            Message::PoolResponse(_, _, proof) => {
+                let _ = proof.deserialize_blocking();
+            }
+            _ => (),
+        }
+    }
+});
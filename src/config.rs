@@ -0,0 +1,259 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::environment::Parameters;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{env::VarError, fmt::Display, net::SocketAddr, path::Path, str::FromStr};
+
+/// The prefix of every environment variable that configures the node.
+const ENV_PREFIX: &str = "SNARKOS_";
+
+///
+/// The configuration of a node, as loaded from a TOML file and from environment variables.
+///
+/// Every option mirrors a command-line flag of the same name on `Node`, and the `[environment]`
+/// table overrides the tunable parameters of the `Environment`. Options are layered with the
+/// following precedence: config file, then environment variables, then command-line flags.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub connect: Option<String>,
+    pub miner: Option<String>,
+    pub operator: Option<String>,
+    pub prover: Option<String>,
+    pub pool: Option<SocketAddr>,
+    pub network: Option<u16>,
+    pub node: Option<SocketAddr>,
+    pub rpc: Option<SocketAddr>,
+    pub rpc_username: Option<String>,
+    pub rpc_password: Option<String>,
+    pub verbosity: Option<u8>,
+    pub dev: Option<u16>,
+    pub display: Option<bool>,
+    pub norpc: Option<bool>,
+    pub trial: Option<bool>,
+    pub sync: Option<bool>,
+    /// The tunable parameters of the `Environment`.
+    pub environment: Parameters,
+}
+
+impl Config {
+    /// Loads the configuration from the given TOML file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| anyhow!("Failed to read the config file ({}): {}", path.display(), error))?;
+        let config: Self = toml::from_str(&contents).map_err(|error| anyhow!("Invalid config file ({}): {}", path.display(), error))?;
+        config
+            .environment
+            .validate()
+            .map_err(|error| anyhow!("Invalid config file ({}): {}", path.display(), error))?;
+        Ok(config)
+    }
+
+    ///
+    /// Overrides the configuration with any `SNARKOS_*` environment variables that are set,
+    /// as returned by the given lookup (e.g. `std::env::var`).
+    ///
+    pub fn load_env_vars<F: Fn(&str) -> Result<String, VarError>>(&mut self, var: F) -> Result<()> {
+        macro_rules! load {
+            ($config:expr, $($field:ident),*) => {
+                $(if let Some(value) = env_var(&var, stringify!($field))? {
+                    $config.$field = Some(value);
+                })*
+            };
+        }
+
+        load!(
+            self,
+            connect,
+            miner,
+            operator,
+            prover,
+            pool,
+            network,
+            node,
+            rpc,
+            rpc_username,
+            rpc_password,
+            verbosity,
+            dev,
+            display,
+            norpc,
+            trial,
+            sync
+        );
+
+        let environment = &mut self.environment;
+        load!(
+            environment,
+            require_encryption,
+            heartbeat_in_secs,
+            connection_timeout_in_millis,
+            ping_sleep_in_secs,
+            radio_silence_in_secs,
+            misbehavior_half_life_in_secs,
+            misbehavior_ban_duration_in_secs,
+            minimum_number_of_peers,
+            maximum_number_of_peers,
+            maximum_number_of_outbound_peers,
+            maximum_number_of_inbound_peers,
+            maximum_inbound_peers_per_subnet,
+            maximum_connection_failures,
+            maximum_candidate_peers,
+            maximum_message_size,
+            maximum_block_request
+        );
+
+        if let Some(nodes) = env_var_list(&var, "beacon_nodes")? {
+            environment.beacon_nodes = Some(nodes);
+        }
+        if let Some(nodes) = env_var_list(&var, "sync_nodes")? {
+            environment.sync_nodes = Some(nodes);
+        }
+        if let Some(nodes) = env_var_list(&var, "trusted_peers")? {
+            environment.trusted_peers = Some(nodes);
+        }
+
+        environment.validate()
+    }
+}
+
+/// Returns the parsed value of the environment variable for the given option, if it is set.
+fn env_var<T: FromStr, F: Fn(&str) -> Result<String, VarError>>(var: &F, option: &str) -> Result<Option<T>>
+where
+    T::Err: Display,
+{
+    let key = format!("{}{}", ENV_PREFIX, option.to_uppercase());
+    match var(&key) {
+        Ok(value) => match value.trim().parse() {
+            Ok(value) => Ok(Some(value)),
+            Err(error) => Err(anyhow!("Invalid value for {} ('{}'): {}", key, value, error)),
+        },
+        Err(VarError::NotPresent) => Ok(None),
+        Err(error) => Err(anyhow!("Invalid value for {}: {}", key, error)),
+    }
+}
+
+/// Returns the parsed comma-separated list of the environment variable for the given option, if it is set.
+fn env_var_list<T: FromStr, F: Fn(&str) -> Result<String, VarError>>(var: &F, option: &str) -> Result<Option<Vec<T>>>
+where
+    T::Err: Display,
+{
+    let key = format!("{}{}", ENV_PREFIX, option.to_uppercase());
+    match env_var::<String, F>(var, option)? {
+        Some(values) => values
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|error| anyhow!("Invalid value for {} ('{}'): {}", key, value, error))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Returns the configuration overridden by the given variables, instead of the environment of the process.
+    fn load_env_vars(vars: &[(&str, &str)]) -> Result<Config> {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        let mut config = Config::default();
+        config.load_env_vars(|key| vars.get(key).cloned().ok_or(VarError::NotPresent))?;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_deny_unknown_fields() {
+        let config: Config = toml::from_str("rpc_username = \"user\"\n[environment]\nmaximum_number_of_peers = 30\n").unwrap();
+        assert_eq!(Some("user".to_string()), config.rpc_username);
+        assert_eq!(Some(30), config.environment.maximum_number_of_peers);
+
+        // A misspelled option is rejected, rather than silently ignored.
+        assert!(toml::from_str::<Config>("rpc_user = \"user\"\n").is_err());
+        assert!(toml::from_str::<Config>("[environment]\nmax_peers = 30\n").is_err());
+    }
+
+    #[test]
+    fn test_load_invalid_config() {
+        let path = tempfile::tempdir().unwrap().into_path().join("node.toml");
+        std::fs::write(&path, "[environment]\nheartbeat_in_secs = 0\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        std::fs::write(&path, "verbosity = \"high\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+
+        assert!(Config::load(path.with_file_name("missing.toml")).is_err());
+    }
+
+    #[test]
+    fn test_load_env_vars() {
+        let config = load_env_vars(&[
+            ("SNARKOS_VERBOSITY", " 2 "),
+            ("SNARKOS_RPC_PASSWORD", "password"),
+            ("SNARKOS_MAXIMUM_NUMBER_OF_PEERS", "30"),
+            ("SNARKOS_SYNC_NODES", " 1.2.3.4:4132, ,5.6.7.8:4132,"),
+            ("OTHER_VERBOSITY", "4"),
+        ])
+        .unwrap();
+        assert_eq!(Some(2), config.verbosity);
+        assert_eq!(Some("password".to_string()), config.rpc_password);
+        assert_eq!(None, config.rpc_username);
+        assert_eq!(Some(30), config.environment.maximum_number_of_peers);
+
+        // The list is split on commas, and empty entries are skipped.
+        let sync_nodes = vec!["1.2.3.4:4132".parse().unwrap(), "5.6.7.8:4132".parse().unwrap()];
+        assert_eq!(Some(sync_nodes), config.environment.sync_nodes);
+
+        // An empty list is specified as an empty string.
+        assert_eq!(
+            Some(vec![]),
+            load_env_vars(&[("SNARKOS_TRUSTED_PEERS", "")]).unwrap().environment.trusted_peers
+        );
+    }
+
+    #[test]
+    fn test_load_malformed_env_vars() {
+        assert!(load_env_vars(&[("SNARKOS_VERBOSITY", "high")]).is_err());
+        assert!(load_env_vars(&[("SNARKOS_NORPC", "yes")]).is_err());
+        assert!(load_env_vars(&[("SNARKOS_NODE", "1.2.3.4")]).is_err());
+        assert!(load_env_vars(&[("SNARKOS_TRUSTED_PEERS", "1.2.3.4:4132,5.6.7.8")]).is_err());
+
+        // The loaded parameters are validated.
+        assert!(load_env_vars(&[("SNARKOS_HEARTBEAT_IN_SECS", "0")]).is_err());
+
+        // A value that is not valid unicode is rejected.
+        let mut config = Config::default();
+        let not_unicode = |key: &str| match key {
+            "SNARKOS_RPC_USERNAME" => Err(VarError::NotUnicode(Default::default())),
+            _ => Err(VarError::NotPresent),
+        };
+        assert!(config.load_env_vars(not_unicode).is_err());
+    }
+}
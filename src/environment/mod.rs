@@ -14,7 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::helpers::{NodeType, Status, Tasks};
+pub mod parameters;
+pub use parameters::*;
+
+use crate::helpers::{Capabilities, NodeType, Status, Tasks};
 use snarkvm::dpc::Network;
 
 use once_cell::sync::OnceCell;
@@ -56,13 +59,21 @@ pub trait Environment: 'static + Clone + Debug + Default + Send + Sync {
     /// The duration in seconds after which a connected peer is considered inactive or
     /// disconnected if no message has been received in the meantime.
     const RADIO_SILENCE_IN_SECS: u64 = 120; // 3.5 minutes
-    /// The duration in seconds after which to expire a failure from a peer.
-    const FAILURE_EXPIRY_TIME_IN_SECS: u64 = 7200; // 2 hours
+    /// The duration in seconds after which the misbehavior score of a peer decays by half.
+    const MISBEHAVIOR_HALF_LIFE_IN_SECS: u64 = 1800; // 30 minutes
+    /// The duration in seconds for which a peer is banned, once its misbehavior score reaches the ban threshold.
+    const MISBEHAVIOR_BAN_DURATION_IN_SECS: u64 = 3600; // 1 hour
+    /// If `true`, the node only maintains connections with peers that negotiate an encrypted transport.
+    const REQUIRE_ENCRYPTION: bool = false;
 
     /// The minimum number of peers required to maintain connections with.
     const MINIMUM_NUMBER_OF_PEERS: usize;
     /// The maximum number of peers permitted to maintain connections with.
     const MAXIMUM_NUMBER_OF_PEERS: usize;
+    /// The number of outbound connections that the node maintains, unless the minimum number of peers is greater.
+    const MAXIMUM_NUMBER_OF_OUTBOUND_PEERS: usize = 8;
+    /// The maximum number of inbound connections permitted from one address group (a /16 IPv4 or /32 IPv6 subnet).
+    const MAXIMUM_INBOUND_PEERS_PER_SUBNET: usize = 4;
     /// The maximum number of connection failures permitted by an inbound connecting peer.
     const MAXIMUM_CONNECTION_FAILURES: u32 = 3;
     /// The maximum number of candidate peers permitted to be stored in the node.
@@ -72,19 +83,140 @@ pub trait Environment: 'static + Clone + Debug + Default + Send + Sync {
     const MAXIMUM_MESSAGE_SIZE: usize = 128 * 1024 * 1024; // 128 MiB
     /// The maximum number of blocks that may be fetched in one request.
     const MAXIMUM_BLOCK_REQUEST: u32 = 250;
-    /// The maximum number of failures tolerated before disconnecting from a peer.
-    const MAXIMUM_NUMBER_OF_FAILURES: usize = 1024;
+    /// The maximum number of block headers that may be fetched in one request.
+    const MAXIMUM_BLOCK_HEADERS_REQUEST: u32 = 2000;
+    /// The maximum number of block hashes and transaction IDs in one inventory announcement or request.
+    const MAXIMUM_INVENTORY_ITEMS: usize = 500;
 
     /// Returns the list of beacon nodes to bootstrap the node server with.
     fn beacon_nodes() -> &'static HashSet<SocketAddr> {
         static NODES: OnceCell<HashSet<SocketAddr>> = OnceCell::new();
-        NODES.get_or_init(|| Self::BEACON_NODES.iter().map(|ip| ip.parse().unwrap()).collect())
+        NODES.get_or_init(|| match &Parameters::get().beacon_nodes {
+            Some(nodes) => nodes.iter().copied().collect(),
+            None => Self::BEACON_NODES.iter().map(|ip| ip.parse().unwrap()).collect(),
+        })
     }
 
     /// Returns the list of sync nodes to bootstrap the node server with.
     fn sync_nodes() -> &'static HashSet<SocketAddr> {
         static NODES: OnceCell<HashSet<SocketAddr>> = OnceCell::new();
-        NODES.get_or_init(|| Self::SYNC_NODES.iter().map(|ip| ip.parse().unwrap()).collect())
+        NODES.get_or_init(|| match &Parameters::get().sync_nodes {
+            Some(nodes) => nodes.iter().copied().collect(),
+            None => Self::SYNC_NODES.iter().map(|ip| ip.parse().unwrap()).collect(),
+        })
+    }
+
+    ///
+    /// Returns the list of trusted peers, if the node is restricted to a private network.
+    ///
+    /// In a private network, the node only connects to and accepts connections from the trusted peers,
+    /// and does not share or request the addresses of other peers.
+    ///
+    fn trusted_peers() -> Option<&'static HashSet<SocketAddr>> {
+        static NODES: OnceCell<Option<HashSet<SocketAddr>>> = OnceCell::new();
+        NODES
+            .get_or_init(|| Parameters::get().trusted_peers.as_ref().map(|nodes| nodes.iter().copied().collect()))
+            .as_ref()
+    }
+
+    ///
+    /// Returns the optional protocol extensions supported by this node.
+    ///
+    /// The capabilities are advertised in the handshake, and an extension is only enabled
+    /// on a connection if the peer advertises it as well.
+    ///
+    fn capabilities() -> Capabilities {
+        Capabilities::COMPRESSION
+            .union(Capabilities::ENCRYPTION)
+            .union(Capabilities::HEADERS_FIRST)
+            .union(Capabilities::BLOCKS_BY_HASH)
+            .union(Capabilities::INVENTORY)
+            .union(Capabilities::MEMORY_POOL)
+    }
+
+    /// Returns `true` if the node only maintains connections with peers that negotiate an encrypted transport.
+    fn require_encryption() -> bool {
+        Parameters::get().require_encryption.unwrap_or(Self::REQUIRE_ENCRYPTION)
+    }
+
+    /// Returns the duration in seconds to sleep in between heartbeat executions.
+    fn heartbeat_in_secs() -> u64 {
+        Parameters::get().heartbeat_in_secs.unwrap_or(Self::HEARTBEAT_IN_SECS)
+    }
+
+    /// Returns the maximum duration in milliseconds permitted for establishing a connection with a node.
+    fn connection_timeout_in_millis() -> u64 {
+        Parameters::get().connection_timeout_in_millis.unwrap_or(Self::CONNECTION_TIMEOUT_IN_MILLIS)
+    }
+
+    /// Returns the duration in seconds to sleep in between ping requests with a connected peer.
+    fn ping_sleep_in_secs() -> u64 {
+        Parameters::get().ping_sleep_in_secs.unwrap_or(Self::PING_SLEEP_IN_SECS)
+    }
+
+    /// Returns the duration in seconds after which a connected peer is considered inactive or disconnected.
+    fn radio_silence_in_secs() -> u64 {
+        Parameters::get().radio_silence_in_secs.unwrap_or(Self::RADIO_SILENCE_IN_SECS)
+    }
+
+    /// Returns the duration in seconds after which the misbehavior score of a peer decays by half.
+    fn misbehavior_half_life_in_secs() -> u64 {
+        Parameters::get().misbehavior_half_life_in_secs.unwrap_or(Self::MISBEHAVIOR_HALF_LIFE_IN_SECS)
+    }
+
+    /// Returns the duration in seconds for which a peer is banned, once its misbehavior score reaches the ban threshold.
+    fn misbehavior_ban_duration_in_secs() -> u64 {
+        Parameters::get().misbehavior_ban_duration_in_secs.unwrap_or(Self::MISBEHAVIOR_BAN_DURATION_IN_SECS)
+    }
+
+    /// Returns the minimum number of peers required to maintain connections with.
+    fn minimum_number_of_peers() -> usize {
+        Parameters::get().minimum_number_of_peers.unwrap_or(Self::MINIMUM_NUMBER_OF_PEERS)
+    }
+
+    /// Returns the maximum number of peers permitted to maintain connections with.
+    fn maximum_number_of_peers() -> usize {
+        Parameters::get().maximum_number_of_peers.unwrap_or(Self::MAXIMUM_NUMBER_OF_PEERS)
+    }
+
+    ///
+    /// Returns the maximum number of outbound connections, which the node dials from diverse address groups.
+    ///
+    /// The outbound connections are reserved for the peers chosen by this node, so that peers
+    /// connecting inbound cannot monopolize the connections of the node.
+    ///
+    fn maximum_number_of_outbound_peers() -> usize {
+        Parameters::get().effective_maximum_number_of_outbound_peers::<Self>()
+    }
+
+    /// Returns the maximum number of inbound connections, which defaults to the connections that are not reserved for outbound peers.
+    fn maximum_number_of_inbound_peers() -> usize {
+        Parameters::get().effective_maximum_number_of_inbound_peers::<Self>()
+    }
+
+    /// Returns the maximum number of inbound connections permitted from one address group.
+    fn maximum_inbound_peers_per_subnet() -> usize {
+        Parameters::get().maximum_inbound_peers_per_subnet.unwrap_or(Self::MAXIMUM_INBOUND_PEERS_PER_SUBNET)
+    }
+
+    /// Returns the maximum number of connection failures permitted by an inbound connecting peer.
+    fn maximum_connection_failures() -> u32 {
+        Parameters::get().maximum_connection_failures.unwrap_or(Self::MAXIMUM_CONNECTION_FAILURES)
+    }
+
+    /// Returns the maximum number of candidate peers permitted to be stored in the node.
+    fn maximum_candidate_peers() -> usize {
+        Parameters::get().maximum_candidate_peers.unwrap_or(Self::MAXIMUM_CANDIDATE_PEERS)
+    }
+
+    /// Returns the maximum size of a message that can be transmitted in the network.
+    fn maximum_message_size() -> usize {
+        Parameters::get().maximum_message_size.unwrap_or(Self::MAXIMUM_MESSAGE_SIZE)
+    }
+
+    /// Returns the maximum number of blocks that may be fetched in one request.
+    fn maximum_block_request() -> u32 {
+        Parameters::get().maximum_block_request.unwrap_or(Self::MAXIMUM_BLOCK_REQUEST)
     }
     
     /// Returns the tasks handler for the node.
@@ -170,70 +302,3 @@ impl<N: Network> Environment for SyncNode<N> {
     const MAXIMUM_NUMBER_OF_PEERS: usize = 1024;
     const HEARTBEAT_IN_SECS: u64 = 5;
 }
-
-#[derive(Clone, Debug, Default)]
-pub struct ClientTrial<N: Network>(PhantomData<N>);
-
-#[rustfmt::skip]
-impl<N: Network> Environment for ClientTrial<N> {
-    type Network = N;
-    const NODE_TYPE: NodeType = NodeType::Client;
-    const SYNC_NODES: &'static [&'static str] = &[
-        "144.126.219.193:4132", "165.232.145.194:4132", "143.198.164.241:4132", "188.166.7.13:4132", "167.99.40.226:4132",
-        "159.223.124.150:4132", "137.184.192.155:4132", "147.182.213.228:4132", "137.184.202.162:4132", "159.223.118.35:4132",
-        "161.35.106.91:4132", "157.245.133.62:4132", "143.198.166.150:4132",
-    ];
-    const MINIMUM_NUMBER_OF_PEERS: usize = 11;
-    const MAXIMUM_NUMBER_OF_PEERS: usize = 31;
-}
-
-#[derive(Clone, Debug, Default)]
-pub struct MinerTrial<N: Network>(PhantomData<N>);
-
-#[rustfmt::skip]
-impl<N: Network> Environment for MinerTrial<N> {
-    type Network = N;
-    const NODE_TYPE: NodeType = NodeType::Miner;
-    const SYNC_NODES: &'static [&'static str] = &[
-        "144.126.219.193:4132", "165.232.145.194:4132", "143.198.164.241:4132", "188.166.7.13:4132", "167.99.40.226:4132",
-        "159.223.124.150:4132", "137.184.192.155:4132", "147.182.213.228:4132", "137.184.202.162:4132", "159.223.118.35:4132",
-        "161.35.106.91:4132", "157.245.133.62:4132", "143.198.166.150:4132",
-    ];
-    const MINIMUM_NUMBER_OF_PEERS: usize = 11;
-    const MAXIMUM_NUMBER_OF_PEERS: usize = 21;
-    const COINBASE_IS_PUBLIC: bool = true;
-}
-
-#[derive(Clone, Debug, Default)]
-pub struct OperatorTrial<N: Network>(PhantomData<N>);
-
-#[rustfmt::skip]
-impl<N: Network> Environment for OperatorTrial<N> {
-    type Network = N;
-    const NODE_TYPE: NodeType = NodeType::Operator;
-    const SYNC_NODES: &'static [&'static str] = &[
-        "144.126.219.193:4132", "165.232.145.194:4132", "143.198.164.241:4132", "188.166.7.13:4132", "167.99.40.226:4132",
-        "159.223.124.150:4132", "137.184.192.155:4132", "147.182.213.228:4132", "137.184.202.162:4132", "159.223.118.35:4132",
-        "161.35.106.91:4132", "157.245.133.62:4132", "143.198.166.150:4132",
-    ];
-    const MINIMUM_NUMBER_OF_PEERS: usize = 11;
-    const MAXIMUM_NUMBER_OF_PEERS: usize = 21;
-    const COINBASE_IS_PUBLIC: bool = true;
-}
-
-#[derive(Clone, Debug, Default)]
-pub struct ProverTrial<N: Network>(PhantomData<N>);
-
-#[rustfmt::skip]
-impl<N: Network> Environment for ProverTrial<N> {
-    type Network = N;
-    const NODE_TYPE: NodeType = NodeType::Prover;
-    const SYNC_NODES: &'static [&'static str] = &[
-        "144.126.219.193:4132", "165.232.145.194:4132", "143.198.164.241:4132", "188.166.7.13:4132", "167.99.40.226:4132",
-        "159.223.124.150:4132", "137.184.192.155:4132", "147.182.213.228:4132", "137.184.202.162:4132", "159.223.118.35:4132",
-        "161.35.106.91:4132", "157.245.133.62:4132", "143.198.166.150:4132",
-    ];
-    const MINIMUM_NUMBER_OF_PEERS: usize = 1;
-    const MAXIMUM_NUMBER_OF_PEERS: usize = 21;
-    const COINBASE_IS_PUBLIC: bool = true;
-}
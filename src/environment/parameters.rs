@@ -0,0 +1,293 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{environment::Environment, helpers::NodeType};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+/// The runtime parameters of the node, initialized once on startup.
+static PARAMETERS: OnceCell<Parameters> = OnceCell::new();
+
+/// The list of sync nodes for the incentivized testnet.
+#[rustfmt::skip]
+const TRIAL_SYNC_NODES: &[&str] = &[
+    "144.126.219.193:4132", "165.232.145.194:4132", "143.198.164.241:4132", "188.166.7.13:4132", "167.99.40.226:4132",
+    "159.223.124.150:4132", "137.184.192.155:4132", "147.182.213.228:4132", "137.184.202.162:4132", "159.223.118.35:4132",
+    "161.35.106.91:4132", "157.245.133.62:4132", "143.198.166.150:4132",
+];
+
+///
+/// The tunable parameters of an `Environment`, which may be overridden at runtime.
+/// Any parameter that is left unspecified falls back to the constant defined by the `Environment`.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Parameters {
+    /// The list of beacon nodes to bootstrap the node server with.
+    pub beacon_nodes: Option<Vec<SocketAddr>>,
+    /// The list of sync nodes to bootstrap the node server with.
+    pub sync_nodes: Option<Vec<SocketAddr>>,
+    /// The list of trusted peers, which restricts the node to a private network with only these peers.
+    pub trusted_peers: Option<Vec<SocketAddr>>,
+    /// If `true`, the node only maintains connections with peers that negotiate an encrypted transport.
+    pub require_encryption: Option<bool>,
+    /// The duration in seconds to sleep in between heartbeat executions.
+    pub heartbeat_in_secs: Option<u64>,
+    /// The maximum duration in milliseconds permitted for establishing a connection with a node.
+    pub connection_timeout_in_millis: Option<u64>,
+    /// The duration in seconds to sleep in between ping requests with a connected peer.
+    pub ping_sleep_in_secs: Option<u64>,
+    /// The duration in seconds after which a connected peer is considered inactive or disconnected.
+    pub radio_silence_in_secs: Option<u64>,
+    /// The duration in seconds after which the misbehavior score of a peer decays by half.
+    pub misbehavior_half_life_in_secs: Option<u64>,
+    /// The duration in seconds for which a peer is banned, once its misbehavior score reaches the ban threshold.
+    pub misbehavior_ban_duration_in_secs: Option<u64>,
+    /// The minimum number of peers required to maintain connections with.
+    pub minimum_number_of_peers: Option<usize>,
+    /// The maximum number of peers permitted to maintain connections with.
+    pub maximum_number_of_peers: Option<usize>,
+    /// The maximum number of outbound connections, which the node dials from diverse address groups.
+    pub maximum_number_of_outbound_peers: Option<usize>,
+    /// The maximum number of inbound connections.
+    pub maximum_number_of_inbound_peers: Option<usize>,
+    /// The maximum number of inbound connections permitted from one address group (a /16 IPv4 or /32 IPv6 subnet).
+    pub maximum_inbound_peers_per_subnet: Option<usize>,
+    /// The maximum number of connection failures permitted by an inbound connecting peer.
+    pub maximum_connection_failures: Option<u32>,
+    /// The maximum number of candidate peers permitted to be stored in the node.
+    pub maximum_candidate_peers: Option<usize>,
+    /// The maximum size of a message that can be transmitted in the network.
+    pub maximum_message_size: Option<usize>,
+    /// The maximum number of blocks that may be fetched in one request.
+    pub maximum_block_request: Option<u32>,
+}
+
+impl Parameters {
+    /// Returns the runtime parameters of the node.
+    pub fn get() -> &'static Self {
+        PARAMETERS.get_or_init(Self::default)
+    }
+
+    ///
+    /// Initializes the runtime parameters of the node, for the `Environment` of the node.
+    /// This may only be called once, before the node starts.
+    ///
+    pub fn initialize<E: Environment>(self) -> Result<()> {
+        self.validate()?;
+        self.validate_for::<E>()?;
+        PARAMETERS
+            .set(self)
+            .map_err(|_| anyhow!("The runtime parameters of the node have already been initialized"))
+    }
+
+    /// Returns the parameters for joining the incentivized testnet with the given node type.
+    pub fn trial(node_type: NodeType) -> Self {
+        let (minimum_number_of_peers, maximum_number_of_peers) = match node_type {
+            NodeType::Client => (11, 31),
+            NodeType::Miner | NodeType::Operator => (11, 21),
+            _ => (1, 21),
+        };
+
+        Self {
+            sync_nodes: Some(TRIAL_SYNC_NODES.iter().map(|ip| ip.parse().unwrap()).collect()),
+            minimum_number_of_peers: Some(minimum_number_of_peers),
+            maximum_number_of_peers: Some(maximum_number_of_peers),
+            ..Default::default()
+        }
+    }
+
+    /// Overrides the parameters in `self` with any parameter that is specified in `other`.
+    pub fn merge(&mut self, other: Self) {
+        macro_rules! merge {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field;
+                })*
+            };
+        }
+
+        merge!(
+            beacon_nodes,
+            sync_nodes,
+            trusted_peers,
+            require_encryption,
+            heartbeat_in_secs,
+            connection_timeout_in_millis,
+            ping_sleep_in_secs,
+            radio_silence_in_secs,
+            misbehavior_half_life_in_secs,
+            misbehavior_ban_duration_in_secs,
+            minimum_number_of_peers,
+            maximum_number_of_peers,
+            maximum_number_of_outbound_peers,
+            maximum_number_of_inbound_peers,
+            maximum_inbound_peers_per_subnet,
+            maximum_connection_failures,
+            maximum_candidate_peers,
+            maximum_message_size,
+            maximum_block_request
+        );
+    }
+
+    /// Returns an error if any of the specified parameters is invalid on its own.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(0) = self.heartbeat_in_secs {
+            return Err(anyhow!("'heartbeat_in_secs' must be greater than 0"));
+        }
+        if let Some(0) = self.radio_silence_in_secs {
+            return Err(anyhow!("'radio_silence_in_secs' must be greater than 0"));
+        }
+        if let Some(0) = self.misbehavior_half_life_in_secs {
+            return Err(anyhow!("'misbehavior_half_life_in_secs' must be greater than 0"));
+        }
+        if let Some(0) = self.maximum_number_of_peers {
+            return Err(anyhow!("'maximum_number_of_peers' must be greater than 0"));
+        }
+        if let Some(0) = self.maximum_inbound_peers_per_subnet {
+            return Err(anyhow!("'maximum_inbound_peers_per_subnet' must be greater than 0"));
+        }
+        if let Some(0) = self.maximum_message_size {
+            return Err(anyhow!("'maximum_message_size' must be greater than 0"));
+        }
+        // The highest bit of the frame length marker is reserved to flag compressed frames.
+        if let Some(maximum_message_size) = self.maximum_message_size {
+            if maximum_message_size >= 1 << 31 {
+                return Err(anyhow!("'maximum_message_size' must be less than 2 GiB"));
+            }
+        }
+        if let Some(0) = self.maximum_block_request {
+            return Err(anyhow!("'maximum_block_request' must be greater than 0"));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Returns an error if the parameters are inconsistent with each other, for the given `Environment`.
+    ///
+    /// Any parameter that is left unspecified is checked with the constant of the `Environment`,
+    /// so that an override is also checked against the defaults of the node type.
+    ///
+    pub fn validate_for<E: Environment>(&self) -> Result<()> {
+        let heartbeat = self.heartbeat_in_secs.unwrap_or(E::HEARTBEAT_IN_SECS);
+        let connection_timeout = self.connection_timeout_in_millis.unwrap_or(E::CONNECTION_TIMEOUT_IN_MILLIS);
+        if connection_timeout > heartbeat.saturating_mul(1000) {
+            return Err(anyhow!(
+                "'connection_timeout_in_millis' ({}ms) must be no greater than 'heartbeat_in_secs' ({}s)",
+                connection_timeout,
+                heartbeat
+            ));
+        }
+
+        let minimum = self.minimum_number_of_peers.unwrap_or(E::MINIMUM_NUMBER_OF_PEERS);
+        let maximum = self.maximum_number_of_peers.unwrap_or(E::MAXIMUM_NUMBER_OF_PEERS);
+        if minimum > maximum {
+            return Err(anyhow!(
+                "'minimum_number_of_peers' ({}) must be no greater than 'maximum_number_of_peers' ({})",
+                minimum,
+                maximum
+            ));
+        }
+        for (name, limit) in [
+            (
+                "maximum_number_of_outbound_peers",
+                self.effective_maximum_number_of_outbound_peers::<E>(),
+            ),
+            (
+                "maximum_number_of_inbound_peers",
+                self.effective_maximum_number_of_inbound_peers::<E>(),
+            ),
+        ] {
+            if limit > maximum {
+                return Err(anyhow!(
+                    "'{}' ({}) must be no greater than 'maximum_number_of_peers' ({})",
+                    name,
+                    limit,
+                    maximum
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the maximum number of outbound connections for the given `Environment`, which defaults to
+    /// `E::MAXIMUM_NUMBER_OF_OUTBOUND_PEERS`, or the minimum number of peers if greater.
+    pub(crate) fn effective_maximum_number_of_outbound_peers<E: Environment>(&self) -> usize {
+        self.maximum_number_of_outbound_peers.unwrap_or_else(|| {
+            let minimum = self.minimum_number_of_peers.unwrap_or(E::MINIMUM_NUMBER_OF_PEERS);
+            let maximum = self.maximum_number_of_peers.unwrap_or(E::MAXIMUM_NUMBER_OF_PEERS);
+            E::MAXIMUM_NUMBER_OF_OUTBOUND_PEERS.max(minimum).min(maximum)
+        })
+    }
+
+    /// Returns the maximum number of inbound connections for the given `Environment`, which defaults to
+    /// the connections that are not reserved for outbound peers.
+    pub(crate) fn effective_maximum_number_of_inbound_peers<E: Environment>(&self) -> usize {
+        self.maximum_number_of_inbound_peers.unwrap_or_else(|| {
+            let maximum = self.maximum_number_of_peers.unwrap_or(E::MAXIMUM_NUMBER_OF_PEERS);
+            maximum.saturating_sub(self.effective_maximum_number_of_outbound_peers::<E>())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::{Client, SyncNode};
+    use snarkvm::dpc::testnet2::Testnet2;
+
+    #[test]
+    fn test_validate_one_sided_overrides() {
+        let validate = |parameters: Parameters| parameters.validate_for::<Client<Testnet2>>();
+
+        // The defaults of the node type are consistent.
+        assert!(validate(Parameters::default()).is_ok());
+
+        // An override is checked against the defaults of the node type (a client permits at most 21 peers).
+        let parameters = Parameters {
+            minimum_number_of_peers: Some(50),
+            ..Default::default()
+        };
+        assert!(validate(parameters.clone()).is_err());
+        assert!(parameters.validate_for::<SyncNode<Testnet2>>().is_ok());
+        assert!(validate(Parameters {
+            maximum_number_of_peers: Some(1),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate(Parameters {
+            maximum_number_of_inbound_peers: Some(22),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate(Parameters {
+            connection_timeout_in_millis: Some(10_000),
+            ..Default::default()
+        })
+        .is_err());
+
+        // The derived limits follow the overrides, so a larger maximum with a larger minimum is consistent.
+        assert!(validate(Parameters {
+            minimum_number_of_peers: Some(50),
+            maximum_number_of_peers: Some(60),
+            ..Default::default()
+        })
+        .is_ok());
+    }
+}
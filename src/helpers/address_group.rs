@@ -0,0 +1,102 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::helpers::canonical_ip;
+
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The prefix length of the address group of an IPv4 address.
+const IPV4_GROUP_PREFIX_LENGTH: u8 = 16;
+/// The prefix length of the address group of an IPv6 address.
+const IPV6_GROUP_PREFIX_LENGTH: u8 = 32;
+
+///
+/// Returns the address group of the given IP address, which is the /16 subnet of an IPv4 address,
+/// or the /32 subnet of an IPv6 address. Peers in the same address group are likely to be operated
+/// by the same network provider. An IPv4-mapped IPv6 address is grouped with its IPv4 address.
+///
+/// Returns `None` if the IP address is not publicly routable (e.g. a loopback or private address),
+/// as such peers are operated locally, and are exempt from the subnet limits.
+///
+pub fn address_group(ip: IpAddr) -> Option<IpNet> {
+    match canonical_ip(ip) {
+        IpAddr::V4(ip) => ipv4_address_group(ip),
+        IpAddr::V6(ip) => ipv6_address_group(ip),
+    }
+}
+
+/// Returns the address group of the given IPv4 address, if it is publicly routable.
+fn ipv4_address_group(ip: Ipv4Addr) -> Option<IpNet> {
+    match ip.is_unspecified() || ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_broadcast() {
+        true => None,
+        false => Ipv4Net::new(ip, IPV4_GROUP_PREFIX_LENGTH)
+            .ok()
+            .map(|subnet| IpNet::V4(subnet.trunc())),
+    }
+}
+
+/// Returns the address group of the given IPv6 address, if it is publicly routable.
+fn ipv6_address_group(ip: Ipv6Addr) -> Option<IpNet> {
+    // Exclude the unique local (fc00::/7) and link-local (fe80::/10) addresses.
+    let first_segment = ip.segments()[0];
+    let is_unique_local = first_segment & 0xfe00 == 0xfc00;
+    let is_link_local = first_segment & 0xffc0 == 0xfe80;
+    match ip.is_unspecified() || ip.is_loopback() || is_unique_local || is_link_local {
+        true => None,
+        false => Ipv6Net::new(ip, IPV6_GROUP_PREFIX_LENGTH)
+            .ok()
+            .map(|subnet| IpNet::V6(subnet.trunc())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the address group of the given IP address, as a string.
+    fn group(ip: &str) -> Option<String> {
+        address_group(ip.parse().unwrap()).map(|subnet| subnet.to_string())
+    }
+
+    #[test]
+    fn test_address_group() {
+        assert_eq!(Some("144.126.0.0/16".to_string()), group("144.126.219.193"));
+        assert_eq!(group("144.126.219.193"), group("144.126.1.2"));
+        assert_ne!(group("144.126.219.193"), group("144.127.219.193"));
+
+        assert_eq!(Some("2001:db8::/32".to_string()), group("2001:db8:1234::1"));
+        assert_eq!(group("2001:db8:1234::1"), group("2001:db8:ffff::2"));
+
+        // An IPv4-mapped IPv6 address is grouped with its IPv4 address.
+        assert_eq!(group("144.126.219.193"), group("::ffff:144.126.219.193"));
+
+        // Addresses that are not publicly routable are exempt.
+        for ip in [
+            "127.0.0.1",
+            "0.0.0.0",
+            "10.1.2.3",
+            "192.168.1.1",
+            "169.254.0.1",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+        ] {
+            assert_eq!(None, group(ip), "{}", ip);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::helpers::{canonical_ip, canonical_subnet, peer_book::unix_timestamp, write_atomically};
+
+use anyhow::{anyhow, Result};
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::{fs, net::IpAddr, path::Path, str::FromStr};
+
+/// The version of the ban list format.
+const BAN_LIST_VERSION: u8 = 1;
+
+/// An entry in the ban list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanEntry {
+    /// The banned subnet. A single IP address is stored as a /32 (IPv4) or /128 (IPv6) subnet.
+    pub subnet: IpNet,
+    /// The UNIX timestamp in seconds at which the ban expires, or `None` if the ban is permanent.
+    pub expires_at: Option<u64>,
+}
+
+impl BanEntry {
+    /// Returns `true` if the ban is in effect at the given UNIX timestamp.
+    fn is_active(&self, now: u64) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at > now,
+            None => true,
+        }
+    }
+}
+
+///
+/// A list of IP addresses and subnets that were manually banned by the node operator,
+/// which is persisted across restarts.
+///
+/// Banned peers are refused on both inbound and outbound connections, until their ban expires or is lifted.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanList {
+    version: u8,
+    entries: Vec<BanEntry>,
+}
+
+impl Default for BanList {
+    fn default() -> Self {
+        Self {
+            version: BAN_LIST_VERSION,
+            entries: Default::default(),
+        }
+    }
+}
+
+impl BanList {
+    /// Loads the ban list from the given file, or returns an empty ban list if the file does not exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).map_err(|error| anyhow!("Failed to read the ban list ({}): {}", path.display(), error))?;
+        let mut ban_list: Self =
+            serde_json::from_str(&contents).map_err(|error| anyhow!("Invalid ban list ({}): {}", path.display(), error))?;
+
+        if ban_list.version != BAN_LIST_VERSION {
+            return Err(anyhow!("Unsupported ban list ({})", path.display()));
+        }
+        // Canonicalize the subnets, as the IP addresses of peers are canonicalized before they are checked.
+        for entry in ban_list.entries.iter_mut() {
+            entry.subnet = canonical_subnet(entry.subnet).trunc();
+        }
+        Ok(ban_list)
+    }
+
+    /// Saves the ban list to the given file, replacing any existing ban list.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_atomically(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    /// Returns the active entries in the ban list.
+    pub fn entries(&self) -> Vec<BanEntry> {
+        let now = unix_timestamp();
+        self.entries.iter().filter(|entry| entry.is_active(now)).cloned().collect()
+    }
+
+    ///
+    /// Bans the given subnet, for the given number of seconds or permanently if `None`.
+    /// If the subnet is already banned, its expiry is replaced.
+    ///
+    pub fn ban(&mut self, subnet: IpNet, duration_in_secs: Option<u64>) {
+        let subnet = canonical_subnet(subnet).trunc();
+        let expires_at = duration_in_secs.map(|duration_in_secs| unix_timestamp().saturating_add(duration_in_secs));
+
+        self.entries.retain(|entry| entry.subnet != subnet);
+        self.entries.push(BanEntry { subnet, expires_at });
+    }
+
+    /// Lifts the ban on the given subnet, and returns `true` if the subnet was banned.
+    pub fn unban(&mut self, subnet: IpNet) -> bool {
+        let subnet = canonical_subnet(subnet).trunc();
+        let num_entries = self.entries.len();
+        self.entries.retain(|entry| entry.subnet != subnet);
+        self.entries.len() != num_entries
+    }
+
+    /// Returns `true` if the given IP address is covered by an active ban.
+    pub fn is_banned(&self, ip: IpAddr) -> bool {
+        let ip = canonical_ip(ip);
+        let now = unix_timestamp();
+        self.entries.iter().any(|entry| entry.is_active(now) && entry.subnet.contains(&ip))
+    }
+
+    /// Removes the expired entries from the ban list, and returns `true` if any entry was removed.
+    pub fn prune(&mut self) -> bool {
+        let now = unix_timestamp();
+        let num_entries = self.entries.len();
+        self.entries.retain(|entry| entry.is_active(now));
+        self.entries.len() != num_entries
+    }
+}
+
+/// Parses the given IP address or CIDR subnet (e.g. `1.2.3.4` or `1.2.3.0/24`) into a subnet.
+pub fn parse_subnet(subnet: &str) -> Result<IpNet> {
+    let subnet = subnet.trim();
+    match IpNet::from_str(subnet) {
+        Ok(subnet) => Ok(canonical_subnet(subnet).trunc()),
+        Err(_) => match IpAddr::from_str(subnet) {
+            Ok(ip) => Ok(IpNet::from(canonical_ip(ip))),
+            Err(_) => Err(anyhow!("Invalid IP address or subnet '{}'", subnet)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ban_list() {
+        let mut ban_list = BanList::default();
+        ban_list.ban(parse_subnet("10.0.0.0/8").unwrap(), None);
+        ban_list.ban(parse_subnet("1.2.3.4").unwrap(), Some(60));
+        ban_list.ban(parse_subnet("2001:db8::1/32").unwrap(), None);
+
+        assert!(ban_list.is_banned("10.1.2.3".parse().unwrap()));
+        assert!(ban_list.is_banned("1.2.3.4".parse().unwrap()));
+        assert!(!ban_list.is_banned("1.2.3.5".parse().unwrap()));
+        assert!(ban_list.is_banned("2001:db8:ffff::1".parse().unwrap()));
+        assert_eq!(3, ban_list.entries().len());
+
+        // An IPv4-mapped IPv6 address is banned with its IPv4 address.
+        assert!(ban_list.is_banned("::ffff:1.2.3.4".parse().unwrap()));
+        assert_eq!(parse_subnet("1.2.3.4").unwrap(), parse_subnet("::ffff:1.2.3.4").unwrap());
+        // An IPv4-mapped IPv6 subnet is banned as its IPv4 subnet.
+        assert_eq!(parse_subnet("10.0.0.0/8").unwrap(), parse_subnet("::ffff:10.0.0.0/104").unwrap());
+        assert!(!ban_list.unban(parse_subnet("::ffff:1.2.3.0/120").unwrap()));
+
+        // Re-banning a subnet replaces its expiry.
+        ban_list.ban(parse_subnet("1.2.3.4/32").unwrap(), None);
+        assert_eq!(3, ban_list.entries().len());
+        assert!(ban_list.entries().iter().all(|entry| entry.expires_at.is_none()));
+
+        // Unbanning requires the exact subnet.
+        assert!(!ban_list.unban(parse_subnet("10.1.0.0/16").unwrap()));
+        assert!(ban_list.unban(parse_subnet("10.0.0.0/8").unwrap()));
+        assert!(!ban_list.is_banned("10.1.2.3".parse().unwrap()));
+
+        // Expired bans are not honored, and are pruned.
+        ban_list.ban(parse_subnet("5.5.5.5").unwrap(), Some(60));
+        ban_list.entries.last_mut().unwrap().expires_at = Some(unix_timestamp() - 1);
+        assert!(!ban_list.is_banned("5.5.5.5".parse().unwrap()));
+        assert!(ban_list.prune());
+        assert_eq!(2, ban_list.entries.len());
+
+        assert!(parse_subnet("1.2.3").is_err());
+        assert!(parse_subnet("1.2.3.4/33").is_err());
+    }
+
+    #[test]
+    fn test_ban_list_save_and_load() {
+        let mut ban_list = BanList::default();
+        ban_list.ban(parse_subnet("192.168.0.0/16").unwrap(), None);
+        ban_list.ban(parse_subnet("::1").unwrap(), Some(60));
+
+        let path = tempfile::tempdir().unwrap().into_path().join("bans.json");
+        assert_eq!(BanList::default(), BanList::load(&path).unwrap());
+
+        ban_list.save(&path).unwrap();
+        assert_eq!(ban_list, BanList::load(&path).unwrap());
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    cmp::min,
+    collections::{BTreeSet, HashSet},
+    net::SocketAddr,
+};
+
+///
+/// A scheduler for downloading a range of blocks in parallel from multiple peers.
+///
+/// The block heights are split into ranges of at most `range_size` blocks, and each idle peer is assigned
+/// the lowest range that it can serve, so that the downloaded blocks can be committed in order as they arrive.
+/// Ranges are only assigned up to `window_size` blocks past the latest block height of the ledger,
+/// which bounds the number of downloaded blocks that are waiting to be committed.
+///
+/// The block heights of a range that times out, or whose peer disconnects, are returned to the scheduler
+/// and re-assigned to the next idle peer, preferring the peers that have not stalled. A re-queued range
+/// that spans more than `range_size` blocks is split again as it is re-assigned.
+///
+#[derive(Clone, Debug)]
+pub struct BlockDownloadScheduler {
+    /// The maximum number of blocks that are requested from a peer at once.
+    range_size: u32,
+    /// The maximum number of blocks past the latest block height of the ledger that may be requested.
+    window_size: u32,
+    /// The last block height of the download.
+    end_block_height: u32,
+    /// The lowest block height that has not been assigned to a peer yet.
+    next_block_height: u32,
+    /// The ranges that are waiting to be re-assigned := (start_block_height, end_block_height)
+    pending_ranges: BTreeSet<(u32, u32)>,
+    /// The peers whose block requests have timed out, which are only assigned a range after the other peers.
+    stalled_peers: HashSet<SocketAddr>,
+}
+
+impl BlockDownloadScheduler {
+    /// Initializes a new scheduler for the blocks from `start_block_height` to `end_block_height` (inclusive).
+    pub fn new(start_block_height: u32, end_block_height: u32, range_size: u32, window_size: u32) -> Self {
+        Self {
+            range_size: range_size.max(1),
+            window_size: window_size.max(1),
+            end_block_height,
+            next_block_height: start_block_height,
+            pending_ranges: Default::default(),
+            stalled_peers: Default::default(),
+        }
+    }
+
+    /// Returns the last block height of the download.
+    pub fn end_block_height(&self) -> u32 {
+        self.end_block_height
+    }
+
+    /// Returns `true` if every block height of the download has been assigned to a peer.
+    pub fn is_complete(&self) -> bool {
+        self.next_block_height > self.end_block_height && self.pending_ranges.is_empty()
+    }
+
+    ///
+    /// Assigns a range of block heights to each of the given idle peers := (peer_ip, peer_block_height),
+    /// and returns the assigned ranges := (peer_ip, start_block_height, end_block_height).
+    ///
+    /// A peer is only assigned block heights up to its own block height, and up to the download window
+    /// past the given latest block height of the ledger. Ranges that were re-queued are assigned first.
+    ///
+    pub fn assign(&mut self, latest_block_height: u32, idle_peers: &[(SocketAddr, u32)]) -> Vec<(SocketAddr, u32, u32)> {
+        let window_end_block_height = latest_block_height.saturating_add(self.window_size);
+
+        // Assign ranges to the stalled peers last, so that their re-queued ranges go to a different peer.
+        let mut idle_peers = idle_peers.to_vec();
+        idle_peers.sort_by_key(|(peer_ip, _)| self.stalled_peers.contains(peer_ip));
+
+        let mut assignments = Vec::with_capacity(idle_peers.len());
+        for (peer_ip, peer_block_height) in idle_peers {
+            let maximum_block_height = min(min(peer_block_height, window_end_block_height), self.end_block_height);
+
+            // Select the lowest pending range that the peer can serve, if one exists.
+            let pending_range = self
+                .pending_ranges
+                .iter()
+                .find(|(start_block_height, _)| *start_block_height <= maximum_block_height)
+                .copied();
+
+            match pending_range {
+                Some((start_block_height, end_block_height)) => {
+                    self.pending_ranges.remove(&(start_block_height, end_block_height));
+                    // Split the range to at most `range_size` blocks, and re-queue the remainder that the peer cannot serve.
+                    let assigned_end_block_height = min(
+                        min(start_block_height.saturating_add(self.range_size - 1), maximum_block_height),
+                        end_block_height,
+                    );
+                    if end_block_height > assigned_end_block_height {
+                        self.pending_ranges.insert((assigned_end_block_height + 1, end_block_height));
+                    }
+                    assignments.push((peer_ip, start_block_height, assigned_end_block_height));
+                }
+                None => {
+                    if self.next_block_height <= maximum_block_height {
+                        let start_block_height = self.next_block_height;
+                        let end_block_height = min(start_block_height.saturating_add(self.range_size - 1), maximum_block_height);
+                        self.next_block_height = end_block_height.saturating_add(1);
+                        assignments.push((peer_ip, start_block_height, end_block_height));
+                    }
+                }
+            }
+        }
+        assignments
+    }
+
+    /// Records that the given peer has responded with a block, which clears its stalled status.
+    pub fn received(&mut self, peer_ip: SocketAddr) {
+        self.stalled_peers.remove(&peer_ip);
+    }
+
+    /// Re-queues the given block heights of the given peer, whose block requests have timed out.
+    pub fn expire(&mut self, peer_ip: SocketAddr, block_heights: impl IntoIterator<Item = u32>) {
+        self.stalled_peers.insert(peer_ip);
+        self.requeue(block_heights);
+    }
+
+    /// Re-queues the given block heights of the given peer, which has disconnected.
+    pub fn remove_peer(&mut self, peer_ip: SocketAddr, block_heights: impl IntoIterator<Item = u32>) {
+        self.stalled_peers.remove(&peer_ip);
+        self.requeue(block_heights);
+    }
+
+    /// Re-queues the given block heights, to be assigned to the next idle peers.
+    pub fn requeue(&mut self, block_heights: impl IntoIterator<Item = u32>) {
+        let block_heights = block_heights.into_iter().collect::<BTreeSet<_>>();
+
+        // Merge the block heights into contiguous ranges.
+        let mut current_range: Option<(u32, u32)> = None;
+        for block_height in block_heights {
+            current_range = match current_range {
+                Some((start_block_height, end_block_height)) if block_height == end_block_height + 1 => {
+                    Some((start_block_height, block_height))
+                }
+                Some(range) => {
+                    self.pending_ranges.insert(range);
+                    Some((block_height, block_height))
+                }
+                None => Some((block_height, block_height)),
+            };
+        }
+        if let Some(range) = current_range {
+            self.pending_ranges.insert(range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_download_scheduler() {
+        let (a, b, c): (SocketAddr, SocketAddr, SocketAddr) = (
+            "1.1.1.1:4132".parse().unwrap(),
+            "2.2.2.2:4132".parse().unwrap(),
+            "3.3.3.3:4132".parse().unwrap(),
+        );
+
+        let mut scheduler = BlockDownloadScheduler::new(1, 1000, 100, 250);
+
+        // The ranges are split across the peers, up to each peer's block height and the download window.
+        let assignments = scheduler.assign(0, &[(a, 1000), (b, 150), (c, 1000)]);
+        assert_eq!(vec![(a, 1, 100), (b, 101, 150), (c, 151, 250)], assignments);
+        assert!(scheduler.assign(0, &[(a, 1000)]).is_empty());
+
+        // The range of a stalled peer is re-assigned to another peer first.
+        scheduler.expire(c, 151..=250);
+        let assignments = scheduler.assign(100, &[(c, 1000), (a, 1000)]);
+        assert_eq!(vec![(a, 151, 250), (c, 251, 350)], assignments);
+
+        // The block heights of a disconnected peer are re-queued as contiguous ranges.
+        scheduler.remove_peer(c, vec![260, 251, 252, 300]);
+        let assignments = scheduler.assign(250, &[(a, 1000), (b, 1000), (c, 1000)]);
+        assert_eq!(vec![(a, 251, 252), (b, 260, 260), (c, 300, 300)], assignments);
+
+        // A re-queued range is split if the peer cannot serve all of it.
+        scheduler.requeue(201..=250);
+        assert_eq!(vec![(b, 201, 220)], scheduler.assign(250, &[(b, 220)]));
+        assert_eq!(vec![(a, 221, 250)], scheduler.assign(250, &[(a, 1000)]));
+
+        // The download completes once every block height has been assigned.
+        assert!(!scheduler.is_complete());
+        while !scheduler.assign(1000, &[(a, 1000), (b, 1000)]).is_empty() {}
+        assert!(scheduler.is_complete());
+        assert_eq!(1000, scheduler.end_block_height());
+    }
+
+    #[test]
+    fn test_block_download_scheduler_splits_pending_ranges() {
+        let (a, b): (SocketAddr, SocketAddr) = ("1.1.1.1:4132".parse().unwrap(), "2.2.2.2:4132".parse().unwrap());
+
+        let mut scheduler = BlockDownloadScheduler::new(1, 1000, 100, 250);
+        assert_eq!(vec![(a, 1, 100), (b, 101, 200)], scheduler.assign(0, &[(a, 1000), (b, 1000)]));
+
+        // The ranges of both peers are merged as they are re-queued, and split to the range size as they are re-assigned.
+        scheduler.remove_peer(a, 1..=100);
+        scheduler.remove_peer(b, 101..=200);
+        assert_eq!(vec![(a, 1, 100), (b, 101, 200)], scheduler.assign(0, &[(a, 1000), (b, 1000)]));
+        assert_eq!(vec![(a, 201, 250)], scheduler.assign(0, &[(a, 1000)]));
+    }
+}
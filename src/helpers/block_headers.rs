@@ -0,0 +1,295 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm::dpc::{BlockHeader, Blocks, Network};
+
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+
+///
+/// A chain of block headers that is validated before its blocks are downloaded, for headers-first sync.
+///
+/// Each block header is checked against its predecessor for its block height, timestamp, difficulty target,
+/// cumulative weight, and proof of succinct work, as the ledger does when adding a block. Only the header root
+/// of each validated block header is retained, so that the downloaded blocks can be matched against it.
+///
+/// A block header does not commit to the hash of its previous block, so the chain cannot check that the block
+/// headers are linked. Instead, each downloaded block is linked to its previous block as the ledger adds it,
+/// and a block that does not extend the ledger is rejected, along with the rest of the download.
+///
+#[derive(Clone, Debug)]
+pub struct BlockHeaderChain<N: Network> {
+    /// The latest validated block header.
+    latest_block_header: BlockHeader<N>,
+    /// The block header that anchors the difficulty target after the testnet2 V12 upgrade, if it is known.
+    anchor_block_header: Option<BlockHeader<N>>,
+    /// The map of each validated block height to its header root.
+    header_roots: BTreeMap<u32, N::BlockHeaderRoot>,
+}
+
+impl<N: Network> BlockHeaderChain<N> {
+    /// Initializes a new chain of block headers, extending the given block header of the ledger.
+    pub fn new(previous_block_header: BlockHeader<N>, anchor_block_header: Option<BlockHeader<N>>) -> Self {
+        Self {
+            latest_block_header: previous_block_header,
+            anchor_block_header,
+            header_roots: Default::default(),
+        }
+    }
+
+    /// Returns the latest block height of the chain.
+    pub fn latest_block_height(&self) -> u32 {
+        self.latest_block_header.height()
+    }
+
+    /// Returns the latest cumulative weight of the chain.
+    pub fn latest_cumulative_weight(&self) -> u128 {
+        self.latest_block_header.cumulative_weight()
+    }
+
+    /// Returns the header root of the validated block header at the given block height, if it exists.
+    pub fn get_header_root(&self, block_height: u32) -> Option<N::BlockHeaderRoot> {
+        self.header_roots.get(&block_height).copied()
+    }
+
+    /// Returns the map of each validated block height to its header root.
+    pub fn into_header_roots(self) -> BTreeMap<u32, N::BlockHeaderRoot> {
+        self.header_roots
+    }
+
+    ///
+    /// Validates the given block headers in order, and appends them to the chain.
+    /// If a block header is invalid, returns an error, and the chain retains the block headers before it.
+    ///
+    pub fn extend(&mut self, block_headers: Vec<BlockHeader<N>>) -> Result<()> {
+        for block_header in block_headers {
+            self.check_next_block_header(&block_header)?;
+
+            let header_root = block_header.to_header_root()?;
+            self.header_roots.insert(block_header.height(), header_root);
+
+            // Retain the block header at the upgrade height, as it anchors the subsequent difficulty targets.
+            if N::NETWORK_ID == 2 && block_header.height() == snarkvm::dpc::testnet2::V12_UPGRADE_BLOCK_HEIGHT {
+                self.anchor_block_header = Some(block_header.clone());
+            }
+            self.latest_block_header = block_header;
+        }
+        Ok(())
+    }
+
+    /// Checks that the given block header is a valid successor of the latest block header.
+    fn check_next_block_header(&self, block_header: &BlockHeader<N>) -> Result<()> {
+        let previous_block_header = &self.latest_block_header;
+        let block_height = block_header.height();
+
+        // Ensure the block height increments by one.
+        if block_height != previous_block_header.height() + 1 {
+            return Err(anyhow!(
+                "Block header {} should have block height {}",
+                block_height,
+                previous_block_header.height() + 1
+            ));
+        }
+
+        // Ensure the block header itself is valid, including its proof of succinct work.
+        if !block_header.is_valid() {
+            return Err(anyhow!("Block header {} is invalid", block_height));
+        }
+
+        // Ensure the block timestamp is within the declared time limit.
+        let now = chrono::Utc::now().timestamp();
+        if block_header.timestamp() > (now + N::ALEO_FUTURE_TIME_LIMIT_IN_SECS) {
+            return Err(anyhow!("Block header {} has a timestamp that exceeds the time limit", block_height));
+        }
+
+        // Ensure the block timestamp is after the previous block timestamp.
+        if block_header.timestamp() <= previous_block_header.timestamp() {
+            return Err(anyhow!(
+                "Block header {} has a timestamp before the previous timestamp",
+                block_height
+            ));
+        }
+
+        // Compute the expected difficulty target.
+        let expected_difficulty_target = if N::NETWORK_ID == 2 && block_height <= snarkvm::dpc::testnet2::V12_UPGRADE_BLOCK_HEIGHT {
+            Blocks::<N>::compute_difficulty_target(previous_block_header, block_header.timestamp(), block_height)
+        } else if N::NETWORK_ID == 2 {
+            match &self.anchor_block_header {
+                Some(anchor_block_header) => {
+                    Blocks::<N>::compute_difficulty_target(anchor_block_header, block_header.timestamp(), block_height)
+                }
+                None => return Err(anyhow!("Missing the anchor block header for block header {}", block_height)),
+            }
+        } else {
+            Blocks::<N>::compute_difficulty_target(N::genesis_block().header(), block_header.timestamp(), block_height)
+        };
+
+        // Ensure the expected difficulty target is met.
+        if block_header.difficulty_target() != expected_difficulty_target {
+            return Err(anyhow!(
+                "Block header {} has an incorrect difficulty target. Found {}, but expected {}",
+                block_height,
+                block_header.difficulty_target(),
+                expected_difficulty_target
+            ));
+        }
+
+        // Ensure the expected cumulative weight is computed correctly.
+        let expected_cumulative_weight = previous_block_header
+            .cumulative_weight()
+            .saturating_add((u64::MAX / expected_difficulty_target) as u128);
+        if block_header.cumulative_weight() != expected_cumulative_weight {
+            return Err(anyhow!(
+                "Block header {} has an incorrect cumulative weight. Found {}, but expected {}",
+                block_height,
+                block_header.cumulative_weight(),
+                expected_cumulative_weight
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkos_storage::{storage::rocksdb::RocksDB, LedgerState};
+    use snarkvm::dpc::{prelude::*, testnet2::Testnet2};
+
+    use rand::thread_rng;
+    use std::sync::atomic::AtomicBool;
+
+    /// Returns the template for the block after the genesis block.
+    fn next_block_template() -> BlockTemplate<Testnet2> {
+        let path = tempfile::tempdir().expect("Failed to open temporary directory").into_path();
+        let ledger = LedgerState::<Testnet2>::open_writer::<RocksDB, _>(path).expect("Failed to initialize ledger");
+        let address = Account::<Testnet2>::new(&mut thread_rng()).address();
+        ledger
+            .get_block_template(address, true, &[], &mut thread_rng())
+            .expect("Failed to get block template")
+    }
+
+    /// Returns a copy of the given block template, with the given timestamp, difficulty target, and cumulative weight.
+    fn modify_block_template(
+        template: &BlockTemplate<Testnet2>,
+        block_timestamp: i64,
+        difficulty_target: u64,
+        cumulative_weight: u128,
+    ) -> BlockTemplate<Testnet2> {
+        BlockTemplate::new(
+            template.previous_block_hash(),
+            template.block_height(),
+            block_timestamp,
+            difficulty_target,
+            cumulative_weight,
+            template.previous_ledger_root(),
+            template.transactions().clone(),
+            template.coinbase_record().clone(),
+        )
+    }
+
+    /// Mines a block header for the given block template, which meets the difficulty target of the template.
+    fn mine_block_header(template: &BlockTemplate<Testnet2>) -> BlockHeader<Testnet2> {
+        let terminator = AtomicBool::new(false);
+        loop {
+            let block_header = BlockHeader::mine_once_unchecked(template, &terminator, &mut thread_rng()).expect("Failed to mine");
+            if block_header.is_valid() {
+                return block_header;
+            }
+        }
+    }
+
+    /// Returns the error of extending a new chain from the genesis block header with the given block header.
+    fn extend_error(block_header: BlockHeader<Testnet2>) -> String {
+        let mut chain = BlockHeaderChain::<Testnet2>::new(Testnet2::genesis_block().header().clone(), None);
+        let error = chain.extend(vec![block_header]).unwrap_err().to_string();
+        assert_eq!(0, chain.latest_block_height());
+        error
+    }
+
+    #[test]
+    fn test_block_header_chain_accepts_valid_headers() {
+        let block_header = mine_block_header(&next_block_template());
+
+        let mut chain = BlockHeaderChain::<Testnet2>::new(Testnet2::genesis_block().header().clone(), None);
+        chain.extend(vec![block_header.clone()]).unwrap();
+        assert_eq!(1, chain.latest_block_height());
+        assert_eq!(block_header.cumulative_weight(), chain.latest_cumulative_weight());
+        assert_eq!(Some(block_header.to_header_root().unwrap()), chain.get_header_root(1));
+    }
+
+    #[test]
+    fn test_block_header_chain_rejects_invalid_difficulty_target() {
+        let template = next_block_template();
+        let template = modify_block_template(&template, template.block_timestamp(), u64::MAX, template.cumulative_weight());
+        assert!(extend_error(mine_block_header(&template)).contains("incorrect difficulty target"));
+    }
+
+    #[test]
+    fn test_block_header_chain_rejects_invalid_timestamp() {
+        let template = next_block_template();
+        let cumulative_weight = template.cumulative_weight();
+
+        // A block timestamp beyond the time limit is rejected.
+        let future_timestamp = chrono::Utc::now().timestamp() + Testnet2::ALEO_FUTURE_TIME_LIMIT_IN_SECS + 3600;
+        let future_template = modify_block_template(&template, future_timestamp, u64::MAX, cumulative_weight);
+        assert!(extend_error(mine_block_header(&future_template)).contains("exceeds the time limit"));
+
+        // A block timestamp that does not follow the previous block timestamp is rejected.
+        let genesis_timestamp = Testnet2::genesis_block().timestamp();
+        let past_template = modify_block_template(&template, genesis_timestamp, u64::MAX, cumulative_weight);
+        assert!(extend_error(mine_block_header(&past_template)).contains("before the previous timestamp"));
+    }
+
+    #[test]
+    fn test_block_header_chain_rejects_invalid_cumulative_weight() {
+        let template = next_block_template();
+        let template = modify_block_template(
+            &template,
+            template.block_timestamp(),
+            template.difficulty_target(),
+            template.cumulative_weight() + 1,
+        );
+        assert!(extend_error(mine_block_header(&template)).contains("incorrect cumulative weight"));
+    }
+
+    #[test]
+    fn test_block_header_chain_rejects_invalid_proof_of_work() {
+        let template = next_block_template();
+        // Mine a block header whose proof does not meet its difficulty target.
+        let template = modify_block_template(&template, template.block_timestamp(), 1, template.cumulative_weight());
+        let block_header = BlockHeader::mine_once_unchecked(&template, &AtomicBool::new(false), &mut thread_rng()).expect("Failed to mine");
+        assert!(!block_header.is_valid());
+        assert!(extend_error(block_header).contains("is invalid"));
+    }
+
+    #[test]
+    fn test_block_header_chain_rejects_invalid_headers() {
+        let genesis_header = Testnet2::genesis_block().header().clone();
+        let mut chain = BlockHeaderChain::<Testnet2>::new(genesis_header.clone(), None);
+
+        // A block header that does not extend the chain is rejected.
+        assert!(chain.extend(vec![genesis_header]).is_err());
+        assert_eq!(0, chain.latest_block_height());
+        assert_eq!(Testnet2::genesis_block().cumulative_weight(), chain.latest_cumulative_weight());
+        assert!(chain.get_header_root(0).is_none());
+
+        // An empty response leaves the chain unchanged.
+        chain.extend(vec![]).unwrap();
+        assert!(chain.into_header_roots().is_empty());
+    }
+}
@@ -18,12 +18,17 @@ use crate::{network::ledger::PeersState, Environment};
 use snarkos_storage::{BlockLocators, LedgerState};
 use snarkvm::dpc::prelude::*;
 
-use std::net::SocketAddr;
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
+
+/// The maximum number of block requests that can be downloaded ahead of the ledger, while syncing from multiple peers.
+pub const MAXIMUM_BLOCK_DOWNLOAD_RANGES: u32 = 8;
 
 /// Checks if any of the peers are ahead and have a larger block height, if they are on a fork, and their block locators.
 /// The maximum known block height and cumulative weight are tracked for the purposes of further operations.
+/// Among peers with the same cumulative weight, the peer with the lowest round-trip time is selected.
 pub fn find_maximal_peer<N: Network, E: Environment>(
     peers_state: &PeersState<N>,
+    peers_rtt: &HashMap<SocketAddr, Duration>,
     maximum_block_height: &mut u32,
     maximum_cumulative_weight: &mut u128,
 ) -> Option<(SocketAddr, bool, BlockLocators<N>)> {
@@ -34,7 +39,9 @@ pub fn find_maximal_peer<N: Network, E: Environment>(
     //     peers_contains_sync_node |= sync_nodes.contains(ip);
     // }
 
-    let mut maximal_peer = None;
+    let mut maximal_peer: Option<(SocketAddr, bool, BlockLocators<N>)> = None;
+    // Peers that have not been measured yet are ranked behind every measured peer.
+    let rtt = |peer_ip: &SocketAddr| peers_rtt.get(peer_ip).copied().unwrap_or(Duration::MAX);
 
     for (peer_ip, peer_state) in peers_state.iter() {
         // Only update the maximal peer if there are no sync nodes or the peer is a sync node.
@@ -47,8 +54,13 @@ pub fn find_maximal_peer<N: Network, E: Environment>(
                     Some(cumulative_weight) => cumulative_weight,
                     None => *block_height as u128,
                 };
-                // If the cumulative weight is more, set this peer as the maximal peer.
-                if cumulative_weight > *maximum_cumulative_weight && is_on_fork.is_some() {
+                // If the cumulative weight is more, or the same as the maximal peer with a lower round-trip time,
+                // set this peer as the maximal peer.
+                let is_faster = match &maximal_peer {
+                    Some((maximal_peer_ip, _, _)) => cumulative_weight == *maximum_cumulative_weight && rtt(peer_ip) < rtt(maximal_peer_ip),
+                    None => false,
+                };
+                if (cumulative_weight > *maximum_cumulative_weight || is_faster) && is_on_fork.is_some() {
                     maximal_peer = Some((*peer_ip, is_on_fork.unwrap(), block_locators.clone()));
                     *maximum_block_height = *block_height;
                     *maximum_cumulative_weight = cumulative_weight;
@@ -96,6 +108,17 @@ pub fn find_common_ancestor<N: Network>(canon: &LedgerState<N>, block_locators:
     Ok((maximum_common_ancestor, first_deviating_locator))
 }
 
+/// Returns `true` if the given block locators agree on the block hash at every block height they share,
+/// which indicates that both peers advertise the same canonical chain.
+pub fn is_same_canonical_chain<N: Network>(block_locators: &BlockLocators<N>, other_block_locators: &BlockLocators<N>) -> bool {
+    block_locators.iter().all(
+        |(block_height, (block_hash, _))| match other_block_locators.get_block_hash(*block_height) {
+            Some(other_block_hash) => *block_hash == other_block_hash,
+            None => true,
+        },
+    )
+}
+
 /// A case annotation enum for the block request handler.
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) enum Case {
@@ -228,8 +251,12 @@ pub(crate) fn handle_block_requests<N: Network, E: Environment>(
         };
 
     // TODO (howardwu): Ensure the start <= end.
-    // Determine the start and end block heights to request.
-    let number_of_block_requests = std::cmp::min(maximum_block_height - latest_common_ancestor, E::MAXIMUM_BLOCK_REQUEST);
+    // Determine the start and end block heights to download, which are split into block requests by the download scheduler.
+    // The download is bounded to the blocks that the scheduler may request at once, and the next download continues from its end.
+    let number_of_block_requests = std::cmp::min(
+        maximum_block_height - latest_common_ancestor,
+        E::maximum_block_request().saturating_mul(MAXIMUM_BLOCK_DOWNLOAD_RANGES),
+    );
     let start_block_height = latest_common_ancestor + 1;
     let end_block_height = start_block_height + number_of_block_requests - 1;
 
@@ -243,7 +270,10 @@ pub(crate) fn handle_block_requests<N: Network, E: Environment>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Client;
+    use crate::{
+        helpers::{NodeType, State},
+        Client,
+    };
     use snarkvm::dpc::testnet2::Testnet2;
 
     use rand::{thread_rng, Rng};
@@ -401,7 +431,7 @@ mod tests {
 
             let expected_number_of_block_requests = std::cmp::min(
                 peer_maximum_block_height - latest_block_height,
-                Client::<Testnet2>::MAXIMUM_BLOCK_REQUEST,
+                Client::<Testnet2>::MAXIMUM_BLOCK_REQUEST * MAXIMUM_BLOCK_DOWNLOAD_RANGES,
             );
             let expected_start_block_height = latest_block_height + 1;
             let expected_end_block_height = expected_start_block_height + expected_number_of_block_requests - 1;
@@ -458,7 +488,7 @@ mod tests {
 
             let expected_number_of_block_requests = std::cmp::min(
                 peer_maximum_block_height - maximum_common_ancestor,
-                Client::<Testnet2>::MAXIMUM_BLOCK_REQUEST,
+                Client::<Testnet2>::MAXIMUM_BLOCK_REQUEST * MAXIMUM_BLOCK_DOWNLOAD_RANGES,
             );
             let expected_start_block_height = maximum_common_ancestor + 1;
             let expected_end_block_height = expected_start_block_height + expected_number_of_block_requests - 1;
@@ -563,7 +593,7 @@ mod tests {
 
             let expected_number_of_block_requests = std::cmp::min(
                 peer_maximum_block_height - maximum_common_ancestor,
-                Client::<Testnet2>::MAXIMUM_BLOCK_REQUEST,
+                Client::<Testnet2>::MAXIMUM_BLOCK_REQUEST * MAXIMUM_BLOCK_DOWNLOAD_RANGES,
             );
             let expected_start_block_height = maximum_common_ancestor + 1;
             let expected_end_block_height = expected_start_block_height + expected_number_of_block_requests - 1;
@@ -622,4 +652,54 @@ mod tests {
             assert_eq!(result, BlockRequestHandler::Abort(Case::TwoCC));
         }
     }
+
+    #[test]
+    fn test_find_maximal_peer_prefers_lower_rtt() {
+        let (a, b, c): (SocketAddr, SocketAddr, SocketAddr) = (
+            "1.1.1.1:4132".parse().unwrap(),
+            "2.2.2.2:4132".parse().unwrap(),
+            "3.3.3.3:4132".parse().unwrap(),
+        );
+
+        // Declare three peers at the same block height, where the cumulative weight defaults to the block height.
+        let peer_state = |block_height: u32| {
+            Some((
+                NodeType::Client,
+                State::Ready,
+                Some(false),
+                block_height,
+                BlockLocators::from(Default::default()),
+            ))
+        };
+        let mut peers_state: PeersState<Testnet2> = PeersState::default();
+        peers_state.insert(a, peer_state(100));
+        peers_state.insert(b, peer_state(100));
+        peers_state.insert(c, peer_state(100));
+
+        let mut peers_rtt = HashMap::new();
+        peers_rtt.insert(a, Duration::from_millis(80));
+        peers_rtt.insert(b, Duration::from_millis(20));
+
+        // Among peers with the same cumulative weight, the peer with the lowest round-trip time is selected.
+        let (mut maximum_block_height, mut maximum_cumulative_weight) = (0, 0);
+        let maximal_peer = find_maximal_peer::<Testnet2, Client<Testnet2>>(
+            &peers_state,
+            &peers_rtt,
+            &mut maximum_block_height,
+            &mut maximum_cumulative_weight,
+        );
+        assert_eq!(Some(b), maximal_peer.map(|(peer_ip, _, _)| peer_ip));
+        assert_eq!((100, 100), (maximum_block_height, maximum_cumulative_weight));
+
+        // A heavier chain is always preferred, regardless of the round-trip time.
+        peers_state.insert(c, peer_state(101));
+        let (mut maximum_block_height, mut maximum_cumulative_weight) = (0, 0);
+        let maximal_peer = find_maximal_peer::<Testnet2, Client<Testnet2>>(
+            &peers_state,
+            &peers_rtt,
+            &mut maximum_block_height,
+            &mut maximum_cumulative_weight,
+        );
+        assert_eq!(Some(c), maximal_peer.map(|(peer_ip, _, _)| peer_ip));
+    }
 }
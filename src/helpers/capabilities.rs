@@ -0,0 +1,111 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+///
+/// A bitfield of the optional protocol extensions supported by a node.
+///
+/// Capabilities are exchanged in the handshake, and an extension is only enabled on a connection
+/// if both sides support it. Each extension reserves one bit, which must never be reassigned,
+/// so that new wire features may be rolled out without incrementing the message version.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    /// The node serves blocks by the hash of their ancestor, and responds to unavailable blocks explicitly.
+    pub const BLOCKS_BY_HASH: Self = Self(1 << 3);
+    /// The node supports deflate compression of block and locator messages.
+    pub const COMPRESSION: Self = Self(1 << 0);
+    /// The node supports upgrading the connection to an encrypted and authenticated Noise transport.
+    pub const ENCRYPTION: Self = Self(1 << 1);
+    /// The node serves block headers, for headers-first sync.
+    pub const HEADERS_FIRST: Self = Self(1 << 2);
+    /// The node announces blocks and transactions by their hash or ID, and serves them on request.
+    pub const INVENTORY: Self = Self(1 << 4);
+    /// The node serves the transaction IDs of its memory pool, for memory pool sync on connect.
+    pub const MEMORY_POOL: Self = Self(1 << 5);
+
+    /// Returns a set of capabilities with no extensions.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns a set of capabilities from the given bits, retaining any unknown bits.
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the bits of the capabilities.
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns `true` if no extensions are set.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all of the given capabilities are set.
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the union of the given capabilities.
+    pub const fn union(&self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Returns the capabilities that are supported by both sides.
+    pub const fn intersection(&self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_negotiation() {
+        let (a, b, c) = (Capabilities::from_bits(1), Capabilities::from_bits(2), Capabilities::from_bits(4));
+
+        let local = a.union(b);
+        let peer = b.union(c).union(Capabilities::from_bits(1 << 63));
+        let negotiated = local.intersection(peer);
+
+        assert_eq!(b, negotiated);
+        assert!(negotiated.contains(b));
+        assert!(!negotiated.contains(a));
+        assert!(!negotiated.contains(c));
+        assert!(negotiated.contains(Capabilities::empty()));
+        assert!(local.intersection(Capabilities::empty()).is_empty());
+
+        // Capabilities are serialized as a plain integer.
+        let bytes = bincode::serialize(&peer).unwrap();
+        assert_eq!(peer.bits().to_le_bytes().to_vec(), bytes);
+        assert_eq!(peer, bincode::deserialize::<Capabilities>(&bytes).unwrap());
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+use std::{fs, io::Write, path::Path};
+
+///
+/// Writes the given bytes to the given file, replacing any existing file, and creates its parent directories.
+///
+/// The bytes are written to a temporary file first, which is then renamed over the given file,
+/// so that an interrupted write does not leave a partially written file behind.
+///
+pub fn write_atomically<P: AsRef<Path>>(path: P, bytes: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, bytes)?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+///
+/// Writes the given bytes to a new file at the given path, which is only readable by its owner.
+/// Returns an error if the file already exists, so that an existing secret is never overwritten.
+///
+pub fn write_private_file<P: AsRef<Path>>(path: P, bytes: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomically() {
+        let path = tempfile::tempdir().unwrap().into_path().join("directory").join("file.json");
+
+        write_atomically(&path, b"first").unwrap();
+        assert_eq!(b"first".to_vec(), fs::read(&path).unwrap());
+
+        // An existing file is replaced, and the temporary file is not left behind.
+        write_atomically(&path, b"second").unwrap();
+        assert_eq!(b"second".to_vec(), fs::read(&path).unwrap());
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn test_write_private_file() {
+        let path = tempfile::tempdir().unwrap().into_path().join("secret.json");
+
+        write_private_file(&path, b"secret").unwrap();
+        assert_eq!(b"secret".to_vec(), fs::read(&path).unwrap());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+        }
+
+        // An existing file is never overwritten.
+        assert!(write_private_file(&path, b"other").is_err());
+        assert_eq!(b"secret".to_vec(), fs::read(&path).unwrap());
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use ipnet::{IpNet, Ipv4Net};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+///
+/// Returns the canonical form of the given IP address, which is the IPv4 address of an
+/// IPv4-mapped IPv6 address (`::ffff:a.b.c.d`), and otherwise the given IP address.
+///
+/// A dual-stack listener reports IPv4 peers by their IPv4-mapped IPv6 address, so the IP address
+/// of a peer is canonicalized before it is compared, deduplicated, restricted, or banned.
+///
+pub fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ipv6) => match ipv6.octets() {
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => IpAddr::V4(Ipv4Addr::new(a, b, c, d)),
+            _ => ip,
+        },
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// Returns the given socket address, with its IP address in canonical form.
+pub fn canonical_socket_addr(addr: SocketAddr) -> SocketAddr {
+    SocketAddr::new(canonical_ip(addr.ip()), addr.port())
+}
+
+///
+/// Returns the canonical form of the given subnet, which is the IPv4 subnet of an IPv4-mapped
+/// IPv6 subnet (`::ffff:a.b.c.d/n`, with `n` of at least 96), and otherwise the given subnet.
+///
+pub fn canonical_subnet(subnet: IpNet) -> IpNet {
+    match (subnet, canonical_ip(subnet.addr())) {
+        (IpNet::V6(ipv6_subnet), IpAddr::V4(ipv4)) if ipv6_subnet.prefix_len() >= 96 => {
+            match Ipv4Net::new(ipv4, ipv6_subnet.prefix_len() - 96) {
+                Ok(ipv4_subnet) => IpNet::V4(ipv4_subnet),
+                Err(_) => subnet,
+            }
+        }
+        _ => subnet,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_socket_addr() {
+        let canonical = |addr: &str| canonical_socket_addr(addr.parse().unwrap());
+        let addr = |addr: &str| addr.parse::<SocketAddr>().unwrap();
+
+        // An IPv4-mapped IPv6 address is canonicalized to its IPv4 address.
+        assert_eq!(addr("1.2.3.4:4132"), canonical("[::ffff:1.2.3.4]:4132"));
+        assert_eq!(addr("127.0.0.1:4132"), canonical("[::ffff:127.0.0.1]:4132"));
+
+        // Any other address is unchanged, including the deprecated IPv4-compatible addresses.
+        for other in ["1.2.3.4:4132", "[2001:db8::1]:4132", "[::1]:4132", "[::1.2.3.4]:4132"] {
+            assert_eq!(addr(other), canonical(other));
+        }
+    }
+
+    #[test]
+    fn test_canonical_subnet() {
+        let canonical = |subnet: &str| canonical_subnet(subnet.parse().unwrap());
+        let subnet = |subnet: &str| subnet.parse::<IpNet>().unwrap();
+
+        // An IPv4-mapped IPv6 subnet is canonicalized to its IPv4 subnet.
+        assert_eq!(subnet("1.2.3.4/32"), canonical("::ffff:1.2.3.4/128"));
+        assert_eq!(subnet("1.2.0.0/16"), canonical("::ffff:1.2.0.0/112"));
+        assert_eq!(subnet("0.0.0.0/0"), canonical("::ffff:0.0.0.0/96"));
+
+        // Any other subnet is unchanged, including a subnet that is wider than the IPv4-mapped range.
+        for other in ["1.2.0.0/16", "2001:db8::/32", "::ffff:0.0.0.0/95", "::/0"] {
+            assert_eq!(subnet(other), canonical(other));
+        }
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::helpers::write_private_file;
+use snarkvm::dpc::{Address, Network, PrivateKey};
+
+use anyhow::{anyhow, Result};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    ChaCha20Poly1305,
+    Key,
+    Nonce,
+};
+use hmac::Hmac;
+use rand::{CryptoRng, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{fs, path::Path, str::FromStr};
+
+/// The version of the keystore format.
+const KEYSTORE_VERSION: u8 = 1;
+/// The key derivation function of the keystore.
+const KDF: &str = "pbkdf2-hmac-sha256";
+/// The number of key derivation iterations for a new keystore.
+const KDF_ITERATIONS: u32 = 262_144;
+/// The minimum number of key derivation iterations of a keystore file.
+const MINIMUM_KDF_ITERATIONS: u32 = 100_000;
+/// The maximum number of key derivation iterations of a keystore file.
+const MAXIMUM_KDF_ITERATIONS: u32 = 10_000_000;
+/// The cipher of the keystore.
+const CIPHER: &str = "chacha20-poly1305";
+/// The size of the cipher nonce, in bytes.
+const NONCE_SIZE: usize = 12;
+
+///
+/// A password-encrypted keystore for an account private key.
+///
+/// The password is stretched with PBKDF2-HMAC-SHA256 into an encryption key, and the private key
+/// is encrypted with ChaCha20-Poly1305, with the address of the account as associated data.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    version: u8,
+    address: String,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Keystore {
+    /// Encrypts the given private key with the given password into a new keystore.
+    pub fn new<N: Network, R: Rng + CryptoRng>(private_key: &PrivateKey<N>, password: &str, rng: &mut R) -> Result<Self> {
+        Self::new_with_iterations(private_key, password, KDF_ITERATIONS, rng)
+    }
+
+    /// Encrypts the given private key with the given password and number of key derivation iterations.
+    fn new_with_iterations<N: Network, R: Rng + CryptoRng>(
+        private_key: &PrivateKey<N>,
+        password: &str,
+        iterations: u32,
+        rng: &mut R,
+    ) -> Result<Self> {
+        if password.is_empty() {
+            return Err(anyhow!("The keystore password must not be empty"));
+        }
+
+        let salt: [u8; 32] = rng.gen();
+        let nonce: [u8; NONCE_SIZE] = rng.gen();
+        let address = Address::from_private_key(private_key).to_string();
+
+        let payload = Payload {
+            msg: private_key.to_string().as_bytes(),
+            aad: address.as_bytes(),
+        };
+        let ciphertext = derive_cipher(password, &salt, iterations)
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| anyhow!("Failed to encrypt the private key"))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            address,
+            kdf: KDF.to_string(),
+            iterations,
+            salt: hex::encode(salt),
+            cipher: CIPHER.to_string(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Loads a keystore from the given file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|error| anyhow!("Failed to read the keystore ({}): {}", path.display(), error))?;
+        let keystore: Self =
+            serde_json::from_str(&contents).map_err(|error| anyhow!("Invalid keystore ({}): {}", path.display(), error))?;
+
+        if keystore.version != KEYSTORE_VERSION || keystore.kdf != KDF || keystore.cipher != CIPHER {
+            return Err(anyhow!("Unsupported keystore ({})", path.display()));
+        }
+        // Ensure the work factor is bounded, so that a crafted keystore can neither weaken nor stall the key derivation.
+        if !(MINIMUM_KDF_ITERATIONS..=MAXIMUM_KDF_ITERATIONS).contains(&keystore.iterations) {
+            return Err(anyhow!(
+                "Invalid keystore ({}): the number of key derivation iterations must be between {} and {}",
+                path.display(),
+                MINIMUM_KDF_ITERATIONS,
+                MAXIMUM_KDF_ITERATIONS
+            ));
+        }
+        Ok(keystore)
+    }
+
+    /// Saves the keystore to the given file, which must not already exist.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        // Ensure the keystore is only readable by its owner.
+        write_private_file(path, serde_json::to_string_pretty(self)?.as_bytes())
+            .map_err(|error| anyhow!("Failed to create the keystore ({}): {}", path.display(), error))
+    }
+
+    /// Returns the address of the account in the keystore, without decrypting it.
+    pub fn address<N: Network>(&self) -> Result<Address<N>> {
+        Address::from_str(&self.address).map_err(|_| anyhow!("The keystore contains an invalid address"))
+    }
+
+    /// Decrypts the private key in the keystore with the given password.
+    pub fn decrypt<N: Network>(&self, password: &str) -> Result<PrivateKey<N>> {
+        let salt = hex::decode(&self.salt)?;
+        let nonce = hex::decode(&self.nonce)?;
+        let ciphertext = hex::decode(&self.ciphertext)?;
+        if nonce.len() != NONCE_SIZE {
+            return Err(anyhow!("The keystore contains an invalid nonce"));
+        }
+
+        // Decrypt the ciphertext, which fails if the password is incorrect or the keystore was tampered with.
+        let payload = Payload {
+            msg: &ciphertext,
+            aad: self.address.as_bytes(),
+        };
+        let plaintext = derive_cipher(password, &salt, self.iterations)
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| anyhow!("Incorrect keystore password"))?;
+
+        let private_key = PrivateKey::<N>::from_str(std::str::from_utf8(&plaintext)?)
+            .map_err(|_| anyhow!("The keystore contains an invalid private key"))?;
+
+        // Ensure the private key corresponds to the address of the keystore.
+        if Address::from_private_key(&private_key).to_string() != self.address {
+            return Err(anyhow!("The keystore private key does not match its address"));
+        }
+        Ok(private_key)
+    }
+}
+
+/// Derives the cipher of a keystore from the given password, using PBKDF2-HMAC-SHA256.
+fn derive_cipher(password: &str, salt: &[u8], iterations: u32) -> ChaCha20Poly1305 {
+    let mut key = Key::default();
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, iterations, &mut key);
+    ChaCha20Poly1305::new(&key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::dpc::{testnet2::Testnet2, Account};
+
+    use rand::thread_rng;
+
+    #[test]
+    fn test_keystore_decrypt() {
+        let account = Account::<Testnet2>::new(&mut thread_rng());
+        let keystore = Keystore::new_with_iterations(account.private_key(), "password", 16, &mut thread_rng()).unwrap();
+
+        assert_eq!(account.address(), keystore.address::<Testnet2>().unwrap());
+        assert_eq!(account.private_key(), &keystore.decrypt::<Testnet2>("password").unwrap());
+        assert!(keystore.decrypt::<Testnet2>("wrong password").is_err());
+    }
+
+    #[test]
+    fn test_keystore_tampered() {
+        let account = Account::<Testnet2>::new(&mut thread_rng());
+        let keystore = Keystore::new_with_iterations(account.private_key(), "password", 16, &mut thread_rng()).unwrap();
+
+        let mut tampered = keystore.clone();
+        let mut ciphertext = hex::decode(&tampered.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        tampered.ciphertext = hex::encode(ciphertext);
+        assert!(tampered.decrypt::<Testnet2>("password").is_err());
+
+        // The address is authenticated with the ciphertext.
+        let mut tampered = keystore;
+        tampered.address = Account::<Testnet2>::new(&mut thread_rng()).address().to_string();
+        assert!(tampered.decrypt::<Testnet2>("password").is_err());
+    }
+
+    #[test]
+    fn test_keystore_save_and_load() {
+        let account = Account::<Testnet2>::new(&mut thread_rng());
+        let keystore = Keystore::new(account.private_key(), "password", &mut thread_rng()).unwrap();
+
+        let path = tempfile::tempdir().unwrap().into_path().join("keystore.json");
+        keystore.save(&path).unwrap();
+        assert!(keystore.save(&path).is_err());
+        assert_eq!(keystore, Keystore::load(&path).unwrap());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+        }
+    }
+
+    #[test]
+    fn test_keystore_load_rejects_unbounded_iterations() {
+        let account = Account::<Testnet2>::new(&mut thread_rng());
+        let directory = tempfile::tempdir().unwrap().into_path();
+
+        for (index, iterations) in [0, MINIMUM_KDF_ITERATIONS - 1, MAXIMUM_KDF_ITERATIONS + 1, u32::MAX]
+            .iter()
+            .enumerate()
+        {
+            let keystore = Keystore::new_with_iterations(account.private_key(), "password", 1, &mut thread_rng()).unwrap();
+            let keystore = Keystore {
+                iterations: *iterations,
+                ..keystore
+            };
+
+            let path = directory.join(format!("keystore-{}.json", index));
+            keystore.save(&path).unwrap();
+            assert!(Keystore::load(&path).is_err());
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fmt, time::Instant};
+
+/// The misbehavior score at which a peer is deprioritized for block requests.
+pub const DEPRIORITIZE_THRESHOLD: f64 = 25.0;
+/// The misbehavior score at which a peer is disconnected.
+pub const DISCONNECT_THRESHOLD: f64 = 100.0;
+/// The misbehavior score at which a peer is disconnected and banned.
+pub const BAN_THRESHOLD: f64 = 200.0;
+/// The misbehavior score below which a score is considered to have fully decayed.
+const NEGLIGIBLE_SCORE: f64 = 0.5;
+
+/// A type of misbehavior by a peer, with a description of the offense.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// The peer sent a block that failed validation.
+    InvalidBlock(String),
+    /// The peer sent a message that could not be deserialized or is internally inconsistent.
+    MalformedMessage(String),
+    /// The peer sent an unsolicited or redundant message.
+    Spam(String),
+    /// The peer requested more data than is permitted in one request.
+    OversizedRequest(String),
+    /// The peer submitted a share that has already been submitted.
+    DuplicateShare(String),
+}
+
+impl Misbehavior {
+    /// Returns the score that this misbehavior adds to the misbehavior score of a peer.
+    pub fn weight(&self) -> f64 {
+        match self {
+            Self::InvalidBlock(..) => 100.0,
+            Self::MalformedMessage(..) => 20.0,
+            Self::OversizedRequest(..) => 20.0,
+            Self::DuplicateShare(..) => 10.0,
+            Self::Spam(..) => 1.0,
+        }
+    }
+}
+
+impl fmt::Display for Misbehavior {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidBlock(reason) => write!(f, "invalid block ({})", reason),
+            Self::MalformedMessage(reason) => write!(f, "malformed message ({})", reason),
+            Self::Spam(reason) => write!(f, "spam ({})", reason),
+            Self::OversizedRequest(reason) => write!(f, "oversized request ({})", reason),
+            Self::DuplicateShare(reason) => write!(f, "duplicate share ({})", reason),
+        }
+    }
+}
+
+/// The penalty for a peer, in increasing order of severity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Penalty {
+    /// The peer is in good standing.
+    None,
+    /// The peer is only selected for block requests if no other peer is suitable.
+    Deprioritize,
+    /// The peer is disconnected.
+    Disconnect,
+    /// The peer is disconnected and banned for a period of time.
+    Ban,
+}
+
+///
+/// The misbehavior score of a peer.
+///
+/// Each misbehavior adds its weight to the score, and the score decays exponentially over time,
+/// halving after every half-life. The penalty of a peer is determined by its current score.
+///
+#[derive(Clone, Debug)]
+pub struct MisbehaviorScore {
+    /// The score as of the last update.
+    score: f64,
+    /// The timestamp of the last update.
+    last_updated: Instant,
+}
+
+impl Default for MisbehaviorScore {
+    fn default() -> Self {
+        Self {
+            score: 0.0,
+            last_updated: Instant::now(),
+        }
+    }
+}
+
+impl MisbehaviorScore {
+    /// Returns the current score, decayed by the given half-life.
+    pub fn score(&self, half_life_in_secs: u64) -> f64 {
+        self.score_at(Instant::now(), half_life_in_secs)
+    }
+
+    /// Adds the given misbehavior to the score, and returns the resulting penalty.
+    pub fn add(&mut self, misbehavior: &Misbehavior, half_life_in_secs: u64) -> Penalty {
+        self.add_at(Instant::now(), misbehavior, half_life_in_secs)
+    }
+
+    /// Returns the current penalty, decayed by the given half-life.
+    pub fn penalty(&self, half_life_in_secs: u64) -> Penalty {
+        Self::penalty_for(self.score(half_life_in_secs))
+    }
+
+    /// Returns `true` if the score has decayed to a negligible value.
+    pub fn is_negligible(&self, half_life_in_secs: u64) -> bool {
+        self.score(half_life_in_secs) < NEGLIGIBLE_SCORE
+    }
+
+    /// Returns the score at the given time, decayed by the given half-life.
+    fn score_at(&self, now: Instant, half_life_in_secs: u64) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_updated).as_secs_f64();
+        self.score * 0.5f64.powf(elapsed / half_life_in_secs.max(1) as f64)
+    }
+
+    /// Adds the given misbehavior to the score at the given time, and returns the resulting penalty.
+    fn add_at(&mut self, now: Instant, misbehavior: &Misbehavior, half_life_in_secs: u64) -> Penalty {
+        self.score = self.score_at(now, half_life_in_secs) + misbehavior.weight();
+        self.last_updated = now;
+        Self::penalty_for(self.score)
+    }
+
+    /// Returns the penalty for the given score.
+    fn penalty_for(score: f64) -> Penalty {
+        if score >= BAN_THRESHOLD {
+            Penalty::Ban
+        } else if score >= DISCONNECT_THRESHOLD {
+            Penalty::Disconnect
+        } else if score >= DEPRIORITIZE_THRESHOLD {
+            Penalty::Deprioritize
+        } else {
+            Penalty::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    const HALF_LIFE_IN_SECS: u64 = 1800;
+
+    #[test]
+    fn test_graduated_penalties() {
+        let mut score = MisbehaviorScore::default();
+        let now = score.last_updated;
+
+        let malformed = Misbehavior::MalformedMessage("test".to_string());
+        assert_eq!(Penalty::None, score.add_at(now, &malformed, HALF_LIFE_IN_SECS));
+        assert_eq!(Penalty::Deprioritize, score.add_at(now, &malformed, HALF_LIFE_IN_SECS));
+
+        let invalid_block = Misbehavior::InvalidBlock("test".to_string());
+        assert_eq!(Penalty::Disconnect, score.add_at(now, &invalid_block, HALF_LIFE_IN_SECS));
+        assert_eq!(Penalty::Ban, score.add_at(now, &invalid_block, HALF_LIFE_IN_SECS));
+    }
+
+    #[test]
+    fn test_decay() {
+        let mut score = MisbehaviorScore::default();
+        let now = score.last_updated;
+
+        // Trivial failures spread over time decay, and never accumulate to a disconnect.
+        let spam = Misbehavior::Spam("test".to_string());
+        let mut penalty = Penalty::None;
+        for i in 0..1024 {
+            penalty = penalty.max(score.add_at(now + Duration::from_secs(60 * i), &spam, HALF_LIFE_IN_SECS));
+        }
+        assert_eq!(Penalty::Deprioritize, penalty);
+
+        // A single invalid block is disconnected immediately.
+        let mut score = MisbehaviorScore::default();
+        let invalid_block = Misbehavior::InvalidBlock("test".to_string());
+        assert_eq!(Penalty::Disconnect, score.add_at(now, &invalid_block, HALF_LIFE_IN_SECS));
+
+        // The score halves after every half-life.
+        let later = now + Duration::from_secs(HALF_LIFE_IN_SECS);
+        assert!((score.score_at(later, HALF_LIFE_IN_SECS) - 50.0).abs() < 1e-9);
+        assert_eq!(
+            Penalty::Deprioritize,
+            MisbehaviorScore::penalty_for(score.score_at(later, HALF_LIFE_IN_SECS))
+        );
+    }
+}
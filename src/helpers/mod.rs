@@ -14,15 +14,51 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod address_group;
+pub use address_group::*;
+
+pub mod ban_list;
+pub use ban_list::*;
+
+pub mod block_download;
+pub use block_download::*;
+
+pub mod block_headers;
+pub use block_headers::*;
+
 pub mod block_requests;
 pub use block_requests::*;
 
+pub mod capabilities;
+pub use capabilities::*;
+
 pub mod circular_map;
 pub use circular_map::*;
 
+pub mod file;
+pub use file::*;
+
+pub mod ip_address;
+pub use ip_address::*;
+
+pub mod keystore;
+pub use keystore::*;
+
+pub mod misbehavior;
+pub use misbehavior::*;
+
+pub mod node_key;
+pub use node_key::*;
+
 pub mod node_type;
 pub use node_type::*;
 
+pub mod peer_book;
+pub use peer_book::*;
+
+pub mod rewards;
+pub use rewards::*;
+
 pub mod tasks;
 pub use tasks::*;
 
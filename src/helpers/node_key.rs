@@ -0,0 +1,210 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::helpers::write_private_file;
+
+use anyhow::{anyhow, Result};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{convert::TryInto, fmt, fs, path::Path, str::FromStr};
+
+/// The version of the node key format.
+const NODE_KEY_VERSION: u8 = 1;
+/// The Noise protocol of the encrypted transport between peers.
+pub const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+/// The static public key of a node, which identifies the node on an encrypted connection.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodePublicKey([u8; 32]);
+
+impl NodePublicKey {
+    /// Returns the public key for the given bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        match bytes.try_into() {
+            Ok(bytes) => Ok(Self(bytes)),
+            Err(_) => Err(anyhow!("Invalid node public key length {}", bytes.len())),
+        }
+    }
+
+    /// Returns the bytes of the public key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl FromStr for NodePublicKey {
+    type Err = anyhow::Error;
+
+    fn from_str(public_key: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(&hex::decode(public_key.trim())?)
+    }
+}
+
+impl fmt::Display for NodePublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for NodePublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NodePublicKey({})", self)
+    }
+}
+
+impl Serialize for NodePublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for NodePublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let public_key = String::deserialize(deserializer)?;
+        Self::from_str(&public_key).map_err(de::Error::custom)
+    }
+}
+
+/// The file format of the node key.
+#[derive(Serialize, Deserialize)]
+struct NodeKeyFile {
+    version: u8,
+    private_key: String,
+    public_key: NodePublicKey,
+}
+
+///
+/// The static keypair of a node, which authenticates the node on encrypted connections.
+///
+/// The keypair is persisted in the data directory, so that peers may recognize the node
+/// by its public key across restarts and changes of IP address.
+///
+#[derive(Clone)]
+pub struct NodeKey {
+    private_key: [u8; 32],
+    public_key: NodePublicKey,
+}
+
+impl NodeKey {
+    /// Generates a new node keypair.
+    pub fn new() -> Result<Self> {
+        let keypair = snow::Builder::new(NOISE_PARAMS.parse()?).generate_keypair()?;
+        Ok(Self {
+            private_key: keypair.private[..]
+                .try_into()
+                .map_err(|_| anyhow!("Invalid node private key length {}", keypair.private.len()))?,
+            public_key: NodePublicKey::from_bytes(&keypair.public)?,
+        })
+    }
+
+    /// Loads the node keypair from the given file, or generates and saves a new node keypair if the file does not exist.
+    pub fn load_or_generate<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match path.exists() {
+            true => Self::load(path),
+            false => {
+                let node_key = Self::new()?;
+                node_key.save(path)?;
+                Ok(node_key)
+            }
+        }
+    }
+
+    /// Loads the node keypair from the given file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|error| anyhow!("Failed to read the node key ({}): {}", path.display(), error))?;
+        let file: NodeKeyFile =
+            serde_json::from_str(&contents).map_err(|error| anyhow!("Invalid node key ({}): {}", path.display(), error))?;
+
+        if file.version != NODE_KEY_VERSION {
+            return Err(anyhow!("Unsupported node key ({})", path.display()));
+        }
+        let private_key = hex::decode(&file.private_key)?
+            .try_into()
+            .map_err(|_| anyhow!("Invalid node key ({}): the private key must be 32 bytes", path.display()))?;
+
+        Ok(Self {
+            private_key,
+            public_key: file.public_key,
+        })
+    }
+
+    /// Saves the node keypair to the given file, which must not already exist.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = NodeKeyFile {
+            version: NODE_KEY_VERSION,
+            private_key: hex::encode(self.private_key),
+            public_key: self.public_key,
+        };
+
+        // Ensure the private key is only readable by the owner of the node.
+        write_private_file(path, serde_json::to_string_pretty(&file)?.as_bytes())
+            .map_err(|error| anyhow!("Failed to create the node key ({}): {}", path.display(), error))
+    }
+
+    /// Returns the public key of the node.
+    pub fn public_key(&self) -> NodePublicKey {
+        self.public_key
+    }
+
+    /// Returns the private key of the node.
+    pub(crate) fn private_key(&self) -> &[u8] {
+        &self.private_key
+    }
+}
+
+impl fmt::Debug for NodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The private key is deliberately omitted.
+        write!(f, "NodeKey({})", self.public_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_key_save_and_load() {
+        let node_key = NodeKey::new().unwrap();
+        assert_ne!(node_key.public_key(), NodeKey::new().unwrap().public_key());
+
+        let path = tempfile::tempdir().unwrap().into_path().join("node.key.json");
+        node_key.save(&path).unwrap();
+        assert!(node_key.save(&path).is_err());
+
+        let loaded = NodeKey::load_or_generate(&path).unwrap();
+        assert_eq!(node_key.public_key(), loaded.public_key());
+        assert_eq!(node_key.private_key(), loaded.private_key());
+    }
+
+    #[test]
+    fn test_node_public_key_parse() {
+        let public_key = NodeKey::new().unwrap().public_key();
+        assert_eq!(public_key, NodePublicKey::from_str(&public_key.to_string()).unwrap());
+        assert_eq!(
+            public_key,
+            serde_json::from_str(&serde_json::to_string(&public_key).unwrap()).unwrap()
+        );
+
+        assert!(NodePublicKey::from_str("abcd").is_err());
+        assert!(NodePublicKey::from_str("not hex").is_err());
+    }
+}
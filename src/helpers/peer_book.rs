@@ -0,0 +1,329 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::helpers::{write_atomically, NodeType};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fs,
+    net::SocketAddr,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The version of the peer book format.
+const PEER_BOOK_VERSION: u8 = 1;
+/// The duration in seconds after which a peer that has not been seen is evicted from the peer book.
+pub const MAXIMUM_PEER_AGE_IN_SECS: u64 = 7 * 24 * 60 * 60; // 7 days
+/// The number of consecutive connection failures after which a peer is evicted from the peer book.
+pub const MAXIMUM_CONSECUTIVE_FAILURES: u32 = 10;
+
+/// The entry of a peer in the peer book.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    /// The UNIX timestamp in seconds of the last successful connection or failed attempt with the peer.
+    pub last_seen: u64,
+    /// The node type of the peer, if the peer has completed a handshake.
+    pub node_type: Option<NodeType>,
+    /// The number of successful connections with the peer.
+    pub num_successes: u32,
+    /// The number of connection failures with the peer since the last successful connection.
+    pub num_failures: u32,
+    /// The UNIX timestamp in seconds at which the restriction of the peer expires, if it is restricted.
+    pub restricted_until: Option<u64>,
+    /// The last measured round-trip time of the peer in milliseconds, if the peer has responded to a `Ping`.
+    pub rtt_in_millis: Option<u64>,
+}
+
+/// The information of a connected peer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerInfo {
+    /// The IP address of the peer.
+    pub ip: SocketAddr,
+    /// The node type of the peer.
+    pub node_type: Option<NodeType>,
+    /// `true` if this node dialed the peer, or `false` if the peer dialed this node.
+    pub is_outbound: bool,
+    /// The smoothed round-trip time of the peer in milliseconds, if it has been measured.
+    pub rtt_in_millis: Option<u64>,
+}
+
+///
+/// A record of the peers known to the node, which is persisted across restarts.
+///
+/// On startup, the peer book is aged and evicted, and its peers are used to seed the candidate
+/// peers and restricted peers, so that the node may rejoin the network without the sync nodes.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerBook {
+    version: u8,
+    peers: BTreeMap<SocketAddr, PeerRecord>,
+}
+
+impl Default for PeerBook {
+    fn default() -> Self {
+        Self {
+            version: PEER_BOOK_VERSION,
+            peers: Default::default(),
+        }
+    }
+}
+
+impl PeerBook {
+    /// Loads the peer book from the given file, or returns an empty peer book if the file does not exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).map_err(|error| anyhow!("Failed to read the peer book ({}): {}", path.display(), error))?;
+        let peer_book: Self =
+            serde_json::from_str(&contents).map_err(|error| anyhow!("Invalid peer book ({}): {}", path.display(), error))?;
+
+        if peer_book.version != PEER_BOOK_VERSION {
+            return Err(anyhow!("Unsupported peer book ({})", path.display()));
+        }
+        Ok(peer_book)
+    }
+
+    /// Saves the peer book to the given file, replacing any existing peer book.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_atomically(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    /// Returns the number of peers in the peer book.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Returns `true` if the peer book is empty.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Returns the entry of the given peer, if it exists.
+    pub fn get(&self, peer_ip: &SocketAddr) -> Option<&PeerRecord> {
+        self.peers.get(peer_ip)
+    }
+
+    /// Records a successful connection with the given peer.
+    pub fn record_success(&mut self, peer_ip: SocketAddr, node_type: NodeType) {
+        let record = self.peers.entry(peer_ip).or_default();
+        record.last_seen = unix_timestamp();
+        record.node_type = Some(node_type);
+        record.num_successes = record.num_successes.saturating_add(1);
+        record.num_failures = 0;
+    }
+
+    /// Records a failed connection attempt with the given peer.
+    pub fn record_failure(&mut self, peer_ip: SocketAddr) {
+        let record = self.peers.entry(peer_ip).or_default();
+        record.last_seen = unix_timestamp();
+        record.num_failures = record.num_failures.saturating_add(1);
+    }
+
+    /// Records the smoothed round-trip time of the given peer, if the peer is in the peer book.
+    pub fn record_round_trip_time(&mut self, peer_ip: SocketAddr, rtt: Duration) {
+        if let Some(record) = self.peers.get_mut(&peer_ip) {
+            record.rtt_in_millis = Some(rtt.as_millis() as u64);
+        }
+    }
+
+    /// Records a restriction of the given peer, for the given number of seconds, retaining any longer restriction.
+    pub fn record_restriction(&mut self, peer_ip: SocketAddr, duration_in_secs: u64) {
+        let record = self.peers.entry(peer_ip).or_default();
+        record.last_seen = unix_timestamp();
+        let restricted_until = unix_timestamp().saturating_add(duration_in_secs);
+        record.restricted_until = Some(record.restricted_until.unwrap_or(0).max(restricted_until));
+    }
+
+    ///
+    /// Returns the peers that are not restricted, ordered from the most to the least reliable.
+    ///
+    /// Peers are ranked by their number of successful connections, followed by their last-seen time.
+    ///
+    pub fn candidate_peers(&self) -> Vec<SocketAddr> {
+        let now = unix_timestamp();
+        let mut peers = self
+            .peers
+            .iter()
+            .filter(|(_, record)| !record.is_restricted(now) && record.num_failures < MAXIMUM_CONSECUTIVE_FAILURES)
+            .collect::<Vec<_>>();
+        peers.sort_by_key(|(_, record)| Reverse((record.num_successes, record.last_seen)));
+        peers.into_iter().map(|(peer_ip, _)| *peer_ip).collect()
+    }
+
+    ///
+    /// Returns the given peers ordered from the lowest to the highest round-trip time.
+    ///
+    /// Peers without a measured round-trip time are ordered last, and otherwise retain their given order.
+    ///
+    pub fn rank_by_round_trip_time(&self, mut peers: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        peers.sort_by_key(|peer_ip| match self.peers.get(peer_ip).and_then(|record| record.rtt_in_millis) {
+            Some(rtt_in_millis) => (false, rtt_in_millis),
+            None => (true, 0),
+        });
+        peers
+    }
+
+    /// Returns the peers that are restricted, with the number of seconds remaining in each restriction.
+    pub fn restricted_peers(&self) -> Vec<(SocketAddr, u64)> {
+        let now = unix_timestamp();
+        self.peers
+            .iter()
+            .filter_map(|(peer_ip, record)| match record.restricted_until {
+                Some(restricted_until) if restricted_until > now => Some((*peer_ip, restricted_until - now)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    ///
+    /// Ages the peer book, by clearing expired restrictions and evicting peers that have not been seen
+    /// within `MAXIMUM_PEER_AGE_IN_SECS` or have failed `MAXIMUM_CONSECUTIVE_FAILURES` times in a row.
+    /// If the peer book still exceeds the given maximum number of peers, the least reliable peers are evicted.
+    ///
+    pub fn prune(&mut self, maximum_number_of_peers: usize) {
+        let now = unix_timestamp();
+
+        // Clear the expired restrictions.
+        self.peers
+            .values_mut()
+            .filter(|record| !record.is_restricted(now))
+            .for_each(|record| {
+                record.restricted_until = None;
+            });
+
+        // Evict the stale and failing peers, retaining any peers that are still restricted.
+        self.peers.retain(|_, record| {
+            record.is_restricted(now)
+                || (now.saturating_sub(record.last_seen) <= MAXIMUM_PEER_AGE_IN_SECS && record.num_failures < MAXIMUM_CONSECUTIVE_FAILURES)
+        });
+
+        // Evict the least reliable peers, if the peer book exceeds the maximum number of peers.
+        if self.peers.len() > maximum_number_of_peers {
+            let mut peers = self
+                .peers
+                .iter()
+                .map(|(peer_ip, record)| (*peer_ip, record.clone()))
+                .collect::<Vec<_>>();
+            peers.sort_by_key(|(_, record)| Reverse((record.is_restricted(now), record.num_successes, record.last_seen)));
+            peers.truncate(maximum_number_of_peers);
+            self.peers = peers.into_iter().collect();
+        }
+    }
+}
+
+impl PeerRecord {
+    /// Returns `true` if the peer is restricted at the given UNIX timestamp.
+    fn is_restricted(&self, now: u64) -> bool {
+        matches!(self.restricted_until, Some(restricted_until) if restricted_until > now)
+    }
+}
+
+/// Returns the current UNIX timestamp in seconds.
+pub(crate) fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_book_ranking_and_pruning() {
+        let (a, b, c, d): (SocketAddr, SocketAddr, SocketAddr, SocketAddr) = (
+            "1.1.1.1:4132".parse().unwrap(),
+            "2.2.2.2:4132".parse().unwrap(),
+            "3.3.3.3:4132".parse().unwrap(),
+            "4.4.4.4:4132".parse().unwrap(),
+        );
+
+        let mut peer_book = PeerBook::default();
+        peer_book.record_success(a, NodeType::Client);
+        peer_book.record_success(b, NodeType::Miner);
+        peer_book.record_success(b, NodeType::Miner);
+        peer_book.record_restriction(c, 60);
+        (0..MAXIMUM_CONSECUTIVE_FAILURES).for_each(|_| peer_book.record_failure(d));
+
+        assert_eq!(vec![b, a], peer_book.candidate_peers());
+        assert_eq!(
+            vec![c],
+            peer_book
+                .restricted_peers()
+                .into_iter()
+                .map(|(peer_ip, _)| peer_ip)
+                .collect::<Vec<_>>()
+        );
+
+        // Age out a peer that has not been seen recently.
+        peer_book.peers.get_mut(&a).unwrap().last_seen -= MAXIMUM_PEER_AGE_IN_SECS + 1;
+        peer_book.prune(usize::MAX);
+        assert_eq!(2, peer_book.len());
+        assert!(peer_book.get(&a).is_none());
+        assert!(peer_book.get(&d).is_none());
+
+        // Evict down to the maximum number of peers, retaining the restricted peer.
+        peer_book.prune(1);
+        assert_eq!(1, peer_book.len());
+        assert!(peer_book.get(&c).is_some());
+    }
+
+    #[test]
+    fn test_peer_book_save_and_load() {
+        let mut peer_book = PeerBook::default();
+        peer_book.record_success("1.1.1.1:4132".parse().unwrap(), NodeType::Sync);
+        peer_book.record_restriction("2.2.2.2:4132".parse().unwrap(), 60);
+
+        let path = tempfile::tempdir().unwrap().into_path().join("peers.json");
+        assert_eq!(PeerBook::default(), PeerBook::load(&path).unwrap());
+
+        peer_book.save(&path).unwrap();
+        peer_book.save(&path).unwrap();
+        assert_eq!(peer_book, PeerBook::load(&path).unwrap());
+    }
+
+    #[test]
+    fn test_peer_book_round_trip_time() {
+        let (a, b, c, d): (SocketAddr, SocketAddr, SocketAddr, SocketAddr) = (
+            "1.1.1.1:4132".parse().unwrap(),
+            "2.2.2.2:4132".parse().unwrap(),
+            "3.3.3.3:4132".parse().unwrap(),
+            "4.4.4.4:4132".parse().unwrap(),
+        );
+
+        let mut peer_book = PeerBook::default();
+        peer_book.record_success(a, NodeType::Client);
+        peer_book.record_success(b, NodeType::Client);
+        peer_book.record_success(c, NodeType::Client);
+        peer_book.record_round_trip_time(a, Duration::from_millis(250));
+        peer_book.record_round_trip_time(b, Duration::from_millis(40));
+
+        // Round-trip times are only recorded for peers in the peer book.
+        peer_book.record_round_trip_time(d, Duration::from_millis(10));
+        assert!(peer_book.get(&d).is_none());
+
+        // Unmeasured peers are ranked last, in their given order.
+        assert_eq!(vec![b, a, d, c], peer_book.rank_by_round_trip_time(vec![d, a, c, b]));
+    }
+}
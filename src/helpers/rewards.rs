@@ -0,0 +1,202 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkos_storage::LedgerState;
+use snarkvm::dpc::{Address, Network, Record};
+
+use serde::Serialize;
+use std::fmt;
+
+/// The number of blocks that must follow a coinbase record's block for the record to be confirmed.
+pub const COINBASE_CONFIRMATION_DEPTH: u32 = 2048;
+
+/// The maturity of a coinbase record.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardStatus {
+    /// The block of the coinbase record is canonical, but not yet buried by enough blocks.
+    Pending,
+    /// The block of the coinbase record is buried by more than `COINBASE_CONFIRMATION_DEPTH` blocks.
+    Confirmed,
+    /// The block of the coinbase record is no longer on the canonical chain, due to a reorg.
+    Orphaned,
+}
+
+/// A coinbase record that was mined by this node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(bound = "")]
+pub struct Reward<N: Network> {
+    /// The block height of the coinbase record.
+    pub block_height: u32,
+    /// The commitment of the coinbase record.
+    pub commitment: N::Commitment,
+    /// The value of the coinbase record, in gates.
+    pub value: i64,
+    /// The number of blocks that follow the block of the coinbase record on the canonical chain.
+    pub confirmation_depth: u32,
+    /// The maturity of the coinbase record.
+    pub status: RewardStatus,
+}
+
+///
+/// A report of the coinbase records mined by an address.
+///
+/// The report is built from the coinbase records in prover storage, by checking each record
+/// against the canonical chain of the ledger. Records that are no longer in the ledger are flagged
+/// as orphaned, and the remaining records are confirmed once they are sufficiently deep.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(bound = "")]
+pub struct RewardsReport<N: Network> {
+    /// The address of the miner.
+    pub address: Address<N>,
+    /// The latest block height of the ledger.
+    pub latest_block_height: u32,
+    /// The number of confirmed coinbase records.
+    pub confirmed_blocks: usize,
+    /// The number of pending coinbase records.
+    pub pending_blocks: usize,
+    /// The number of orphaned coinbase records.
+    pub orphaned_blocks: usize,
+    /// The total value of the confirmed coinbase records, in gates.
+    pub confirmed_value: i64,
+    /// The total value of the pending coinbase records, in gates.
+    pub pending_value: i64,
+    /// The coinbase records of the address, in increasing block height order.
+    pub rewards: Vec<Reward<N>>,
+}
+
+impl<N: Network> RewardsReport<N> {
+    /// Returns the rewards report of the given address, for the given coinbase records and ledger.
+    pub fn new(ledger: &LedgerState<N>, coinbase_records: Vec<(u32, Record<N>)>, address: Address<N>) -> Self {
+        let records = coinbase_records.into_iter().map(|(block_height, record)| {
+            // Determine if the coinbase record exists on the canonical chain.
+            let is_canonical = matches!(ledger.contains_commitment(&record.commitment()), Ok(true));
+            (block_height, record, is_canonical)
+        });
+        Self::from_records(address, ledger.latest_block_height(), records)
+    }
+
+    /// Returns the rewards report of the given address, for the given coinbase records and whether each is canonical.
+    fn from_records(address: Address<N>, latest_block_height: u32, records: impl Iterator<Item = (u32, Record<N>, bool)>) -> Self {
+        let mut rewards = records
+            .filter(|(_, record, _)| record.owner() == address)
+            .map(|(block_height, record, is_canonical)| {
+                // A coinbase record for a block that has not been added to the ledger yet is pending.
+                let (confirmation_depth, status) = match (is_canonical, block_height > latest_block_height) {
+                    (true, _) | (false, true) => {
+                        let confirmation_depth = latest_block_height.saturating_sub(block_height);
+                        match confirmation_depth > COINBASE_CONFIRMATION_DEPTH {
+                            true => (confirmation_depth, RewardStatus::Confirmed),
+                            false => (confirmation_depth, RewardStatus::Pending),
+                        }
+                    }
+                    (false, false) => (0, RewardStatus::Orphaned),
+                };
+
+                Reward {
+                    block_height,
+                    commitment: record.commitment(),
+                    value: record.value().0,
+                    confirmation_depth,
+                    status,
+                }
+            })
+            .collect::<Vec<_>>();
+        rewards.sort_by_key(|reward| reward.block_height);
+
+        let count = |status: RewardStatus| rewards.iter().filter(|reward| reward.status == status).count();
+        let value = |status: RewardStatus| {
+            rewards
+                .iter()
+                .filter(|reward| reward.status == status)
+                .map(|reward| reward.value)
+                .sum::<i64>()
+        };
+
+        Self {
+            address,
+            latest_block_height,
+            confirmed_blocks: count(RewardStatus::Confirmed),
+            pending_blocks: count(RewardStatus::Pending),
+            orphaned_blocks: count(RewardStatus::Orphaned),
+            confirmed_value: value(RewardStatus::Confirmed),
+            pending_value: value(RewardStatus::Pending),
+            rewards,
+        }
+    }
+}
+
+impl<N: Network> fmt::Display for RewardsReport<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Mining Report (confirmed_blocks = {}, pending_blocks = {}, orphaned_blocks = {}, miner_address = {})",
+            self.confirmed_blocks, self.pending_blocks, self.orphaned_blocks, self.address
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snarkvm::dpc::{testnet2::Testnet2, Account, AleoAmount, Transaction};
+
+    use rand::thread_rng;
+
+    /// Returns a new coinbase record for the given address.
+    fn new_coinbase_record(address: Address<Testnet2>, value: i64) -> Record<Testnet2> {
+        let (_, record) = Transaction::<Testnet2>::new_coinbase(address, AleoAmount(value), true, &mut thread_rng())
+            .expect("Failed to create a coinbase transaction");
+        record
+    }
+
+    #[test]
+    fn test_rewards_report() {
+        let address = Account::<Testnet2>::new(&mut thread_rng()).address();
+        let other_address = Account::<Testnet2>::new(&mut thread_rng()).address();
+        let latest_block_height = 5000;
+
+        let records = vec![
+            (4000, new_coinbase_record(address, 3), true),
+            (1000, new_coinbase_record(address, 1), true),
+            (2000, new_coinbase_record(address, 2), false),
+            (5001, new_coinbase_record(address, 4), false),
+            (1500, new_coinbase_record(other_address, 5), true),
+        ];
+        let report = RewardsReport::from_records(address, latest_block_height, records.into_iter());
+
+        assert_eq!(1, report.confirmed_blocks);
+        assert_eq!(2, report.pending_blocks);
+        assert_eq!(1, report.orphaned_blocks);
+        assert_eq!(1, report.confirmed_value);
+        assert_eq!(7, report.pending_value);
+
+        let statuses = report
+            .rewards
+            .iter()
+            .map(|reward| (reward.block_height, reward.confirmation_depth, reward.status));
+        assert_eq!(
+            vec![
+                (1000, 4000, RewardStatus::Confirmed),
+                (2000, 0, RewardStatus::Orphaned),
+                (4000, 1000, RewardStatus::Pending),
+                (5001, 0, RewardStatus::Pending),
+            ],
+            statuses.collect::<Vec<_>>()
+        );
+    }
+}
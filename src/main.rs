@@ -17,7 +17,6 @@
 use snarkos::{initialize_logger, Node};
 
 use anyhow::Result;
-use structopt::StructOpt;
 use tokio::runtime;
 
 fn main() -> Result<()> {
@@ -25,8 +24,8 @@ fn main() -> Result<()> {
         eprintln!("\nWARNING - Your machine must have at least 16-cores to run a node.\n");
     }
 
-    // Parse the provided arguments.
-    let node = Node::from_args();
+    // Parse the provided arguments, config file, and environment variables.
+    let node = Node::load()?;
 
     // Start logging, if enabled.
     if !node.display {
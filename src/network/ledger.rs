@@ -15,7 +15,18 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    helpers::{block_requests::*, CircularMap, NodeType, State},
+    helpers::{
+        block_requests::*,
+        BlockDownloadScheduler,
+        BlockHeaderChain,
+        Capabilities,
+        CircularMap,
+        Misbehavior,
+        MisbehaviorScore,
+        NodeType,
+        Penalty,
+        State,
+    },
     Data,
     Environment,
     LedgerReader,
@@ -25,13 +36,13 @@ use crate::{
     ProverRequest,
     ProverRouter,
 };
-use snarkos_storage::{storage::Storage, BlockLocators, LedgerState, MAXIMUM_LINEAR_BLOCK_LOCATORS};
+use snarkos_storage::{storage::Storage, BlockLocators, InvalidBlockError, LedgerState, MAXIMUM_LINEAR_BLOCK_LOCATORS};
 use snarkvm::dpc::prelude::*;
 
 use anyhow::Result;
 use chrono::Utc;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     hash::{Hash, Hasher},
     net::SocketAddr,
     path::Path,
@@ -57,16 +68,28 @@ type LedgerHandler<N> = mpsc::Receiver<LedgerRequest<N>>;
 ///
 #[derive(Debug)]
 pub enum LedgerRequest<N: Network> {
+    /// BlockHeadersResponse := (peer_ip, block_headers)
+    BlockHeadersResponse(SocketAddr, Vec<BlockHeader<N>>),
+    /// BlockNotFound := (peer_ip, block_hash)
+    BlockNotFound(SocketAddr, N::BlockHash),
     /// BlockResponse := (peer_ip, block, prover_router)
     BlockResponse(SocketAddr, Block<N>, ProverRouter<N>),
     /// Disconnect := (peer_ip, message)
     Disconnect(SocketAddr, String),
-    /// Failure := (peer_ip, failure)
-    Failure(SocketAddr, String),
+    /// Failure := (peer_ip, misbehavior)
+    Failure(SocketAddr, Misbehavior),
     /// Heartbeat := (prover_router)
     Heartbeat(ProverRouter<N>),
-    /// Pong := (peer_ip, node_type, status, is_fork, block_locators)
-    Pong(SocketAddr, NodeType, State, Option<bool>, BlockLocators<N>),
+    /// Pong := (peer_ip, node_type, status, capabilities, rtt, is_fork, block_locators)
+    Pong(
+        SocketAddr,
+        NodeType,
+        State,
+        Capabilities,
+        Option<Duration>,
+        Option<bool>,
+        BlockLocators<N>,
+    ),
     /// UnconfirmedBlock := (peer_ip, block, prover_router)
     UnconfirmedBlock(SocketAddr, Block<N>, ProverRouter<N>),
 }
@@ -114,6 +137,46 @@ impl<N: Network> From<(u32, Option<N::BlockHash>)> for BlockRequest<N> {
     }
 }
 
+///
+/// A download of the blocks of a canonical chain, which are requested in parallel from the peers advertising it.
+///
+#[derive(Debug)]
+struct BlockDownload<N: Network> {
+    /// The scheduler of the block ranges that are requested from each peer.
+    scheduler: BlockDownloadScheduler,
+    /// The block locators of the canonical chain that is being downloaded.
+    block_locators: BlockLocators<N>,
+    /// `true` if the ledger was reverted to download a fork, in which case the block requests include the expected block hashes.
+    ledger_is_on_fork: bool,
+    /// The map of downloaded blocks that are waiting to be committed in order := (block_height, (peer_ip, block))
+    downloaded_blocks: BTreeMap<u32, (SocketAddr, Block<N>)>,
+    /// The map of each block height to the header root of its validated block header, if the block headers were downloaded first.
+    header_roots: BTreeMap<u32, N::BlockHeaderRoot>,
+}
+
+///
+/// A download of the block headers of a canonical chain from a peer, which are validated before its blocks are downloaded.
+///
+#[derive(Debug)]
+struct HeaderDownload<N: Network> {
+    /// The peer that the block headers are requested from.
+    peer_ip: SocketAddr,
+    /// The chain of block headers that have been validated.
+    chain: BlockHeaderChain<N>,
+    /// The first block height of the canonical chain to download.
+    start_block_height: u32,
+    /// The last block height of the canonical chain to download.
+    end_block_height: u32,
+    /// The block locators of the canonical chain that is being downloaded.
+    block_locators: BlockLocators<N>,
+    /// `true` if the ledger must revert to the common ancestor, before downloading the blocks.
+    ledger_is_on_fork: bool,
+    /// The common ancestor of this ledger and the canonical chain.
+    common_ancestor: u32,
+    /// The timestamp of the last block headers request.
+    requested_at: Instant,
+}
+
 pub type PeersState<N> = HashMap<SocketAddr, Option<(NodeType, State, Option<bool>, u32, BlockLocators<N>)>>;
 
 ///
@@ -141,10 +204,20 @@ pub struct Ledger<N: Network, E: Environment> {
     /// A lock to ensure methods that need to be mutually-exclusive are enforced.
     /// In this context, `update_ledger`, `add_block`, and `update_block_requests` must be mutually-exclusive.
     block_requests_lock: Arc<Mutex<()>>,
+    /// The download of the canonical chain that the ledger is syncing to, if it exists.
+    block_download: RwLock<Option<BlockDownload<N>>>,
+    /// The download of the block headers of the canonical chain that the ledger is validating, if it exists.
+    header_download: RwLock<Option<HeaderDownload<N>>>,
+    /// The set of peers whose last block headers request has timed out, and whose response may still arrive.
+    expired_header_requests: RwLock<HashSet<SocketAddr>>,
+    /// The map of each peer to the protocol extensions negotiated with it.
+    peers_capabilities: RwLock<HashMap<SocketAddr, Capabilities>>,
+    /// The map of each peer to their smoothed round-trip time, if it has been measured.
+    peers_rtt: RwLock<HashMap<SocketAddr, Duration>>,
     /// The timestamp of the last successful block update.
     last_block_update_timestamp: RwLock<Instant>,
-    /// The map of each peer to their failure messages := (failure_message, timestamp).
-    failures: RwLock<HashMap<SocketAddr, Vec<(String, i64)>>>,
+    /// The map of each peer to their misbehavior score, which is retained after the peer disconnects.
+    misbehavior_scores: RwLock<HashMap<SocketAddr, MisbehaviorScore>>,
     /// The peers router of the node.
     peers_router: PeersRouter<N, E>,
 }
@@ -165,8 +238,13 @@ impl<N: Network, E: Environment> Ledger<N, E> {
             peers_state: Default::default(),
             block_requests: Default::default(),
             block_requests_lock: Arc::new(Mutex::new(())),
+            block_download: Default::default(),
+            header_download: Default::default(),
+            expired_header_requests: Default::default(),
+            peers_capabilities: Default::default(),
+            peers_rtt: Default::default(),
             last_block_update_timestamp: RwLock::new(Instant::now()),
-            failures: Default::default(),
+            misbehavior_scores: Default::default(),
             peers_router,
         });
 
@@ -239,11 +317,21 @@ impl<N: Network, E: Environment> Ledger<N, E> {
     ///
     pub(super) async fn update(&self, request: LedgerRequest<N>) {
         match request {
+            LedgerRequest::BlockHeadersResponse(peer_ip, block_headers) => {
+                self.add_block_headers(peer_ip, block_headers).await;
+            }
+            LedgerRequest::BlockNotFound(peer_ip, block_hash) => {
+                // Remove the block requests that the peer is unable to serve.
+                if self.remove_block_requests_not_found(peer_ip, block_hash).await {
+                    // On success, request the blocks from another peer.
+                    self.update_block_requests().await;
+                }
+            }
             LedgerRequest::BlockResponse(peer_ip, block, prover_router) => {
                 // Remove the block request from the ledger.
                 if self.remove_block_request(peer_ip, block.height()).await {
                     // On success, process the block response.
-                    self.add_block(block, &prover_router).await;
+                    self.add_downloaded_block(peer_ip, block, &prover_router).await;
                     // Check if syncing with this peer is complete.
                     if self
                         .block_requests
@@ -261,8 +349,8 @@ impl<N: Network, E: Environment> Ledger<N, E> {
             LedgerRequest::Disconnect(peer_ip, message) => {
                 self.disconnect(peer_ip, &message).await;
             }
-            LedgerRequest::Failure(peer_ip, failure) => {
-                self.add_failure(peer_ip, failure).await;
+            LedgerRequest::Failure(peer_ip, misbehavior) => {
+                self.add_misbehavior(peer_ip, misbehavior).await;
             }
             LedgerRequest::Heartbeat(prover_router) => {
                 // Update for sync nodes.
@@ -273,10 +361,8 @@ impl<N: Network, E: Environment> Ledger<N, E> {
                 self.update_status().await;
                 // Remove expired block requests.
                 self.remove_expired_block_requests().await;
-                // Remove expired failures.
-                self.remove_expired_failures().await;
-                // Disconnect from peers with frequent failures.
-                self.disconnect_from_failing_peers().await;
+                // Penalize peers with high misbehavior scores.
+                self.penalize_misbehaving_peers().await;
                 // Update the block requests.
                 if E::NODE_TYPE != NodeType::Prover {
                     self.update_block_requests().await;
@@ -292,9 +378,15 @@ impl<N: Network, E: Environment> Ledger<N, E> {
                     self.peers_state.read().await.len()
                 );
             }
-            LedgerRequest::Pong(peer_ip, node_type, status, is_fork, block_locators) => {
+            LedgerRequest::Pong(peer_ip, node_type, status, capabilities, rtt, is_fork, block_locators) => {
                 // Ensure the peer has been initialized in the ledger.
                 self.initialize_peer(peer_ip).await;
+                // Update the protocol extensions of the peer.
+                self.peers_capabilities.write().await.insert(peer_ip, capabilities);
+                // Update the round-trip time of the peer, if it has been measured.
+                if let Some(rtt) = rtt {
+                    self.peers_rtt.write().await.insert(peer_ip, rtt);
+                }
                 // Process the pong.
                 self.update_peer(peer_ip, node_type, status, is_fork, block_locators).await;
             }
@@ -302,7 +394,7 @@ impl<N: Network, E: Environment> Ledger<N, E> {
                 // Ensure the node is not peering.
                 if !E::status().is_peering() {
                     // Process the unconfirmed block.
-                    self.add_block(block.clone(), &prover_router).await;
+                    self.add_block(block.clone(), Some(peer_ip), &prover_router).await;
                     // Propagate the unconfirmed block to the connected peers.
                     let message = Message::UnconfirmedBlock(block.height(), block.hash(), Data::Object(block));
                     let request = PeersRequest::MessagePropagate(peer_ip, message);
@@ -360,6 +452,30 @@ impl<N: Network, E: Environment> Ledger<N, E> {
         }
     }
 
+    ///
+    /// Disconnects and bans the given peer from the ledger, for `E::misbehavior_ban_duration_in_secs()`.
+    ///
+    async fn disconnect_and_ban(&self, peer_ip: SocketAddr, message: &str) {
+        info!("Disconnecting and banning {} ({})", peer_ip, message);
+        // Remove all entries of the peer from the ledger.
+        self.remove_peer(&peer_ip).await;
+        // Update the status of the ledger.
+        self.update_status().await;
+        // Send a `Disconnect` message to the peer.
+        if let Err(error) = self
+            .peers_router
+            .send(PeersRequest::MessageSend(peer_ip, Message::Disconnect))
+            .await
+        {
+            warn!("[Disconnect] {}", error);
+        }
+        // Route a `PeerBanned` to the peers.
+        let request = PeersRequest::PeerBanned(peer_ip, E::misbehavior_ban_duration_in_secs());
+        if let Err(error) = self.peers_router.send(request).await {
+            warn!("[PeerBanned] {}", error);
+        }
+    }
+
     ///
     /// Performs a heartbeat update for the sync nodes.
     ///
@@ -412,7 +528,7 @@ impl<N: Network, E: Environment> Ledger<N, E> {
         let unconfirmed_blocks_snapshot = self.unconfirmed_blocks.read().await.clone();
         while let Some(unconfirmed_block) = unconfirmed_blocks_snapshot.get(&block_hash) {
             // Attempt to add the unconfirmed block.
-            match self.add_block(unconfirmed_block.clone(), prover_router).await {
+            match self.add_block(unconfirmed_block.clone(), None, prover_router).await {
                 // Upon success, update the block hash iterator.
                 true => block_hash = unconfirmed_block.hash(),
                 false => break,
@@ -422,7 +538,7 @@ impl<N: Network, E: Environment> Ledger<N, E> {
         // If the timestamp of the last block increment has surpassed the preset limit,
         // the ledger is likely syncing from invalid state, and should revert by one block.
         if E::status().is_syncing()
-            && self.last_block_update_timestamp.read().await.elapsed() > 2 * Duration::from_secs(E::RADIO_SILENCE_IN_SECS)
+            && self.last_block_update_timestamp.read().await.elapsed() > 2 * Duration::from_secs(E::radio_silence_in_secs())
         {
             // Acquire the lock for block requests.
             let _block_request_lock = self.block_requests_lock.lock().await;
@@ -440,6 +556,8 @@ impl<N: Network, E: Environment> Ledger<N, E> {
                 .await
                 .values_mut()
                 .for_each(|requests| *requests = Default::default());
+            *self.block_download.write().await = None;
+            *self.header_download.write().await = None;
             self.revert_to_block_height(self.canon.latest_block_height().saturating_sub(1))
                 .await;
         }
@@ -460,7 +578,7 @@ impl<N: Network, E: Environment> Ledger<N, E> {
             return;
         }
         // If there is an insufficient number of connected peers, set the status to `Peering`.
-        else if self.peers_state.read().await.len() < E::MINIMUM_NUMBER_OF_PEERS {
+        else if self.peers_state.read().await.len() < E::minimum_number_of_peers() {
             status = State::Peering;
         }
         // If the ledger is out of date, set the status to `Syncing`.
@@ -512,8 +630,9 @@ impl<N: Network, E: Environment> Ledger<N, E> {
     ///     2) to the pending queue for later use.
     ///
     /// Returns `true` if the given block is successfully added to the *canon* chain.
+    /// If the block is invalid, the misbehavior is attributed to the given peer, if it exists.
     ///
-    async fn add_block(&self, unconfirmed_block: Block<N>, prover_router: &ProverRouter<N>) -> bool {
+    async fn add_block(&self, unconfirmed_block: Block<N>, peer_ip: Option<SocketAddr>, prover_router: &ProverRouter<N>) -> bool {
         // Retrieve the unconfirmed block height.
         let unconfirmed_block_height = unconfirmed_block.height();
         // Retrieve the unconfirmed block hash.
@@ -583,7 +702,15 @@ impl<N: Network, E: Environment> Ledger<N, E> {
 
                         return true;
                     }
-                    Err(error) => warn!("{}", error),
+                    Err(error) => {
+                        warn!("{}", error);
+                        // Only penalize the peer for a block that fails validation, and not for a failure of the ledger storage.
+                        if let Some(peer_ip) = peer_ip {
+                            if error.is::<InvalidBlockError>() {
+                                self.add_misbehavior(peer_ip, Misbehavior::InvalidBlock(error.to_string())).await;
+                            }
+                        }
+                    }
                 },
             }
         } else {
@@ -606,6 +733,72 @@ impl<N: Network, E: Environment> Ledger<N, E> {
         false
     }
 
+    ///
+    /// Adds the given block from a block response. If the ledger is downloading a canonical chain,
+    /// the block is held until the blocks before it have been downloaded, so that they are committed in order.
+    ///
+    async fn add_downloaded_block(&self, peer_ip: SocketAddr, block: Block<N>, prover_router: &ProverRouter<N>) {
+        let (block, is_mismatched) = match &mut *self.block_download.write().await {
+            Some(block_download) => {
+                block_download.scheduler.received(peer_ip);
+
+                // Ensure the block matches its validated block header, if the block headers were downloaded first.
+                let block_height = block.height();
+                let is_mismatched = match block_download.header_roots.get(&block_height) {
+                    Some(header_root) => block.header().to_header_root().ok().as_ref() != Some(header_root),
+                    None => false,
+                };
+                match is_mismatched {
+                    true => block_download.scheduler.requeue([block_height]),
+                    false => {
+                        block_download.downloaded_blocks.insert(block_height, (peer_ip, block));
+                    }
+                }
+                (None, is_mismatched)
+            }
+            None => (Some(block), false),
+        };
+
+        // If the block does not match its validated block header, attribute the misbehavior to the peer.
+        if is_mismatched {
+            let misbehavior = Misbehavior::InvalidBlock("block does not match its validated block header".to_string());
+            self.add_misbehavior(peer_ip, misbehavior).await;
+            return;
+        }
+
+        // If the ledger is not downloading a canonical chain, add the block directly.
+        if let Some(block) = block {
+            self.add_block(block, Some(peer_ip), prover_router).await;
+            return;
+        }
+
+        // Commit the downloaded blocks that extend the canonical chain, in order.
+        loop {
+            let next_block_height = self.canon.latest_block_height() + 1;
+            let next_block = match &mut *self.block_download.write().await {
+                Some(block_download) => {
+                    // Discard the downloaded blocks that are no longer ahead of the canonical chain.
+                    block_download.downloaded_blocks = block_download.downloaded_blocks.split_off(&next_block_height);
+                    block_download.downloaded_blocks.remove(&next_block_height)
+                }
+                None => None,
+            };
+
+            match next_block {
+                Some((peer_ip, block)) => {
+                    // If the block is not added, re-queue its block height to be requested from the next idle peer.
+                    if !self.add_block(block, Some(peer_ip), prover_router).await {
+                        if let Some(block_download) = &mut *self.block_download.write().await {
+                            block_download.scheduler.requeue([next_block_height]);
+                        }
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
     ///
     /// Reverts the ledger state back to height `block_height`, returning `true` on success.
     ///
@@ -655,7 +848,6 @@ impl<N: Network, E: Environment> Ledger<N, E> {
         if !peer_state_exists {
             self.peers_state.write().await.entry(peer_ip).or_insert(None);
             self.block_requests.write().await.entry(peer_ip).or_insert_with(Default::default);
-            self.failures.write().await.entry(peer_ip).or_insert_with(Default::default);
         }
     }
 
@@ -664,8 +856,24 @@ impl<N: Network, E: Environment> Ledger<N, E> {
     ///
     async fn remove_peer(&self, peer_ip: &SocketAddr) {
         self.peers_state.write().await.remove(peer_ip);
-        self.block_requests.write().await.remove(peer_ip);
-        self.failures.write().await.remove(peer_ip);
+        self.peers_capabilities.write().await.remove(peer_ip);
+        self.peers_rtt.write().await.remove(peer_ip);
+        self.expired_header_requests.write().await.remove(peer_ip);
+
+        // Abort the header download from the peer, if it exists.
+        let mut header_download = self.header_download.write().await;
+        if matches!(&*header_download, Some(download) if download.peer_ip == *peer_ip) {
+            *header_download = None;
+        }
+        drop(header_download);
+
+        let block_requests = self.block_requests.write().await.remove(peer_ip);
+
+        // Re-queue the outstanding block requests of the peer, to be requested from another peer.
+        if let (Some(block_requests), Some(block_download)) = (block_requests, &mut *self.block_download.write().await) {
+            let block_heights = block_requests.keys().map(|request| request.block_height);
+            block_download.scheduler.remove_peer(*peer_ip, block_heights);
+        }
     }
 
     ///
@@ -681,15 +889,16 @@ impl<N: Network, E: Environment> Ledger<N, E> {
     ) {
         // Ensure the list of block locators is not empty.
         if block_locators.is_empty() {
-            self.add_failure(peer_ip, "Received a sync response with no block locators".to_string())
-                .await;
+            let misbehavior = Misbehavior::MalformedMessage("received a sync response with no block locators".to_string());
+            self.add_misbehavior(peer_ip, misbehavior).await;
         } else {
             // Ensure the peer provided well-formed block locators.
             match self.canon.check_block_locators(&block_locators) {
                 Ok(is_valid) => {
                     if !is_valid {
                         warn!("Invalid block locators from {}", peer_ip);
-                        self.add_failure(peer_ip, "Invalid block locators".to_string()).await;
+                        self.add_misbehavior(peer_ip, Misbehavior::MalformedMessage("invalid block locators".to_string()))
+                            .await;
                         return;
                     }
                 }
@@ -708,7 +917,7 @@ impl<N: Network, E: Environment> Ledger<N, E> {
                     if expected_block_height != *block_height {
                         let error = format!("Invalid block height {} for block hash {}", expected_block_height, block_hash);
                         trace!("{}", error);
-                        self.add_failure(peer_ip, error).await;
+                        self.add_misbehavior(peer_ip, Misbehavior::MalformedMessage(error)).await;
                         return;
                     } else {
                         // Update the common ancestor, as this block hash exists in this ledger.
@@ -761,15 +970,42 @@ impl<N: Network, E: Environment> Ledger<N, E> {
 
             match self.peers_state.write().await.get_mut(&peer_ip) {
                 Some(peer_state) => *peer_state = Some((node_type, status, is_fork, latest_block_height_of_peer, block_locators)),
-                None => self.add_failure(peer_ip, format!("Missing ledger state for {}", peer_ip)).await,
+                None => {
+                    let misbehavior = Misbehavior::Spam(format!("missing ledger state for {}", peer_ip));
+                    self.add_misbehavior(peer_ip, misbehavior).await
+                }
             };
         }
     }
 
     ///
-    /// Proceeds to send block requests to a connected peer, if the ledger is out of date.
+    /// Proceeds to send block requests to the connected peers, if the ledger is out of date.
     ///
     async fn update_block_requests(&self) {
+        // If a header download is in progress, wait for its block headers, unless the request has timed out.
+        {
+            let mut header_download = self.header_download.write().await;
+            if let Some(download) = &*header_download {
+                match download.requested_at.elapsed() > Duration::from_secs(E::radio_silence_in_secs()) {
+                    true => {
+                        debug!("Block headers request to {} has timed out", download.peer_ip);
+                        self.expired_header_requests.write().await.insert(download.peer_ip);
+                        *header_download = None;
+                    }
+                    false => return,
+                }
+            }
+        }
+
+        // If a block download is in progress, assign its remaining block ranges to the idle peers.
+        if self.block_download.read().await.is_some() {
+            // Acquire the lock for block requests.
+            let _block_requests_lock = self.block_requests_lock.lock().await;
+            if self.schedule_block_requests().await {
+                return;
+            }
+        }
+
         // Ensure the ledger is not awaiting responses from outstanding block requests.
         if self.number_of_block_requests().await > 0 {
             return;
@@ -785,11 +1021,37 @@ impl<N: Network, E: Environment> Ledger<N, E> {
         let mut maximum_cumulative_weight = latest_cumulative_weight;
 
         // Check if any of the peers are ahead and have a larger block height.
-        if let Some((peer_ip, maximal_peer_is_on_fork, maximum_block_locators)) = find_maximal_peer::<N, E>(
-            &*self.peers_state.read().await,
-            &mut maximum_block_height,
-            &mut maximum_cumulative_weight,
-        ) {
+        // Peers that are deprioritized for their misbehavior are only selected if no other peer is ahead.
+        let maximal_peer = {
+            let peers_state = self.peers_state.read().await;
+            let peers_rtt = self.peers_rtt.read().await;
+            let deprioritized_peers = self.deprioritized_peers().await;
+            let preferred_peer = match deprioritized_peers.is_empty() {
+                true => None,
+                false => {
+                    let preferred_peers_state = peers_state
+                        .iter()
+                        .filter(|(peer_ip, _)| !deprioritized_peers.contains(peer_ip))
+                        .map(|(peer_ip, peer_state)| (*peer_ip, peer_state.clone()))
+                        .collect::<PeersState<N>>();
+                    find_maximal_peer::<N, E>(
+                        &preferred_peers_state,
+                        &*peers_rtt,
+                        &mut maximum_block_height,
+                        &mut maximum_cumulative_weight,
+                    )
+                }
+            };
+            preferred_peer.or_else(|| {
+                find_maximal_peer::<N, E>(
+                    &*peers_state,
+                    &*peers_rtt,
+                    &mut maximum_block_height,
+                    &mut maximum_cumulative_weight,
+                )
+            })
+        };
+        if let Some((peer_ip, maximal_peer_is_on_fork, maximum_block_locators)) = maximal_peer {
             // Case 1 - Ensure the peer has a heavier canonical chain than this ledger.
             // Note: this check is duplicated in `handle_block_requests`, as it is fast
             // and allows us to skip acquiring `_block_requests_lock`.
@@ -806,7 +1068,7 @@ impl<N: Network, E: Environment> Ledger<N, E> {
                 Ok(ret) => ret,
                 Err(error) => {
                     trace!("{}", error);
-                    self.add_failure(peer_ip, error).await;
+                    self.add_misbehavior(peer_ip, Misbehavior::MalformedMessage(error)).await;
                     return;
                 }
             };
@@ -836,58 +1098,368 @@ impl<N: Network, E: Environment> Ledger<N, E> {
                 }
             };
 
-            // Revert the ledger, if it is on a fork.
-            if ledger_is_on_fork {
-                // If the revert operation fails, abort.
-                if !self.revert_to_block_height(maximum_common_ancestor).await {
-                    warn!("Ledger failed to revert to block {}", maximum_common_ancestor);
+            // If the peer serves block headers, validate the block headers of its canonical chain before downloading its blocks.
+            let supports_headers_first = match self.peers_capabilities.read().await.get(&peer_ip) {
+                Some(capabilities) => capabilities.contains(Capabilities::HEADERS_FIRST),
+                None => false,
+            };
+            if supports_headers_first {
+                // Retrieve the block header that the canonical chain extends, and the anchor block header, if it is in this ledger.
+                let previous_block_header = match self.canon.get_block_header(start_block_height - 1) {
+                    Ok(block_header) => block_header,
+                    Err(error) => {
+                        warn!("{}", error);
+                        return;
+                    }
+                };
+                let anchor_block_header = match N::NETWORK_ID == 2 && start_block_height > snarkvm::dpc::testnet2::V12_UPGRADE_BLOCK_HEIGHT
+                {
+                    true => self.canon.get_block_header(snarkvm::dpc::testnet2::V12_UPGRADE_BLOCK_HEIGHT).ok(),
+                    false => None,
+                };
+
+                let header_download = HeaderDownload {
+                    peer_ip,
+                    chain: BlockHeaderChain::new(previous_block_header, anchor_block_header),
+                    start_block_height,
+                    end_block_height,
+                    block_locators: maximum_block_locators,
+                    ledger_is_on_fork,
+                    common_ancestor: maximum_common_ancestor,
+                    requested_at: Instant::now(),
+                };
+                self.request_block_headers(header_download).await;
+                return;
+            }
+
+            self.start_block_download(
+                peer_ip,
+                start_block_height,
+                end_block_height,
+                maximum_block_locators,
+                ledger_is_on_fork,
+                maximum_common_ancestor,
+                Default::default(),
+            )
+            .await;
+        }
+    }
+
+    ///
+    /// Starts downloading the blocks of the canonical chain of the given peer, from every peer that advertises it.
+    /// If the ledger is on a fork, it is first reverted to the common ancestor.
+    ///
+    /// The caller must hold the lock for block requests.
+    ///
+    #[allow(clippy::too_many_arguments)]
+    async fn start_block_download(
+        &self,
+        peer_ip: SocketAddr,
+        start_block_height: u32,
+        end_block_height: u32,
+        block_locators: BlockLocators<N>,
+        ledger_is_on_fork: bool,
+        common_ancestor: u32,
+        header_roots: BTreeMap<u32, N::BlockHeaderRoot>,
+    ) {
+        // Revert the ledger, if it is on a fork.
+        if ledger_is_on_fork {
+            // If the revert operation fails, abort.
+            if !self.revert_to_block_height(common_ancestor).await {
+                warn!("Ledger failed to revert to block {}", common_ancestor);
+                return;
+            }
+        }
+
+        debug!(
+            "Downloading blocks {} to {} from the canonical chain of {}",
+            start_block_height, end_block_height, peer_ip
+        );
+        *self.block_download.write().await = Some(BlockDownload {
+            scheduler: BlockDownloadScheduler::new(
+                start_block_height,
+                end_block_height,
+                E::maximum_block_request(),
+                E::maximum_block_request().saturating_mul(MAXIMUM_BLOCK_DOWNLOAD_RANGES),
+            ),
+            block_locators,
+            ledger_is_on_fork,
+            downloaded_blocks: Default::default(),
+            header_roots,
+        });
+        self.schedule_block_requests().await;
+    }
+
+    ///
+    /// Requests the next block headers of the given header download from its peer.
+    ///
+    async fn request_block_headers(&self, mut header_download: HeaderDownload<N>) {
+        let peer_ip = header_download.peer_ip;
+        let start_block_height = header_download.chain.latest_block_height() + 1;
+        let end_block_height = std::cmp::min(
+            header_download.end_block_height,
+            start_block_height.saturating_add(E::MAXIMUM_BLOCK_HEADERS_REQUEST - 1),
+        );
+
+        // Send a `BlockHeadersRequest` message to the peer.
+        debug!(
+            "Requesting block headers {} to {} from {}",
+            start_block_height, end_block_height, peer_ip
+        );
+        let request = PeersRequest::MessageSend(peer_ip, Message::BlockHeadersRequest(start_block_height, end_block_height));
+        if let Err(error) = self.peers_router.send(request).await {
+            warn!("[BlockHeadersRequest] {}", error);
+            return;
+        }
+
+        header_download.requested_at = Instant::now();
+        *self.header_download.write().await = Some(header_download);
+    }
+
+    ///
+    /// Validates the given block headers from a block headers response, and once the block headers
+    /// of the canonical chain have been validated, proceeds to download its blocks.
+    ///
+    async fn add_block_headers(&self, peer_ip: SocketAddr, block_headers: Vec<BlockHeader<N>>) {
+        // Acquire the lock for block requests.
+        let _block_requests_lock = self.block_requests_lock.lock().await;
+
+        // Ensure the block headers were requested from the peer.
+        let header_download = {
+            let mut header_download = self.header_download.write().await;
+
+            // Determine if the block headers respond to the pending block headers request to the peer.
+            let is_expected_response = match &*header_download {
+                Some(download) if download.peer_ip == peer_ip => match block_headers.first() {
+                    Some(block_header) => block_header.height() == download.chain.latest_block_height() + 1,
+                    None => true,
+                },
+                _ => false,
+            };
+            // Ignore a late response to a block headers request that has timed out, as the peer is slow rather than misbehaving.
+            let is_late_response = self.expired_header_requests.write().await.remove(&peer_ip) && !is_expected_response;
+            if is_late_response {
+                debug!("Ignoring a late block headers response from {}", peer_ip);
+                return;
+            }
+
+            match header_download.take() {
+                Some(download) if download.peer_ip == peer_ip => download,
+                download => {
+                    *header_download = download;
+                    drop(header_download);
+                    let misbehavior = Misbehavior::Spam("received an unrequested block headers response".to_string());
+                    self.add_misbehavior(peer_ip, misbehavior).await;
                     return;
                 }
             }
+        };
+
+        // If the peer no longer has the requested block headers, abort the header download.
+        if block_headers.is_empty() {
+            debug!("Peer {} did not return the requested block headers", peer_ip);
+            return;
+        }
+        // Ensure the peer did not return more block headers than permitted.
+        if block_headers.len() > E::MAXIMUM_BLOCK_HEADERS_REQUEST as usize {
+            let misbehavior = Misbehavior::MalformedMessage(format!("received {} block headers", block_headers.len()));
+            self.add_misbehavior(peer_ip, misbehavior).await;
+            return;
+        }
+
+        // Validate the block headers, without blocking the ledger.
+        let (header_download, result) = match task::spawn_blocking(move || {
+            let mut header_download = header_download;
+            let result = header_download.chain.extend(block_headers);
+            (header_download, result)
+        })
+        .await
+        {
+            Ok(output) => output,
+            Err(error) => {
+                warn!("[BlockHeadersResponse] {}", error);
+                return;
+            }
+        };
+        if let Err(error) = result {
+            warn!("Invalid block headers from {}: {}", peer_ip, error);
+            self.add_misbehavior(peer_ip, Misbehavior::InvalidBlock(error.to_string())).await;
+            return;
+        }
+
+        // Request the next block headers, until the chain reaches the end block height.
+        let end_block_height = header_download.end_block_height;
+        if header_download.chain.latest_block_height() < end_block_height {
+            self.request_block_headers(header_download).await;
+            return;
+        }
+
+        // Ensure the block headers match the canonical chain that the peer advertised in its block locators.
+        let advertised_header_root = match header_download.block_locators.get(&end_block_height) {
+            Some((_, Some(block_header))) => block_header.to_header_root().ok(),
+            _ => None,
+        };
+        if advertised_header_root.is_some() && advertised_header_root != header_download.chain.get_header_root(end_block_height) {
+            let misbehavior = Misbehavior::MalformedMessage("block headers do not match the block locators".to_string());
+            self.add_misbehavior(peer_ip, misbehavior).await;
+            return;
+        }
+
+        // Ensure the canonical chain is heavier than this ledger.
+        if header_download.chain.latest_cumulative_weight() <= self.canon.latest_cumulative_weight() {
+            debug!("Block headers from {} do not lead to a heavier canonical chain", peer_ip);
+            return;
+        }
+
+        debug!(
+            "Validated block headers {} to {} from {}",
+            header_download.start_block_height, end_block_height, peer_ip
+        );
+        let HeaderDownload {
+            start_block_height,
+            block_locators,
+            ledger_is_on_fork,
+            common_ancestor,
+            chain,
+            ..
+        } = header_download;
+        self.start_block_download(
+            peer_ip,
+            start_block_height,
+            end_block_height,
+            block_locators,
+            ledger_is_on_fork,
+            common_ancestor,
+            chain.into_header_roots(),
+        )
+        .await;
+    }
+
+    ///
+    /// Assigns the remaining block ranges of the block download to the idle peers that advertise
+    /// the same canonical chain, and sends them the corresponding block requests.
+    ///
+    /// Returns `false` if the block download has completed or stalled, in which case it is cleared.
+    /// The caller must hold the lock for block requests.
+    ///
+    async fn schedule_block_requests(&self) -> bool {
+        // Retrieve the latest block height of this ledger.
+        let latest_block_height = self.canon.latest_block_height();
+
+        let mut block_download = self.block_download.write().await;
+        let download = match &mut *block_download {
+            Some(download) => download,
+            None => return false,
+        };
 
-            // Send a `BlockRequest` message to the peer.
+        // Determine the peers that advertise the canonical chain being downloaded, and are ahead of this ledger.
+        // Peers that are deprioritized for their misbehavior are only selected if no other peer is suitable.
+        let mut sync_peers = self
+            .peers_state
+            .read()
+            .await
+            .iter()
+            .filter_map(|(peer_ip, peer_state)| match peer_state {
+                Some((_, _, _, block_height, block_locators))
+                    if *block_height > latest_block_height && is_same_canonical_chain(&download.block_locators, block_locators) =>
+                {
+                    Some((*peer_ip, *block_height))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let deprioritized_peers = self.deprioritized_peers().await;
+        if sync_peers.iter().any(|(peer_ip, _)| !deprioritized_peers.contains(peer_ip)) {
+            sync_peers.retain(|(peer_ip, _)| !deprioritized_peers.contains(peer_ip));
+        }
+
+        // Determine the sync peers that have no outstanding block requests.
+        let (number_of_block_requests, idle_peers) = {
+            let block_requests = self.block_requests.read().await;
+            let idle_peers = sync_peers
+                .into_iter()
+                .filter(|(peer_ip, _)| block_requests.get(peer_ip).map(|requests| requests.is_empty()).unwrap_or(false))
+                .collect::<Vec<_>>();
+            (block_requests.values().map(|requests| requests.len()).sum::<usize>(), idle_peers)
+        };
+
+        // If the download is complete, or no peer is able to continue it, clear the block download.
+        if number_of_block_requests == 0 && (download.scheduler.is_complete() || idle_peers.is_empty()) {
+            trace!("Block download to block {} has ended", download.scheduler.end_block_height());
+            *block_download = None;
+            return false;
+        }
+
+        // Determine the sync peers that serve blocks by the hash of their ancestor.
+        let hash_anchored_peers = self
+            .peers_capabilities
+            .read()
+            .await
+            .iter()
+            .filter(|(_, capabilities)| capabilities.contains(Capabilities::BLOCKS_BY_HASH))
+            .map(|(peer_ip, _)| *peer_ip)
+            .collect::<HashSet<_>>();
+
+        // Assign the next block ranges to the idle peers, including the expected block hashes if the ledger is on a fork.
+        let assignments = download
+            .scheduler
+            .assign(latest_block_height, &idle_peers)
+            .into_iter()
+            .map(|(peer_ip, start_block_height, end_block_height)| {
+                let block_requests = (start_block_height..=end_block_height)
+                    .map(|block_height| match download.ledger_is_on_fork {
+                        true => (block_height, download.block_locators.get_block_hash(block_height)),
+                        false => (block_height, None),
+                    })
+                    .collect::<Vec<_>>();
+
+                // Anchor the block range to the hash of its preceding block, if it is known, so that the peer
+                // only responds with blocks of the canonical chain that descend from it.
+                let previous_block_height = start_block_height.saturating_sub(1);
+                let previous_block_hash = match hash_anchored_peers.contains(&peer_ip) {
+                    true => download.block_locators.get_block_hash(previous_block_height).or_else(|| {
+                        match previous_block_height <= latest_block_height {
+                            true => self.canon.get_block_hash(previous_block_height).ok(),
+                            false => download
+                                .downloaded_blocks
+                                .get(&previous_block_height)
+                                .map(|(_, block)| block.hash()),
+                        }
+                    }),
+                    false => None,
+                };
+                let message = match previous_block_hash {
+                    Some(block_hash) => Message::BlockRequestByHash(block_hash, end_block_height - start_block_height + 1),
+                    None => Message::BlockRequest(start_block_height, end_block_height),
+                };
+                (peer_ip, start_block_height, end_block_height, message, block_requests)
+            })
+            .collect::<Vec<_>>();
+
+        // Release the lock over the block download.
+        drop(block_download);
+
+        for (peer_ip, start_block_height, end_block_height, message, block_requests) in assignments {
+            // Send a `BlockRequest` or `BlockRequestByHash` message to the peer.
             debug!("Requesting blocks {} to {} from {}", start_block_height, end_block_height, peer_ip);
-            let request = PeersRequest::MessageSend(peer_ip, Message::BlockRequest(start_block_height, end_block_height));
+            let request = PeersRequest::MessageSend(peer_ip, message);
             if let Err(error) = self.peers_router.send(request).await {
                 warn!("[BlockRequest] {}", error);
-                return;
+                if let Some(download) = &mut *self.block_download.write().await {
+                    download.scheduler.requeue(start_block_height..=end_block_height);
+                }
+                continue;
             }
 
-            // Filter out any pre-existing block requests for the peer.
-            let mut missing_block_requests = false;
-            let mut new_block_heights = Vec::new();
-            if let Some(block_requests) = self.block_requests.read().await.get(&peer_ip) {
-                for block_height in start_block_height..=end_block_height {
-                    if !block_requests.contains_key(&block_height.into()) {
-                        new_block_heights.push(block_height);
-                    }
-                }
-            } else {
-                self.add_failure(peer_ip, format!("Missing block requests for {}", peer_ip)).await;
-                missing_block_requests = true;
-            }
-
-            if !missing_block_requests && !new_block_heights.is_empty() {
-                // Log each block request to ensure the peer responds with all requested blocks.
-                if let Some(locked_block_requests) = self.block_requests.write().await.get_mut(&peer_ip) {
-                    for block_height in new_block_heights {
-                        // If the ledger is on a fork and was reverted, include the expected new block hash for the fork.
-                        match ledger_is_on_fork {
-                            true => {
-                                self.add_block_request(
-                                    peer_ip,
-                                    block_height,
-                                    maximum_block_locators.get_block_hash(block_height),
-                                    locked_block_requests,
-                                )
-                                .await
-                            }
-                            false => self.add_block_request(peer_ip, block_height, None, locked_block_requests).await,
-                        };
-                    }
+            // Log each block request to ensure the peer responds with all requested blocks.
+            if let Some(locked_block_requests) = self.block_requests.write().await.get_mut(&peer_ip) {
+                for (block_height, block_hash) in block_requests {
+                    self.add_block_request(peer_ip, block_height, block_hash, locked_block_requests)
+                        .await;
                 }
             }
         }
+        true
     }
 
     ///
@@ -909,7 +1481,10 @@ impl<N: Network, E: Environment> Ledger<N, E> {
     ) {
         match locked_block_requests.insert((block_height, block_hash).into(), Utc::now().timestamp()) {
             None => debug!("Requesting block {} from {}", block_height, peer_ip),
-            Some(_old_request) => self.add_failure(peer_ip, format!("Duplicate block request for {}", peer_ip)).await,
+            Some(_old_request) => {
+                self.add_misbehavior(peer_ip, Misbehavior::Spam(format!("duplicate block request for {}", peer_ip)))
+                    .await
+            }
         }
     }
 
@@ -930,7 +1505,8 @@ impl<N: Network, E: Environment> Ledger<N, E> {
     async fn remove_block_request(&self, peer_ip: SocketAddr, block_height: u32) -> bool {
         // Ensure the block height corresponds to a requested block.
         if !self.contains_block_request(peer_ip, block_height).await {
-            self.add_failure(peer_ip, "Received an invalid block response".to_string()).await;
+            self.add_misbehavior(peer_ip, Misbehavior::Spam("received an unrequested block response".to_string()))
+                .await;
             false
         } else {
             if let Some(requests) = self.block_requests.write().await.get_mut(&peer_ip) {
@@ -938,8 +1514,8 @@ impl<N: Network, E: Environment> Ledger<N, E> {
                 match is_success {
                     true => return true,
                     false => {
-                        self.add_failure(peer_ip, format!("Non-existent block request from {}", peer_ip))
-                            .await
+                        let misbehavior = Misbehavior::Spam(format!("non-existent block request from {}", peer_ip));
+                        self.add_misbehavior(peer_ip, misbehavior).await
                     }
                 }
             }
@@ -948,53 +1524,301 @@ impl<N: Network, E: Environment> Ledger<N, E> {
     }
 
     ///
-    /// Removes block requests that have expired.
+    /// Removes the outstanding block requests to the specified peer for the blocks descending from the given block hash,
+    /// as the peer has none of them, and re-queues them in the block download, if it exists.
+    /// On success, returns `true`, otherwise returns `false`.
     ///
-    async fn remove_expired_block_requests(&self) {
-        // Clear all block requests that have lived longer than `E::RADIO_SILENCE_IN_SECS`.
-        let now = Utc::now().timestamp();
-        self.block_requests.write().await.iter_mut().for_each(|(_peer, block_requests)| {
-            block_requests.retain(|_, time_of_request| now.saturating_sub(*time_of_request) < E::RADIO_SILENCE_IN_SECS as i64)
-        });
-    }
+    async fn remove_block_requests_not_found(&self, peer_ip: SocketAddr, block_hash: N::BlockHash) -> bool {
+        // Determine the block height of the given block hash, which is the preceding block of a block request,
+        // or the last block that the peer sent in response to it.
+        let block_height = match self.canon.get_block_height(&block_hash) {
+            Ok(block_height) => Some(block_height),
+            Err(_) => match &*self.block_download.read().await {
+                Some(download) => download
+                    .block_locators
+                    .iter()
+                    .find(|(_, (locator_hash, _))| *locator_hash == block_hash)
+                    .map(|(block_height, _)| *block_height)
+                    .or_else(|| {
+                        download
+                            .downloaded_blocks
+                            .iter()
+                            .find(|(_, (_, block))| block.hash() == block_hash)
+                            .map(|(block_height, _)| *block_height)
+                    }),
+                None => None,
+            },
+        };
 
-    ///
-    /// Adds the given failure message to the specified peer IP.
-    ///
-    async fn add_failure(&self, peer_ip: SocketAddr, failure: String) {
-        trace!("Adding failure for {}: {}", peer_ip, failure);
-        match self.failures.write().await.get_mut(&peer_ip) {
-            Some(failures) => failures.push((failure, Utc::now().timestamp())),
-            None => error!("Missing failure entry for {}", peer_ip),
+        // Remove the block requests for the blocks descending from the given block hash.
+        let block_heights = match (block_height, self.block_requests.write().await.get_mut(&peer_ip)) {
+            (Some(block_height), Some(requests)) => {
+                let block_heights = requests
+                    .keys()
+                    .map(|request| request.block_height)
+                    .filter(|requested_block_height| *requested_block_height > block_height)
+                    .collect::<Vec<_>>();
+                requests.retain(|request, _| request.block_height <= block_height);
+                block_heights
+            }
+            _ => vec![],
         };
+
+        // Ensure the peer has outstanding block requests.
+        if block_heights.is_empty() {
+            self.add_misbehavior(peer_ip, Misbehavior::Spam("received an unrequested block not found".to_string()))
+                .await;
+            return false;
+        }
+
+        debug!(
+            "Peer {} has no blocks descending from {}, re-queueing {} block requests",
+            peer_ip,
+            block_hash,
+            block_heights.len()
+        );
+        if let Some(block_download) = &mut *self.block_download.write().await {
+            block_download.scheduler.expire(peer_ip, block_heights);
+        }
+        true
     }
 
     ///
-    /// Removes failures that have expired.
+    /// Removes block requests that have expired, and re-queues them in the block download, if it exists.
     ///
-    async fn remove_expired_failures(&self) {
-        // Clear all failures that have lived longer than `E::FAILURE_EXPIRY_TIME_IN_SECS`.
+    async fn remove_expired_block_requests(&self) {
+        // Clear all block requests that have lived longer than `E::radio_silence_in_secs()`.
         let now = Utc::now().timestamp();
-        self.failures.write().await.iter_mut().for_each(|(_, failures)| {
-            failures.retain(|(_, time_of_fail)| now.saturating_sub(*time_of_fail) < E::FAILURE_EXPIRY_TIME_IN_SECS as i64)
+        let mut expired_block_requests = HashMap::<SocketAddr, Vec<u32>>::new();
+        self.block_requests.write().await.iter_mut().for_each(|(peer_ip, block_requests)| {
+            block_requests.retain(|request, time_of_request| {
+                let is_expired = now.saturating_sub(*time_of_request) >= E::radio_silence_in_secs() as i64;
+                if is_expired {
+                    expired_block_requests.entry(*peer_ip).or_default().push(request.block_height);
+                }
+                !is_expired
+            })
         });
+
+        // Re-queue the expired block requests, to be requested from a different peer.
+        if let Some(block_download) = &mut *self.block_download.write().await {
+            for (peer_ip, block_heights) in expired_block_requests {
+                debug!("{} block requests to {} have timed out", block_heights.len(), peer_ip);
+                block_download.scheduler.expire(peer_ip, block_heights);
+            }
+        }
+    }
+
+    ///
+    /// Adds the given misbehavior to the misbehavior score of the specified peer IP.
+    ///
+    async fn add_misbehavior(&self, peer_ip: SocketAddr, misbehavior: Misbehavior) {
+        let penalty = self
+            .misbehavior_scores
+            .write()
+            .await
+            .entry(peer_ip)
+            .or_default()
+            .add(&misbehavior, E::misbehavior_half_life_in_secs());
+        trace!("Adding misbehavior for {}: {} (penalty = {:?})", peer_ip, misbehavior, penalty);
     }
 
     ///
-    /// Disconnects from connected peers who exhibit frequent failures.
+    /// Returns the set of peers that are deprioritized for their misbehavior.
     ///
-    async fn disconnect_from_failing_peers(&self) {
-        let peers_to_disconnect = self
-            .failures
+    async fn deprioritized_peers(&self) -> HashSet<SocketAddr> {
+        self.misbehavior_scores
             .read()
             .await
             .iter()
-            .filter(|(_, failures)| failures.len() > E::MAXIMUM_NUMBER_OF_FAILURES)
+            .filter(|(_, score)| score.penalty(E::misbehavior_half_life_in_secs()) >= Penalty::Deprioritize)
             .map(|(peer_ip, _)| *peer_ip)
-            .collect::<Vec<_>>();
+            .collect()
+    }
+
+    ///
+    /// Disconnects from, or bans, connected peers whose misbehavior scores have reached the respective thresholds,
+    /// and removes the misbehavior scores that have fully decayed.
+    ///
+    async fn penalize_misbehaving_peers(&self) {
+        let half_life_in_secs = E::misbehavior_half_life_in_secs();
+
+        // Remove the misbehavior scores that have fully decayed.
+        self.misbehavior_scores
+            .write()
+            .await
+            .retain(|_, score| !score.is_negligible(half_life_in_secs));
+
+        // Determine the connected peers to penalize.
+        let peers_to_penalize = {
+            let peers_state = self.peers_state.read().await;
+            self.misbehavior_scores
+                .read()
+                .await
+                .iter()
+                .filter(|(peer_ip, _)| peers_state.contains_key(peer_ip))
+                .map(|(peer_ip, score)| (*peer_ip, score.penalty(half_life_in_secs)))
+                .filter(|(_, penalty)| *penalty >= Penalty::Disconnect)
+                .collect::<Vec<_>>()
+        };
+
+        for (peer_ip, penalty) in peers_to_penalize {
+            match penalty {
+                Penalty::Ban => self.disconnect_and_ban(peer_ip, "exceeded misbehavior limit").await,
+                _ => self.disconnect(peer_ip, "exceeded misbehavior limit").await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Client;
+    use snarkos_storage::storage::rocksdb::RocksDB;
+    use snarkvm::dpc::testnet2::Testnet2;
+
+    type TestLedger = Ledger<Testnet2, Client<Testnet2>>;
+    type TestPeersHandler = mpsc::Receiver<PeersRequest<Testnet2, Client<Testnet2>>>;
+
+    /// Initializes a new ledger, along with the receiver of its requests to the peers.
+    async fn new_ledger() -> (Arc<TestLedger>, TestPeersHandler) {
+        let (peers_router, peers_handler) = mpsc::channel(1024);
+        let path = tempfile::tempdir().expect("Failed to open temporary directory").into_path();
+        let ledger = TestLedger::open::<RocksDB, _>(&path, peers_router)
+            .await
+            .expect("Failed to initialize ledger");
+        (ledger, peers_handler)
+    }
+
+    /// Adds a connected peer at the given block height, on the canonical chain of the given block locators.
+    async fn add_peer(ledger: &TestLedger, peer_ip: SocketAddr, block_height: u32, block_locators: &BlockLocators<Testnet2>) {
+        ledger.initialize_peer(peer_ip).await;
+        let peer_state = (NodeType::Client, State::Ready, Some(false), block_height, block_locators.clone());
+        ledger.peers_state.write().await.insert(peer_ip, Some(peer_state));
+    }
+
+    /// Returns the block ranges that were requested from the peers, in order of their start block height.
+    fn requested_ranges(peers_handler: &mut TestPeersHandler) -> Vec<(SocketAddr, u32, u32)> {
+        let mut ranges = vec![];
+        while let Ok(request) = peers_handler.try_recv() {
+            if let PeersRequest::MessageSend(peer_ip, Message::BlockRequest(start_block_height, end_block_height)) = request {
+                ranges.push((peer_ip, start_block_height, end_block_height));
+            }
+        }
+        ranges.sort_by_key(|(_, start_block_height, _)| *start_block_height);
+        ranges
+    }
 
-        for peer_ip in peers_to_disconnect {
-            self.disconnect(peer_ip, "exceeded failure limit").await;
+    #[tokio::test]
+    async fn test_block_download_schedules_ranges_across_peers() {
+        let (ledger, mut peers_handler) = new_ledger().await;
+        let block_locators = ledger.canon.latest_block_locators();
+        let range_size = Client::<Testnet2>::maximum_block_request();
+
+        let (a, b, c): (SocketAddr, SocketAddr, SocketAddr) = (
+            "1.1.1.1:4132".parse().unwrap(),
+            "2.2.2.2:4132".parse().unwrap(),
+            "3.3.3.3:4132".parse().unwrap(),
+        );
+        add_peer(&ledger, a, 10 * range_size, &block_locators).await;
+        add_peer(&ledger, b, 10 * range_size, &block_locators).await;
+
+        // The download is split into ranges of at most `range_size` blocks, one for each idle peer.
+        ledger
+            .start_block_download(a, 1, 10 * range_size, block_locators.clone(), false, 0, Default::default())
+            .await;
+        let ranges = requested_ranges(&mut peers_handler);
+        assert_eq!(
+            vec![(1, range_size), (range_size + 1, 2 * range_size)],
+            ranges.iter().map(|(_, start, end)| (*start, *end)).collect::<Vec<_>>()
+        );
+        for (peer_ip, start_block_height, end_block_height) in &ranges {
+            let number_of_block_requests = (end_block_height - start_block_height + 1) as usize;
+            assert_eq!(number_of_block_requests, ledger.block_requests.read().await[peer_ip].len());
         }
+
+        // The range of a disconnected peer is re-assigned to the next idle peer.
+        let (disconnected_peer, start_block_height, end_block_height) = ranges[0];
+        ledger.remove_peer(&disconnected_peer).await;
+        add_peer(&ledger, c, 10 * range_size, &block_locators).await;
+        assert!(ledger.schedule_block_requests().await);
+        assert_eq!(
+            vec![(c, start_block_height, end_block_height)],
+            requested_ranges(&mut peers_handler)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_header_download_ignores_late_response() {
+        let (ledger, _peers_handler) = new_ledger().await;
+        let genesis_header = Testnet2::genesis_block().header().clone();
+        let peer_ip: SocketAddr = "1.1.1.1:4132".parse().unwrap();
+        ledger.initialize_peer(peer_ip).await;
+
+        // Start a header download from the peer, whose request has already timed out.
+        let radio_silence = Duration::from_secs(Client::<Testnet2>::radio_silence_in_secs() + 1);
+        *ledger.header_download.write().await = Some(HeaderDownload {
+            peer_ip,
+            chain: BlockHeaderChain::new(genesis_header.clone(), None),
+            start_block_height: 1,
+            end_block_height: 10,
+            block_locators: ledger.canon.latest_block_locators(),
+            ledger_is_on_fork: false,
+            common_ancestor: 0,
+            requested_at: Instant::now().checked_sub(radio_silence).unwrap(),
+        });
+        ledger.update_block_requests().await;
+        assert!(ledger.header_download.read().await.is_none());
+
+        // The late response to the request is ignored, without penalizing the peer.
+        ledger.add_block_headers(peer_ip, vec![genesis_header.clone()]).await;
+        assert!(!ledger.misbehavior_scores.read().await.contains_key(&peer_ip));
+
+        // Any further unrequested response is penalized.
+        ledger.add_block_headers(peer_ip, vec![genesis_header]).await;
+        assert!(ledger.misbehavior_scores.read().await.contains_key(&peer_ip));
+    }
+
+    #[tokio::test]
+    async fn test_block_not_found_removes_only_descending_requests() {
+        let (ledger, _peers_handler) = new_ledger().await;
+        let (a, b): (SocketAddr, SocketAddr) = ("1.1.1.1:4132".parse().unwrap(), "2.2.2.2:4132".parse().unwrap());
+        ledger.initialize_peer(a).await;
+
+        // Download blocks 1 to 5 from the peer, on a canonical chain that includes a block hash at block height 3.
+        let block_hash = Testnet2::genesis_block().previous_block_hash();
+        let mut block_locators = BTreeMap::new();
+        block_locators.insert(3, (block_hash, None));
+        let mut scheduler = BlockDownloadScheduler::new(1, 5, 5, 5);
+        assert_eq!(vec![(a, 1, 5)], scheduler.assign(0, &[(a, 5)]));
+        *ledger.block_download.write().await = Some(BlockDownload {
+            scheduler,
+            block_locators: BlockLocators::from(block_locators),
+            ledger_is_on_fork: false,
+            downloaded_blocks: Default::default(),
+            header_roots: Default::default(),
+        });
+        if let Some(block_requests) = ledger.block_requests.write().await.get_mut(&a) {
+            for block_height in 1..=5 {
+                ledger.add_block_request(a, block_height, None, block_requests).await;
+            }
+        }
+
+        // Only the block requests for the blocks descending from the given block hash are removed and re-queued.
+        assert!(ledger.remove_block_requests_not_found(a, block_hash).await);
+        let mut block_heights = ledger.block_requests.read().await[&a]
+            .keys()
+            .map(|request| request.block_height)
+            .collect::<Vec<_>>();
+        block_heights.sort_unstable();
+        assert_eq!(vec![1, 2, 3], block_heights);
+        if let Some(download) = &mut *ledger.block_download.write().await {
+            assert_eq!(vec![(b, 4, 5)], download.scheduler.assign(0, &[(b, 5)]));
+        }
+
+        // A block not found message for blocks that were not requested is penalized.
+        assert!(!ledger.remove_block_requests_not_found(a, block_hash).await);
+        assert!(ledger.misbehavior_scores.read().await.contains_key(&a));
     }
 }
@@ -15,7 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    helpers::{NodeType, State},
+    helpers::{Capabilities, NodePublicKey, NodeType, State},
     Environment,
 };
 use snarkos_storage::BlockLocators;
@@ -23,11 +23,31 @@ use snarkvm::{dpc::posw::PoSWProof, prelude::*};
 
 use ::bytes::{Buf, BytesMut};
 use anyhow::{anyhow, Result};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{marker::PhantomData, net::SocketAddr};
+use snow::TransportState;
+use std::{
+    io::{Read, Write},
+    marker::PhantomData,
+    net::SocketAddr,
+};
 use tokio::task;
 use tokio_util::codec::{Decoder, Encoder};
 
+/// The bit of the frame length marker that flags a compressed frame.
+const COMPRESSED_FRAME_FLAG: u32 = 1 << 31;
+///
+/// The maximum size of a compressed frame once inflated, which bounds the memory and work of inflating a frame in the decoder.
+///
+/// The encoder only compresses a message up to this size, and sends any larger message uncompressed, as its peer
+/// would reject the compressed frame. Such a message is still bounded by the maximum message size, like any other frame.
+///
+const MAXIMUM_INFLATED_FRAME_SIZE: usize = 4 * 1024 * 1024; // 4 MiB
+/// The maximum size of a Noise transport message, including its authentication tag.
+const NOISE_MAXIMUM_MESSAGE_SIZE: usize = 65535;
+/// The maximum number of framed bytes that are encrypted into one Noise transport message.
+const MAXIMUM_CHUNK_SIZE: usize = NOISE_MAXIMUM_MESSAGE_SIZE - 16;
+
 /// This object enables deferred deserialization / ahead-of-time serialization for objects that
 /// take a while to deserialize / serialize, in order to allow these operations to be non-blocking.
 #[derive(Clone, Debug)]
@@ -84,8 +104,8 @@ pub enum Message<N: Network, E: Environment> {
     BlockRequest(u32, u32),
     /// BlockResponse := (block)
     BlockResponse(Data<Block<N>>),
-    /// ChallengeRequest := (version, fork_depth, node_type, status, listener_port, nonce, cumulative_weight)
-    ChallengeRequest(u32, u32, NodeType, State, u16, u64, u128),
+    /// ChallengeRequest := (version, fork_depth, node_type, status, listener_port, nonce, cumulative_weight, capabilities, node_id)
+    ChallengeRequest(u32, u32, NodeType, State, u16, u64, u128, Capabilities, Option<NodePublicKey>),
     /// ChallengeResponse := (block_header)
     ChallengeResponse(Data<BlockHeader<N>>),
     /// Disconnect := ()
@@ -108,6 +128,26 @@ pub enum Message<N: Network, E: Environment> {
     PoolRequest(u64, Data<BlockTemplate<N>>),
     /// PoolResponse := (address, nonce, proof)
     PoolResponse(Address<N>, N::PoSWNonce, Data<PoSWProof<N>>),
+    /// EncryptionHandshake := (noise_message)
+    EncryptionHandshake(Vec<u8>),
+    /// BlockHeadersRequest := (start_block_height, end_block_height (inclusive))
+    BlockHeadersRequest(u32, u32),
+    /// BlockHeadersResponse := (\[block_header\])
+    BlockHeadersResponse(Data<Vec<BlockHeader<N>>>),
+    /// BlockRequestByHash := (block_hash, number_of_blocks)
+    BlockRequestByHash(N::BlockHash, u32),
+    /// BlockNotFound := (block_hash)
+    BlockNotFound(N::BlockHash),
+    /// BlockInventory := (block_height, block_hash)
+    BlockInventory(u32, N::BlockHash),
+    /// TransactionInventory := (\[transaction_id\])
+    TransactionInventory(Vec<N::TransactionID>),
+    /// InventoryRequest := (\[block_hash\], \[transaction_id\])
+    InventoryRequest(Vec<N::BlockHash>, Vec<N::TransactionID>),
+    /// MemoryPoolRequest := ()
+    MemoryPoolRequest,
+    /// MemoryPoolResponse := (\[transaction_id\])
+    MemoryPoolResponse(Vec<N::TransactionID>),
     /// Unused
     #[allow(unused)]
     Unused(PhantomData<E>),
@@ -132,6 +172,16 @@ impl<N: Network, E: Environment> Message<N, E> {
             Self::PoolRegister(..) => "PoolRegister",
             Self::PoolRequest(..) => "PoolRequest",
             Self::PoolResponse(..) => "PoolResponse",
+            Self::EncryptionHandshake(..) => "EncryptionHandshake",
+            Self::BlockHeadersRequest(..) => "BlockHeadersRequest",
+            Self::BlockHeadersResponse(..) => "BlockHeadersResponse",
+            Self::BlockRequestByHash(..) => "BlockRequestByHash",
+            Self::BlockNotFound(..) => "BlockNotFound",
+            Self::BlockInventory(..) => "BlockInventory",
+            Self::TransactionInventory(..) => "TransactionInventory",
+            Self::InventoryRequest(..) => "InventoryRequest",
+            Self::MemoryPoolRequest => "MemoryPoolRequest",
+            Self::MemoryPoolResponse(..) => "MemoryPoolResponse",
             Self::Unused(..) => "Unused",
         }
     }
@@ -155,18 +205,60 @@ impl<N: Network, E: Environment> Message<N, E> {
             Self::PoolRequest(..) => 12,
             Self::PoolResponse(..) => 13,
             Self::Unused(..) => 14,
+            Self::EncryptionHandshake(..) => 15,
+            Self::BlockHeadersRequest(..) => 16,
+            Self::BlockHeadersResponse(..) => 17,
+            Self::BlockRequestByHash(..) => 18,
+            Self::BlockNotFound(..) => 19,
+            Self::BlockInventory(..) => 20,
+            Self::TransactionInventory(..) => 21,
+            Self::InventoryRequest(..) => 22,
+            Self::MemoryPoolRequest => 23,
+            Self::MemoryPoolResponse(..) => 24,
         }
     }
 
+    /// Returns `true` if the message is large enough to benefit from compression.
+    #[inline]
+    pub fn is_compressible(&self) -> bool {
+        matches!(
+            self,
+            Self::BlockResponse(..) | Self::BlockHeadersResponse(..) | Self::Pong(..) | Self::UnconfirmedBlock(..)
+        )
+    }
+
     /// Returns the message data as bytes.
     #[inline]
     pub fn data(&self) -> Result<Vec<u8>> {
         match self {
             Self::BlockRequest(start_block_height, end_block_height) => Ok(to_bytes_le![start_block_height, end_block_height]?),
             Self::BlockResponse(block) => Ok(block.serialize_blocking()?),
-            Self::ChallengeRequest(version, fork_depth, node_type, status, listener_port, nonce, cumulative_weight) => Ok(
-                bincode::serialize(&(version, fork_depth, node_type, status, listener_port, nonce, cumulative_weight))?,
-            ),
+            Self::ChallengeRequest(
+                version,
+                fork_depth,
+                node_type,
+                status,
+                listener_port,
+                nonce,
+                cumulative_weight,
+                capabilities,
+                node_id,
+            ) => {
+                let fields = bincode::serialize(&(
+                    version,
+                    fork_depth,
+                    node_type,
+                    status,
+                    listener_port,
+                    nonce,
+                    cumulative_weight,
+                    capabilities,
+                ))?;
+                match node_id {
+                    Some(node_id) => Ok([fields, node_id.as_bytes().to_vec()].concat()),
+                    None => Ok(fields),
+                }
+            }
             Self::ChallengeResponse(block_header) => Ok(block_header.serialize_blocking()?),
             Self::Disconnect => Ok(vec![]),
             Self::PeerRequest => Ok(vec![]),
@@ -203,6 +295,16 @@ impl<N: Network, E: Environment> Message<N, E> {
                 proof.serialize_blocking()?,
             ]
             .concat()),
+            Self::EncryptionHandshake(noise_message) => Ok(bincode::serialize(noise_message)?),
+            Self::BlockHeadersRequest(start_block_height, end_block_height) => Ok(to_bytes_le![start_block_height, end_block_height]?),
+            Self::BlockHeadersResponse(block_headers) => Ok(block_headers.serialize_blocking()?),
+            Self::BlockRequestByHash(block_hash, number_of_blocks) => Ok(to_bytes_le![block_hash, number_of_blocks]?),
+            Self::BlockNotFound(block_hash) => Ok(block_hash.to_bytes_le()?),
+            Self::BlockInventory(block_height, block_hash) => Ok(to_bytes_le![block_height, block_hash]?),
+            Self::TransactionInventory(transaction_ids) => Ok(bincode::serialize(transaction_ids)?),
+            Self::InventoryRequest(block_hashes, transaction_ids) => Ok(bincode::serialize(&(block_hashes, transaction_ids))?),
+            Self::MemoryPoolRequest => Ok(vec![]),
+            Self::MemoryPoolResponse(transaction_ids) => Ok(bincode::serialize(transaction_ids)?),
             Self::Unused(_) => Ok(vec![]),
         }
     }
@@ -213,12 +315,17 @@ impl<N: Network, E: Environment> Message<N, E> {
         Ok([self.id().to_le_bytes().to_vec(), self.data()?].concat())
     }
 
+    ///
     /// Deserializes the given buffer into a message.
+    ///
+    /// The buffer may be received from an untrusted peer, so the length of every field is checked
+    /// before it is read, and any malformed buffer is rejected with an error instead of a panic.
+    ///
     #[inline]
-    pub fn deserialize(buffer: &[u8]) -> Result<Self> {
+    pub fn deserialize(buffer: &[u8]) -> Result<Self, MessageError> {
         // Ensure the buffer contains at least the length of an ID.
         if buffer.len() < 2 {
-            return Err(anyhow!("Invalid message buffer"));
+            return Err(MessageError::MissingId(buffer.len()));
         }
 
         // Split the buffer into the ID and data portion.
@@ -226,67 +333,265 @@ impl<N: Network, E: Environment> Message<N, E> {
 
         // Deserialize the data field.
         let message = match id {
-            0 => Self::BlockRequest(bincode::deserialize(&data[0..4])?, bincode::deserialize(&data[4..8])?),
+            0 => {
+                let mut reader = MessageReader::new("BlockRequest", data);
+                let message = Self::BlockRequest(reader.read(4)?, reader.read(4)?);
+                reader.finish()?;
+                message
+            }
             1 => Self::BlockResponse(Data::Buffer(data.to_vec())),
             2 => {
-                let (version, fork_depth, node_type, status, listener_port, nonce, cumulative_weight) = bincode::deserialize(data)?;
-                Self::ChallengeRequest(version, fork_depth, node_type, status, listener_port, nonce, cumulative_weight)
+                let mut reader = MessageReader::new("ChallengeRequest", data);
+                let (version, fork_depth, node_type, status, listener_port, nonce, cumulative_weight) = reader.read(42)?;
+                // The capabilities are appended to the challenge request, and peers that predate them advertise none.
+                let capabilities = match reader.is_empty() {
+                    true => Capabilities::empty(),
+                    false => reader.read(8)?,
+                };
+                // The node ID is appended to the capabilities, and peers that predate it advertise none.
+                let node_id = match reader.is_empty() {
+                    true => None,
+                    false => NodePublicKey::from_bytes(reader.read_bytes(32)?).ok(),
+                };
+                reader.finish()?;
+                Self::ChallengeRequest(
+                    version,
+                    fork_depth,
+                    node_type,
+                    status,
+                    listener_port,
+                    nonce,
+                    cumulative_weight,
+                    capabilities,
+                    node_id,
+                )
             }
             3 => Self::ChallengeResponse(Data::Buffer(data.to_vec())),
-            4 => match data.is_empty() {
-                true => Self::Disconnect,
-                false => return Err(anyhow!("Invalid 'Disconnect' message: {:?} {:?}", buffer, data)),
-            },
-            5 => match data.is_empty() {
-                true => Self::PeerRequest,
-                false => return Err(anyhow!("Invalid 'PeerRequest' message: {:?} {:?}", buffer, data)),
-            },
-            6 => Self::PeerResponse(bincode::deserialize(data)?),
+            4 => {
+                MessageReader::new("Disconnect", data).finish()?;
+                Self::Disconnect
+            }
+            5 => {
+                MessageReader::new("PeerRequest", data).finish()?;
+                Self::PeerRequest
+            }
+            6 => Self::PeerResponse(MessageReader::new("PeerResponse", data).read_remaining()?),
             7 => {
-                let (version, fork_depth, node_type, status, block_hash) = bincode::deserialize(&data[0..48])?;
-                let block_header = Data::Buffer(data[48..].to_vec());
+                let mut reader = MessageReader::new("Ping", data);
+                let (version, fork_depth, node_type, status, block_hash) = reader.read(48)?;
+                let block_header = reader.remaining_buffer();
 
                 Self::Ping(version, fork_depth, node_type, status, block_hash, block_header)
             }
             8 => {
-                let is_fork = match data[0] {
+                let mut reader = MessageReader::new("Pong", data);
+                let is_fork = match reader.read_bytes(1)?[0] {
                     0 => None,
                     1 => Some(true),
                     2 => Some(false),
-                    _ => return Err(anyhow!("Invalid 'Pong' message: {:?} {:?}", buffer, data)),
+                    flag => return Err(MessageError::InvalidForkFlag(flag)),
                 };
 
-                Self::Pong(is_fork, Data::Buffer(data[1..].to_vec()))
+                Self::Pong(is_fork, reader.remaining_buffer())
             }
-            9 => Self::UnconfirmedBlock(
-                bincode::deserialize(&data[0..4])?,
-                bincode::deserialize(&data[4..36])?,
-                Data::Buffer(data[36..].to_vec()),
-            ),
-            10 => Self::UnconfirmedTransaction(bincode::deserialize(data)?),
-            11 => Self::PoolRegister(bincode::deserialize(data)?),
-            12 => Self::PoolRequest(bincode::deserialize(&data[0..8])?, Data::Buffer(data[8..].to_vec())),
-            13 => Self::PoolResponse(
-                bincode::deserialize(&data[0..32])?,
-                bincode::deserialize(&data[32..64])?,
-                Data::Buffer(data[64..].to_vec()),
-            ),
-            _ => return Err(anyhow!("Invalid message ID {}", id)),
+            9 => {
+                let mut reader = MessageReader::new("UnconfirmedBlock", data);
+                Self::UnconfirmedBlock(reader.read(4)?, reader.read(32)?, reader.remaining_buffer())
+            }
+            10 => Self::UnconfirmedTransaction(MessageReader::new("UnconfirmedTransaction", data).read_remaining()?),
+            11 => Self::PoolRegister(MessageReader::new("PoolRegister", data).read_remaining()?),
+            12 => {
+                let mut reader = MessageReader::new("PoolRequest", data);
+                Self::PoolRequest(reader.read(8)?, reader.remaining_buffer())
+            }
+            13 => {
+                let mut reader = MessageReader::new("PoolResponse", data);
+                Self::PoolResponse(reader.read(32)?, reader.read(32)?, reader.remaining_buffer())
+            }
+            15 => Self::EncryptionHandshake(MessageReader::new("EncryptionHandshake", data).read_remaining()?),
+            16 => {
+                let mut reader = MessageReader::new("BlockHeadersRequest", data);
+                let message = Self::BlockHeadersRequest(reader.read(4)?, reader.read(4)?);
+                reader.finish()?;
+                message
+            }
+            17 => Self::BlockHeadersResponse(Data::Buffer(data.to_vec())),
+            18 => {
+                let mut reader = MessageReader::new("BlockRequestByHash", data);
+                let message = Self::BlockRequestByHash(reader.read(32)?, reader.read(4)?);
+                reader.finish()?;
+                message
+            }
+            19 => {
+                let mut reader = MessageReader::new("BlockNotFound", data);
+                let message = Self::BlockNotFound(reader.read(32)?);
+                reader.finish()?;
+                message
+            }
+            20 => {
+                let mut reader = MessageReader::new("BlockInventory", data);
+                let message = Self::BlockInventory(reader.read(4)?, reader.read(32)?);
+                reader.finish()?;
+                message
+            }
+            21 => Self::TransactionInventory(MessageReader::new("TransactionInventory", data).read_remaining()?),
+            22 => {
+                let (block_hashes, transaction_ids) = MessageReader::new("InventoryRequest", data).read_remaining()?;
+                Self::InventoryRequest(block_hashes, transaction_ids)
+            }
+            23 => {
+                MessageReader::new("MemoryPoolRequest", data).finish()?;
+                Self::MemoryPoolRequest
+            }
+            24 => Self::MemoryPoolResponse(MessageReader::new("MemoryPoolResponse", data).read_remaining()?),
+            _ => return Err(MessageError::InvalidId(id)),
         };
 
         Ok(message)
     }
 }
 
-impl<N: Network, E: Environment> Encoder<Message<N, E>> for Message<N, E> {
-    type Error = anyhow::Error;
+/// An error that occurs when deserializing a message received from a peer.
+#[derive(Debug, Error)]
+pub enum MessageError {
+    #[error("The message buffer is too short to contain a message ID ({0} bytes)")]
+    MissingId(usize),
+    #[error("Invalid message ID {0}")]
+    InvalidId(u16),
+    #[error("Invalid '{name}' message: expected at least {expected} bytes, found {found} bytes")]
+    Truncated { name: &'static str, expected: usize, found: usize },
+    #[error("Invalid '{name}' message: found {found} unexpected trailing bytes")]
+    TrailingBytes { name: &'static str, found: usize },
+    #[error("Invalid 'Pong' message: unknown fork flag {0}")]
+    InvalidForkFlag(u8),
+    #[error("Invalid '{name}' message: {source}")]
+    Malformed { name: &'static str, source: bincode::Error },
+}
 
-    fn encode(&mut self, message: Message<N, E>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+/// A reader over the data of a message, which checks that each field is in bounds before reading it.
+struct MessageReader<'a> {
+    /// The name of the message, for error reporting.
+    name: &'static str,
+    /// The data of the message.
+    data: &'a [u8],
+    /// The number of bytes that have been read.
+    offset: usize,
+}
+
+impl<'a> MessageReader<'a> {
+    /// Initializes a new reader over the given message data.
+    fn new(name: &'static str, data: &'a [u8]) -> Self {
+        Self { name, data, offset: 0 }
+    }
+
+    /// Returns the next `length` bytes, or an error if the message is truncated.
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], MessageError> {
+        let end = self.offset.saturating_add(length);
+        match self.data.get(self.offset..end) {
+            Some(bytes) => {
+                self.offset = end;
+                Ok(bytes)
+            }
+            None => Err(MessageError::Truncated {
+                name: self.name,
+                expected: end,
+                found: self.data.len(),
+            }),
+        }
+    }
+
+    /// Deserializes the next `length` bytes into a field.
+    fn read<T: DeserializeOwned>(&mut self, length: usize) -> Result<T, MessageError> {
+        let bytes = self.read_bytes(length)?;
+        bincode::deserialize(bytes).map_err(|source| MessageError::Malformed { name: self.name, source })
+    }
+
+    /// Deserializes the remaining bytes into a field.
+    fn read_remaining<T: DeserializeOwned>(&mut self) -> Result<T, MessageError> {
+        let bytes = self.read_bytes(self.data.len().saturating_sub(self.offset))?;
+        bincode::deserialize(bytes).map_err(|source| MessageError::Malformed { name: self.name, source })
+    }
+
+    /// Returns `true` if every byte of the message has been read.
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    /// Returns the remaining bytes, for deferred deserialization.
+    fn remaining_buffer<T: 'static + Serialize + DeserializeOwned + Send>(&mut self) -> Data<T> {
+        let bytes = &self.data[self.offset.min(self.data.len())..];
+        self.offset = self.data.len();
+        Data::Buffer(bytes.to_vec())
+    }
+
+    /// Ensures that every byte of the message has been read.
+    fn finish(&self) -> Result<(), MessageError> {
+        match self.data.len().saturating_sub(self.offset) {
+            0 => Ok(()),
+            found => Err(MessageError::TrailingBytes { name: self.name, found }),
+        }
+    }
+}
+
+///
+/// The codec that frames messages on a connection with a peer.
+///
+/// Each frame is prefixed with its length as a 4-byte little-endian marker. Once both peers have negotiated
+/// compression, the block and locator messages are deflated, and compressed frames are flagged in the
+/// highest bit of the length marker, which is never set as the maximum message size is below 2 GiB.
+///
+/// Once the connection is upgraded to an encrypted transport, the framed bytes are split into chunks
+/// of at most `MAXIMUM_CHUNK_SIZE` bytes, and each chunk is encrypted and prefixed with its 2-byte length.
+///
+pub struct MessageCodec<N: Network, E: Environment> {
+    /// If `true`, compressed frames may be sent and received on this connection.
+    compression: bool,
+    /// The Noise transport state, if the connection is encrypted.
+    cipher: Option<TransportState>,
+    /// The decrypted bytes that have not yet formed a complete frame.
+    plaintext: BytesMut,
+    _phantom: PhantomData<(N, E)>,
+}
+
+impl<N: Network, E: Environment> Default for MessageCodec<N, E> {
+    fn default() -> Self {
+        Self {
+            compression: false,
+            cipher: None,
+            plaintext: Default::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<N: Network, E: Environment> MessageCodec<N, E> {
+    /// Enables or disables compressed frames on this connection.
+    pub fn set_compression(&mut self, compression: bool) {
+        self.compression = compression;
+    }
+
+    /// Encrypts every subsequent frame on this connection with the given Noise transport state.
+    pub fn set_cipher(&mut self, cipher: TransportState) {
+        self.cipher = Some(cipher);
+    }
+
+    /// Returns `true` if the connection is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Returns the maximum size of a compressed frame once inflated.
+    fn maximum_inflated_size() -> usize {
+        MAXIMUM_INFLATED_FRAME_SIZE.min(E::maximum_message_size())
+    }
+
+    /// Writes the given message as a frame to the given buffer.
+    fn encode_frame(&self, message: Message<N, E>, dst: &mut BytesMut) -> Result<()> {
         // Serialize the message into a buffer.
-        let buffer = message.serialize()?;
+        let mut buffer = message.serialize()?;
 
         // Ensure the message does not exceed the maximum length limit.
-        if buffer.len() > E::MAXIMUM_MESSAGE_SIZE {
+        if buffer.len() > E::maximum_message_size() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("Frame of length {} is too large.", buffer.len()),
@@ -294,9 +599,19 @@ impl<N: Network, E: Environment> Encoder<Message<N, E>> for Message<N, E> {
             .into());
         }
 
+        // Compress the message, if compression is enabled and it reduces the size of the frame.
+        let mut compressed_flag = 0;
+        if self.compression && message.is_compressible() && buffer.len() <= Self::maximum_inflated_size() {
+            let compressed = compress(&buffer)?;
+            if compressed.len() < buffer.len() {
+                buffer = compressed;
+                compressed_flag = COMPRESSED_FRAME_FLAG;
+            }
+        }
+
         // Convert the length into a byte array.
         // The cast to u32 cannot overflow due to the length check above.
-        let len_slice = u32::to_le_bytes(buffer.len() as u32);
+        let len_slice = u32::to_le_bytes(buffer.len() as u32 | compressed_flag);
 
         // Reserve space in the buffer.
         dst.reserve(4 + buffer.len());
@@ -306,13 +621,9 @@ impl<N: Network, E: Environment> Encoder<Message<N, E>> for Message<N, E> {
         dst.extend_from_slice(&buffer);
         Ok(())
     }
-}
-
-impl<N: Network, E: Environment> Decoder for Message<N, E> {
-    type Error = std::io::Error;
-    type Item = Message<N, E>;
 
-    fn decode(&mut self, source: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+    /// Reads the next frame from the given buffer into a message, if the full frame has arrived.
+    fn decode_frame(&self, source: &mut BytesMut) -> std::io::Result<Option<Message<N, E>>> {
         // Ensure there is enough bytes to read the length marker.
         if source.len() < 4 {
             return Ok(None);
@@ -321,17 +632,27 @@ impl<N: Network, E: Environment> Decoder for Message<N, E> {
         // Read the length marker.
         let mut length_bytes = [0u8; 4];
         length_bytes.copy_from_slice(&source[..4]);
-        let length = u32::from_le_bytes(length_bytes) as usize;
+        let length_marker = u32::from_le_bytes(length_bytes);
+        let is_compressed = length_marker & COMPRESSED_FRAME_FLAG != 0;
+        let length = (length_marker & !COMPRESSED_FRAME_FLAG) as usize;
 
         // Check that the length is not too large to avoid a denial of
         // service attack where the node server runs out of memory.
-        if length > E::MAXIMUM_MESSAGE_SIZE {
+        if length > E::maximum_message_size() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("Frame of length {} is too large.", length),
             ));
         }
 
+        // Ensure compression was negotiated, before accepting a compressed frame.
+        if is_compressed && !self.compression {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Received a compressed frame without negotiating compression",
+            ));
+        }
+
         if source.len() < 4 + length {
             // The full message has not yet arrived.
             //
@@ -344,14 +665,383 @@ impl<N: Network, E: Environment> Decoder for Message<N, E> {
         }
 
         // Convert the buffer to a message, or fail if it is not valid.
-        let message = match Message::deserialize(&source[4..][..length]) {
-            Ok(message) => Ok(Some(message)),
-            Err(error) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+        let frame = &source[4..][..length];
+        let message = match is_compressed {
+            true => decompress(frame, Self::maximum_inflated_size()).and_then(|buffer| deserialize_frame(&buffer)),
+            false => deserialize_frame(frame),
         };
 
         // Use `advance` to modify the source such that it no longer contains this frame.
         source.advance(4 + length);
 
+        message.map(Some)
+    }
+}
+
+impl<N: Network, E: Environment> Encoder<Message<N, E>> for MessageCodec<N, E> {
+    type Error = anyhow::Error;
+
+    fn encode(&mut self, message: Message<N, E>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if self.cipher.is_none() {
+            return self.encode_frame(message, dst);
+        }
+
+        // Frame the message, before encrypting it.
+        let mut frame = BytesMut::new();
+        self.encode_frame(message, &mut frame)?;
+
+        if let Some(cipher) = &mut self.cipher {
+            let mut ciphertext = vec![0u8; NOISE_MAXIMUM_MESSAGE_SIZE];
+            for chunk in frame.chunks(MAXIMUM_CHUNK_SIZE) {
+                let length = cipher.write_message(chunk, &mut ciphertext)?;
+                // The cast to u16 cannot overflow, as a Noise message is at most 65535 bytes.
+                dst.reserve(2 + length);
+                dst.extend_from_slice(&(length as u16).to_le_bytes());
+                dst.extend_from_slice(&ciphertext[..length]);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<N: Network, E: Environment> Decoder for MessageCodec<N, E> {
+    type Error = std::io::Error;
+    type Item = Message<N, E>;
+
+    fn decode(&mut self, source: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let cipher = match &mut self.cipher {
+            Some(cipher) => cipher,
+            None => return self.decode_frame(source),
+        };
+
+        // Decrypt every chunk that has fully arrived into the plaintext buffer.
+        let mut plaintext = vec![0u8; NOISE_MAXIMUM_MESSAGE_SIZE];
+        while source.len() >= 2 {
+            let length = u16::from_le_bytes([source[0], source[1]]) as usize;
+            if source.len() < 2 + length {
+                source.reserve(2 + length - source.len());
+                break;
+            }
+
+            let plaintext_length = cipher
+                .read_message(&source[2..][..length], &mut plaintext)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Failed to decrypt a frame: {}", error)))?;
+            self.plaintext.extend_from_slice(&plaintext[..plaintext_length]);
+            source.advance(2 + length);
+        }
+
+        // Read the next frame from the decrypted bytes.
+        let mut decrypted = std::mem::take(&mut self.plaintext);
+        let message = self.decode_frame(&mut decrypted);
+        self.plaintext = decrypted;
         message
     }
 }
+
+/// Deserializes the given frame into a message, or fails with an `InvalidData` error if it is not valid.
+fn deserialize_frame<N: Network, E: Environment>(frame: &[u8]) -> std::io::Result<Message<N, E>> {
+    Message::deserialize(frame).map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Deflates the given buffer.
+fn compress(buffer: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::with_capacity(buffer.len() / 2), Compression::default());
+    encoder.write_all(buffer)?;
+    encoder.finish()
+}
+
+/// Inflates the given frame, or fails if the inflated frame exceeds the given maximum size.
+fn decompress(frame: &[u8], maximum_size: usize) -> std::io::Result<Vec<u8>> {
+    // Inflate at most one byte past the maximum size, to avoid a denial of service attack
+    // where a small frame inflates until the node server runs out of memory.
+    let mut buffer = Vec::new();
+    DeflateDecoder::new(frame)
+        .take(maximum_size as u64 + 1)
+        .read_to_end(&mut buffer)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    match buffer.len() > maximum_size {
+        true => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Compressed frame inflates beyond {} bytes", maximum_size),
+        )),
+        false => Ok(buffer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        environment::Client,
+        helpers::{NodeKey, NOISE_PARAMS},
+    };
+    use snarkvm::dpc::testnet2::Testnet2;
+
+    type TestMessage = Message<Testnet2, Client<Testnet2>>;
+
+    #[test]
+    fn test_deserialize_truncated() {
+        let genesis_block = Testnet2::genesis_block();
+        let transaction_id = genesis_block.transactions().first().unwrap().transaction_id();
+        let messages = vec![
+            TestMessage::BlockRequest(1, 2),
+            TestMessage::BlockHeadersRequest(1, 2),
+            TestMessage::BlockHeadersResponse(Data::Object(vec![genesis_block.header().clone()])),
+            TestMessage::BlockRequestByHash(genesis_block.hash(), 2),
+            TestMessage::BlockNotFound(genesis_block.hash()),
+            TestMessage::BlockInventory(0, genesis_block.hash()),
+            TestMessage::TransactionInventory(vec![transaction_id]),
+            TestMessage::InventoryRequest(vec![genesis_block.hash()], vec![transaction_id]),
+            TestMessage::MemoryPoolResponse(vec![transaction_id]),
+            TestMessage::ChallengeRequest(
+                12,
+                4096,
+                NodeType::Client,
+                State::Ready,
+                4132,
+                1,
+                0,
+                Capabilities::from_bits(5),
+                Some(NodeKey::new().unwrap().public_key()),
+            ),
+            TestMessage::PeerResponse(vec!["127.0.0.1:4132".parse().unwrap(), "[2001:db8::1]:4132".parse().unwrap()]),
+            TestMessage::Ping(
+                12,
+                4096,
+                NodeType::Client,
+                State::Ready,
+                genesis_block.hash(),
+                Data::Object(genesis_block.header().clone()),
+            ),
+            TestMessage::Pong(Some(true), Data::Object(BlockLocators::from(Default::default()))),
+            TestMessage::UnconfirmedBlock(0, genesis_block.hash(), Data::Object(genesis_block.clone())),
+        ];
+
+        for message in messages {
+            let buffer = message.serialize().unwrap();
+            assert_eq!(message.id(), TestMessage::deserialize(&buffer).unwrap().id());
+
+            // Ensure every truncation of the message is rejected or deferred, instead of panicking.
+            for length in 0..buffer.len() {
+                let _ = TestMessage::deserialize(&buffer[..length]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_request_by_hash() {
+        let block_hash = Testnet2::genesis_block().hash();
+
+        let buffer = TestMessage::BlockRequestByHash(block_hash, 250).serialize().unwrap();
+        match TestMessage::deserialize(&buffer).unwrap() {
+            TestMessage::BlockRequestByHash(decoded, number_of_blocks) => assert_eq!((block_hash, 250), (decoded, number_of_blocks)),
+            _ => panic!("Failed to deserialize the block request by hash"),
+        }
+
+        let buffer = TestMessage::BlockNotFound(block_hash).serialize().unwrap();
+        match TestMessage::deserialize(&buffer).unwrap() {
+            TestMessage::BlockNotFound(decoded) => assert_eq!(block_hash, decoded),
+            _ => panic!("Failed to deserialize the block not found"),
+        }
+    }
+
+    #[test]
+    fn test_peer_response_ipv6() {
+        let peer_ips: Vec<SocketAddr> = vec![
+            "127.0.0.1:4132".parse().unwrap(),
+            "[2001:db8::1]:4132".parse().unwrap(),
+            "[::ffff:1.2.3.4]:4133".parse().unwrap(),
+        ];
+        let buffer = TestMessage::PeerResponse(peer_ips.clone()).serialize().unwrap();
+        match TestMessage::deserialize(&buffer).unwrap() {
+            TestMessage::PeerResponse(decoded) => assert_eq!(peer_ips, decoded),
+            _ => panic!("Failed to deserialize the peer response"),
+        }
+    }
+
+    #[test]
+    fn test_challenge_request_extensions() {
+        let node_id = NodeKey::new().unwrap().public_key();
+        let message = TestMessage::ChallengeRequest(
+            12,
+            4096,
+            NodeType::Miner,
+            State::Ready,
+            4132,
+            1,
+            0,
+            Capabilities::from_bits(3),
+            Some(node_id),
+        );
+        match TestMessage::deserialize(&message.serialize().unwrap()) {
+            Ok(TestMessage::ChallengeRequest(.., capabilities, Some(decoded))) => {
+                assert_eq!(Capabilities::from_bits(3), capabilities);
+                assert_eq!(node_id, decoded);
+            }
+            _ => panic!("Failed to deserialize the challenge request"),
+        }
+
+        // A challenge request from a peer that predates capabilities advertises none, and no node ID.
+        let legacy = [
+            2u16.to_le_bytes().to_vec(),
+            bincode::serialize(&(12u32, 4096u32, NodeType::Miner, State::Ready, 4132u16, 1u64, 0u128)).unwrap(),
+        ]
+        .concat();
+        match TestMessage::deserialize(&legacy) {
+            Ok(TestMessage::ChallengeRequest(.., capabilities, None)) => assert!(capabilities.is_empty()),
+            _ => panic!("Failed to deserialize the legacy challenge request"),
+        }
+
+        // A truncated node ID is rejected.
+        let buffer = message.serialize().unwrap();
+        assert!(matches!(
+            TestMessage::deserialize(&buffer[..buffer.len() - 1]),
+            Err(MessageError::Truncated { .. })
+        ));
+    }
+
+    #[test]
+    fn test_codec_compression() {
+        let genesis_block = Testnet2::genesis_block();
+        let message = TestMessage::BlockResponse(Data::Object(genesis_block.clone()));
+
+        // Construct a compressed frame of the message.
+        let compressed = compress(&message.serialize().unwrap()).unwrap();
+        let mut frame = BytesMut::new();
+        frame.extend_from_slice(&(compressed.len() as u32 | COMPRESSED_FRAME_FLAG).to_le_bytes());
+        frame.extend_from_slice(&compressed);
+
+        // A connection that has not negotiated compression rejects the frame.
+        let mut codec = MessageCodec::<Testnet2, Client<Testnet2>>::default();
+        assert!(codec.decode(&mut frame.clone()).is_err());
+
+        codec.set_compression(true);
+        match codec.decode(&mut frame).unwrap() {
+            Some(TestMessage::BlockResponse(block)) => assert_eq!(genesis_block, &block.deserialize_blocking().unwrap()),
+            _ => panic!("Failed to decode the compressed frame"),
+        }
+        assert!(frame.is_empty());
+
+        // Messages round trip through an encoder with compression enabled.
+        let mut buffer = BytesMut::new();
+        codec.encode(message, &mut buffer).unwrap();
+        codec.encode(TestMessage::PeerRequest, &mut buffer).unwrap();
+        assert!(matches!(codec.decode(&mut buffer), Ok(Some(TestMessage::BlockResponse(..)))));
+        assert!(matches!(codec.decode(&mut buffer), Ok(Some(TestMessage::PeerRequest))));
+
+        // A compressed frame that inflates beyond the maximum inflated size is rejected.
+        let compressed = compress(&vec![0u8; MAXIMUM_INFLATED_FRAME_SIZE + 1]).unwrap();
+        let mut frame = BytesMut::new();
+        frame.extend_from_slice(&(compressed.len() as u32 | COMPRESSED_FRAME_FLAG).to_le_bytes());
+        frame.extend_from_slice(&compressed);
+        assert!(codec.decode(&mut frame).is_err());
+    }
+
+    #[test]
+    fn test_codec_sends_large_frames_uncompressed() {
+        // Construct a compressible message that is larger than the maximum inflated size.
+        let block_header = Testnet2::genesis_block().header().clone();
+        let block_headers = vec![block_header; MAXIMUM_INFLATED_FRAME_SIZE / 256];
+        let message = TestMessage::BlockHeadersResponse(Data::Object(block_headers.clone()));
+        assert!(message.serialize().unwrap().len() > MAXIMUM_INFLATED_FRAME_SIZE);
+
+        let mut codec = MessageCodec::<Testnet2, Client<Testnet2>>::default();
+        codec.set_compression(true);
+
+        // The message is sent uncompressed, even though compression is enabled.
+        let mut buffer = BytesMut::new();
+        codec.encode(message, &mut buffer).unwrap();
+        let length_marker = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+        assert_eq!(0, length_marker & COMPRESSED_FRAME_FLAG);
+        assert_eq!(buffer.len() - 4, length_marker as usize);
+
+        // The message round trips through the decoder.
+        match codec.decode(&mut buffer).unwrap() {
+            Some(TestMessage::BlockHeadersResponse(decoded)) => assert_eq!(block_headers, decoded.deserialize_blocking().unwrap()),
+            _ => panic!("Failed to decode the uncompressed frame"),
+        }
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_codec_encryption() {
+        let (local_key, peer_key) = (NodeKey::new().unwrap(), NodeKey::new().unwrap());
+        let builder = |node_key: &NodeKey| snow::Builder::new(NOISE_PARAMS.parse().unwrap()).local_private_key(node_key.private_key());
+        let mut initiator = builder(&local_key).build_initiator().unwrap();
+        let mut responder = builder(&peer_key).build_responder().unwrap();
+
+        // Perform the Noise handshake in memory.
+        let (mut buffer, mut payload) = (vec![0u8; NOISE_MAXIMUM_MESSAGE_SIZE], vec![0u8; NOISE_MAXIMUM_MESSAGE_SIZE]);
+        while !initiator.is_handshake_finished() {
+            let (sender, receiver) = match initiator.is_my_turn() {
+                true => (&mut initiator, &mut responder),
+                false => (&mut responder, &mut initiator),
+            };
+            let length = sender.write_message(&[], &mut buffer).unwrap();
+            receiver.read_message(&buffer[..length], &mut payload).unwrap();
+        }
+
+        let mut encoder = MessageCodec::<Testnet2, Client<Testnet2>>::default();
+        let mut decoder = MessageCodec::<Testnet2, Client<Testnet2>>::default();
+        encoder.set_cipher(initiator.into_transport_mode().unwrap());
+        decoder.set_cipher(responder.into_transport_mode().unwrap());
+
+        // A message that spans several encrypted chunks is reassembled.
+        let peer_ips = (0..10_000u32)
+            .map(|i| SocketAddr::from((i.to_le_bytes(), 4132)))
+            .collect::<Vec<_>>();
+        let mut frame = BytesMut::new();
+        encoder.encode(TestMessage::PeerResponse(peer_ips.clone()), &mut frame).unwrap();
+        encoder.encode(TestMessage::PeerRequest, &mut frame).unwrap();
+        assert!(frame.len() > 2 * MAXIMUM_CHUNK_SIZE);
+
+        // The ciphertext is decrypted incrementally, as it arrives.
+        let mut source = frame.split_to(MAXIMUM_CHUNK_SIZE);
+        assert!(decoder.decode(&mut source).unwrap().is_none());
+        source.extend_from_slice(&frame);
+        match decoder.decode(&mut source).unwrap() {
+            Some(TestMessage::PeerResponse(decoded)) => assert_eq!(peer_ips, decoded),
+            _ => panic!("Failed to decode the encrypted frame"),
+        }
+        assert!(matches!(decoder.decode(&mut source), Ok(Some(TestMessage::PeerRequest))));
+
+        // A tampered ciphertext is rejected.
+        let mut frame = BytesMut::new();
+        encoder.encode(TestMessage::PeerRequest, &mut frame).unwrap();
+        frame[2] ^= 1;
+        assert!(decoder.decode(&mut frame).is_err());
+    }
+
+    #[test]
+    fn test_decompress_limit() {
+        let buffer = vec![0u8; 1024 * 1024];
+        let compressed = compress(&buffer).unwrap();
+        assert!(compressed.len() < 16 * 1024);
+
+        assert_eq!(buffer, decompress(&compressed, buffer.len()).unwrap());
+        assert!(decompress(&compressed, buffer.len() - 1).is_err());
+        assert!(decompress(&[0xff; 16], buffer.len()).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        assert!(matches!(TestMessage::deserialize(&[0]), Err(MessageError::MissingId(1))));
+        assert!(matches!(TestMessage::deserialize(&[99, 0]), Err(MessageError::InvalidId(99))));
+        assert!(matches!(
+            TestMessage::deserialize(&[0, 0, 1, 0, 0]),
+            Err(MessageError::Truncated { .. })
+        ));
+        assert!(matches!(
+            TestMessage::deserialize(&[4, 0, 0]),
+            Err(MessageError::TrailingBytes { .. })
+        ));
+        assert!(matches!(TestMessage::deserialize(&[7, 0]), Err(MessageError::Truncated { .. })));
+        assert!(matches!(TestMessage::deserialize(&[8, 0]), Err(MessageError::Truncated { .. })));
+        assert!(matches!(
+            TestMessage::deserialize(&[8, 0, 3]),
+            Err(MessageError::InvalidForkFlag(3))
+        ));
+        assert!(matches!(TestMessage::deserialize(&[13, 0, 0]), Err(MessageError::Truncated { .. })));
+    }
+}
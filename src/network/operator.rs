@@ -15,7 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    helpers::NodeType,
+    helpers::{Misbehavior, NodeType},
     Data,
     Environment,
     LedgerReader,
@@ -279,7 +279,12 @@ impl<N: Network, E: Environment> Operator<N, E> {
                     // Ensure the given nonce from the prover is new.
                     if self.known_nonces.read().await.contains(&nonce) {
                         warn!("[PoolResponse] Peer {} sent a duplicate share", peer_ip);
-                        // TODO (julesdesmit): punish?
+                        // Route a `Failure` to the ledger.
+                        let misbehavior =
+                            Misbehavior::DuplicateShare(format!("duplicate nonce for block {}", block_template.block_height()));
+                        if let Err(error) = self.ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
+                            warn!("[Failure] {}", error);
+                        }
                         return;
                     }
 
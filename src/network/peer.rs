@@ -15,13 +15,15 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    helpers::{NodeType, State, Status},
+    helpers::{canonical_socket_addr, Capabilities, Misbehavior, NodeKey, NodePublicKey, NodeType, State, Status, NOISE_PARAMS},
     network::{
+        is_trusted_peer,
         ConnectionResult,
         LedgerReader,
         LedgerRequest,
         LedgerRouter,
         Message,
+        MessageCodec,
         OperatorRequest,
         OperatorRouter,
         PeersRequest,
@@ -41,7 +43,12 @@ use std::{
     net::SocketAddr,
     time::{Duration, Instant, SystemTime},
 };
-use tokio::{net::TcpStream, sync::mpsc, task, time::timeout};
+use tokio::{
+    net::TcpStream,
+    sync::mpsc,
+    task,
+    time::{interval, timeout},
+};
 use tokio_stream::StreamExt;
 use tokio_util::codec::Framed;
 
@@ -50,6 +57,83 @@ pub(crate) type OutboundRouter<N, E> = mpsc::Sender<Message<N, E>>;
 /// Shorthand for the child half of the `Peer` outbound message channel.
 type OutboundHandler<N, E> = mpsc::Receiver<Message<N, E>>;
 
+/// The interval at which pending transaction IDs are announced to a peer, in milliseconds.
+const TRANSACTION_INVENTORY_INTERVAL_IN_MILLIS: u64 = 500;
+
+///
+/// The inventory announced to a peer.
+///
+/// Announced blocks are retained until the peer requests them, while announced transactions are
+/// served from the memory pool, so only their IDs are kept, until they are announced in a batch.
+///
+struct Inventory<N: Network, E: Environment> {
+    /// The map of block hashes announced to the peer, to their announcement timestamp and block message.
+    announced_blocks: HashMap<N::BlockHash, (Instant, Message<N, E>)>,
+    /// The list of transaction IDs that are pending announcement to the peer.
+    pending_transactions: Vec<N::TransactionID>,
+}
+
+impl<N: Network, E: Environment> Default for Inventory<N, E> {
+    fn default() -> Self {
+        Self {
+            announced_blocks: Default::default(),
+            pending_transactions: Default::default(),
+        }
+    }
+}
+
+impl<N: Network, E: Environment> Inventory<N, E> {
+    ///
+    /// Returns the inventory announcement of the given unconfirmed block or transaction message, if it is ready to be sent.
+    /// Unconfirmed transactions are batched, and announced once `E::MAXIMUM_INVENTORY_ITEMS` are pending.
+    /// Any other message is returned unchanged.
+    ///
+    fn announce(&mut self, message: Message<N, E>) -> Option<Message<N, E>> {
+        // Remove the announcements that are older than the radio silence period, as the peer did not request them.
+        let radio_silence = Duration::from_secs(E::radio_silence_in_secs());
+        self.announced_blocks
+            .retain(|_, (announced_at, _)| announced_at.elapsed() < radio_silence);
+
+        match &message {
+            Message::UnconfirmedBlock(block_height, block_hash, _) => {
+                let (block_height, block_hash) = (*block_height, *block_hash);
+                self.announced_blocks.insert(block_hash, (Instant::now(), message));
+                Some(Message::BlockInventory(block_height, block_hash))
+            }
+            Message::UnconfirmedTransaction(transaction) => {
+                self.pending_transactions.push(transaction.transaction_id());
+                match self.pending_transactions.len() >= E::MAXIMUM_INVENTORY_ITEMS {
+                    true => self.take_transactions(),
+                    false => None,
+                }
+            }
+            _ => Some(message),
+        }
+    }
+
+    ///
+    /// Returns the announcement of the pending transaction IDs, if there are any.
+    ///
+    fn take_transactions(&mut self) -> Option<Message<N, E>> {
+        match self.pending_transactions.is_empty() {
+            true => None,
+            false => Some(Message::TransactionInventory(std::mem::take(&mut self.pending_transactions))),
+        }
+    }
+
+    ///
+    /// Returns the announced block messages for the given block hashes.
+    /// Announcements that have since been removed are skipped, and the peer requests them from another peer.
+    ///
+    fn take_blocks(&mut self, block_hashes: &[N::BlockHash]) -> Vec<Message<N, E>> {
+        block_hashes
+            .iter()
+            .filter_map(|block_hash| self.announced_blocks.remove(block_hash))
+            .map(|(_, message)| message)
+            .collect()
+    }
+}
+
 ///
 /// The state for each connected client.
 ///
@@ -62,12 +146,18 @@ pub(crate) struct Peer<N: Network, E: Environment> {
     node_type: NodeType,
     /// The node type of the peer.
     status: Status,
+    /// The protocol extensions that are supported by both this node and the peer.
+    capabilities: Capabilities,
     /// The block header of the peer.
     block_header: BlockHeader<N>,
     /// The timestamp of the last message received from this peer.
     last_seen: Instant,
+    /// The timestamp at which the last `Ping` was sent to this peer, if its `Pong` is outstanding.
+    ping_sent_at: Option<Instant>,
+    /// The smoothed round-trip time of this peer, measured from each `Ping` to its `Pong`.
+    rtt: Option<Duration>,
     /// The TCP socket that handles sending and receiving data with this peer.
-    outbound_socket: Framed<TcpStream, Message<N, E>>,
+    outbound_socket: Framed<TcpStream, MessageCodec<N, E>>,
     /// The `outbound_handler` half of the MPSC message channel, used to receive messages from peers.
     /// When a message is received on this `OutboundHandler`, it will be written to the socket.
     outbound_handler: OutboundHandler<N, E>,
@@ -79,31 +169,54 @@ pub(crate) struct Peer<N: Network, E: Environment> {
     seen_outbound_blocks: HashMap<N::BlockHash, SystemTime>,
     /// The map of peers to a map of transaction IDs to their last seen timestamp.
     seen_outbound_transactions: HashMap<N::TransactionID, SystemTime>,
+    /// The blocks and transactions announced to this peer.
+    inventory: Inventory<N, E>,
+    /// The map of transaction IDs requested by this peer from the memory pool, to their request timestamp.
+    requested_transactions: HashMap<N::TransactionID, Instant>,
+    /// The map of transaction IDs that this node requested from this peer, to their request timestamp.
+    solicited_transactions: HashMap<N::TransactionID, Instant>,
+    /// `true` if this node has requested the memory pool of this peer.
+    is_memory_pool_requested: bool,
+    /// `true` if this node has received the memory pool of this peer.
+    is_memory_pool_received: bool,
+    /// `true` if this node has served its memory pool to this peer.
+    is_memory_pool_served: bool,
 }
 
 impl<N: Network, E: Environment> Peer<N, E> {
     /// Create a new instance of `Peer`.
+    #[allow(clippy::too_many_arguments)]
     async fn new(
         stream: TcpStream,
         local_ip: SocketAddr,
         local_nonce: u64,
+        local_key: &NodeKey,
         peers_router: &PeersRouter<N, E>,
         ledger_reader: &LedgerReader<N>,
         connected_nonces: &[u64],
+        connected_node_ids: &[NodePublicKey],
+        is_outbound: bool,
     ) -> Result<Self> {
         // Construct the socket.
-        let mut outbound_socket = Framed::new(stream, Message::<N, E>::PeerRequest);
+        let mut outbound_socket = Framed::new(stream, MessageCodec::<N, E>::default());
 
         // Perform the handshake before proceeding.
-        let (peer_ip, peer_nonce, node_type, status) = Peer::handshake(
+        let (peer_ip, peer_nonce, node_type, status, capabilities, node_id) = Peer::handshake(
             &mut outbound_socket,
             local_ip,
             local_nonce,
+            local_key,
             ledger_reader.latest_cumulative_weight(),
             connected_nonces,
+            connected_node_ids,
         )
         .await?;
 
+        // Enable compression on this connection, if it is supported by both sides.
+        outbound_socket
+            .codec_mut()
+            .set_compression(capabilities.contains(Capabilities::COMPRESSION));
+
         // Send the first `Ping` message to the peer.
         let message = Message::Ping(
             E::MESSAGE_VERSION,
@@ -115,13 +228,21 @@ impl<N: Network, E: Environment> Peer<N, E> {
         );
         trace!("Sending '{}' to {}", message.name(), peer_ip);
         outbound_socket.send(message).await?;
+        let ping_sent_at = Instant::now();
 
         // Create a channel for this peer.
         let (outbound_router, outbound_handler) = mpsc::channel(1024);
 
         // Add an entry for this `Peer` in the connected peers.
         peers_router
-            .send(PeersRequest::PeerConnected(peer_ip, peer_nonce, outbound_router))
+            .send(PeersRequest::PeerConnected(
+                peer_ip,
+                peer_nonce,
+                node_type,
+                node_id,
+                is_outbound,
+                outbound_router,
+            ))
             .await?;
 
         Ok(Peer {
@@ -129,14 +250,23 @@ impl<N: Network, E: Environment> Peer<N, E> {
             version: 0,
             node_type,
             status,
+            capabilities,
             block_header: N::genesis_block().header().clone(),
             last_seen: Instant::now(),
+            ping_sent_at: Some(ping_sent_at),
+            rtt: None,
             outbound_socket,
             outbound_handler,
             seen_inbound_blocks: Default::default(),
             seen_inbound_transactions: Default::default(),
             seen_outbound_blocks: Default::default(),
             seen_outbound_transactions: Default::default(),
+            inventory: Default::default(),
+            requested_transactions: Default::default(),
+            solicited_transactions: Default::default(),
+            is_memory_pool_requested: false,
+            is_memory_pool_received: false,
+            is_memory_pool_served: false,
         })
     }
 
@@ -145,6 +275,22 @@ impl<N: Network, E: Environment> Peer<N, E> {
         self.listener_ip
     }
 
+    /// Returns `true` if the given protocol extensions are supported by both this node and the peer.
+    fn supports(&self, capabilities: Capabilities) -> bool {
+        self.capabilities.contains(capabilities)
+    }
+
+    /// Updates the smoothed round-trip time of the peer with the given sample, and returns the smoothed round-trip time.
+    fn update_rtt(&mut self, sample: Duration) -> Duration {
+        // Weight each sample by 1/8 (as in RFC 6298), so a single delayed `Pong` does not skew the estimate.
+        let rtt = match self.rtt {
+            Some(rtt) => (rtt * 7 + sample) / 8,
+            None => sample,
+        };
+        self.rtt = Some(rtt);
+        rtt
+    }
+
     /// Sends the given message to this peer.
     async fn send(&mut self, message: Message<N, E>) -> Result<()> {
         trace!("Sending '{}' to {}", message.name(), self.peer_ip());
@@ -152,16 +298,24 @@ impl<N: Network, E: Environment> Peer<N, E> {
         Ok(())
     }
 
-    /// Performs the handshake protocol, returning the listener IP and nonce of the peer upon success.
+    ///
+    /// Performs the handshake protocol, returning the listener IP, nonce, and node ID (if authenticated) of the peer
+    /// upon success, along with the protocol extensions that are supported by both this node and the peer.
+    /// If both sides support encryption, the connection is upgraded to an encrypted transport,
+    /// which authenticates the node ID of the peer. Otherwise, the node ID advertised by the peer is discarded.
+    ///
+    #[allow(clippy::too_many_arguments)]
     async fn handshake(
-        outbound_socket: &mut Framed<TcpStream, Message<N, E>>,
+        outbound_socket: &mut Framed<TcpStream, MessageCodec<N, E>>,
         local_ip: SocketAddr,
         local_nonce: u64,
+        local_key: &NodeKey,
         local_cumulative_weight: u128,
         connected_nonces: &[u64],
-    ) -> Result<(SocketAddr, u64, NodeType, Status)> {
-        // Get the IP address of the peer.
-        let mut peer_ip = outbound_socket.get_ref().peer_addr()?;
+        connected_node_ids: &[NodePublicKey],
+    ) -> Result<(SocketAddr, u64, NodeType, Status, Capabilities, Option<NodePublicKey>)> {
+        // Get the IP address of the peer, in canonical form.
+        let mut peer_ip = canonical_socket_addr(outbound_socket.get_ref().peer_addr()?);
 
         // Retrieve the genesis block header.
         let genesis_header = N::genesis_block().header();
@@ -175,15 +329,19 @@ impl<N: Network, E: Environment> Peer<N, E> {
             local_ip.port(),
             local_nonce,
             local_cumulative_weight,
+            E::capabilities(),
+            Some(local_key.public_key()),
         );
+        let local_request = message.serialize()?;
         trace!("Sending '{}-A' to {}", message.name(), peer_ip);
         outbound_socket.send(message).await?;
 
         // Wait for the counterparty challenge request to come in.
-        let (peer_nonce, node_type, status) = match outbound_socket.next().await {
+        let (peer_nonce, node_type, status, capabilities, node_id, peer_request) = match outbound_socket.next().await {
             Some(Ok(message)) => {
                 // Process the message.
                 trace!("Received '{}-B' from {}", message.name(), peer_ip);
+                let peer_request = message.serialize()?;
                 match message {
                     Message::ChallengeRequest(
                         version,
@@ -193,6 +351,8 @@ impl<N: Network, E: Environment> Peer<N, E> {
                         listener_port,
                         peer_nonce,
                         peer_cumulative_weight,
+                        peer_capabilities,
+                        node_id,
                     ) => {
                         // Ensure the message protocol version is not outdated.
                         if version < E::MESSAGE_VERSION {
@@ -236,16 +396,25 @@ impl<N: Network, E: Environment> Peer<N, E> {
                             // Update the peer IP to the listener port.
                             peer_ip.set_port(listener_port);
                             // Ensure the claimed listener port is open.
-                            let stream =
-                                match timeout(Duration::from_millis(E::CONNECTION_TIMEOUT_IN_MILLIS), TcpStream::connect(peer_ip)).await {
-                                    Ok(stream) => stream,
-                                    Err(error) => return Err(anyhow!("Unable to reach '{}': '{:?}'", peer_ip, error)),
-                                };
+                            let stream = match timeout(
+                                Duration::from_millis(E::connection_timeout_in_millis()),
+                                TcpStream::connect(peer_ip),
+                            )
+                            .await
+                            {
+                                Ok(stream) => stream,
+                                Err(error) => return Err(anyhow!("Unable to reach '{}': '{:?}'", peer_ip, error)),
+                            };
                             // Error if the stream is not open.
                             if let Err(error) = stream {
                                 return Err(anyhow!("Unable to reach '{}': '{}'", peer_ip, error));
                             }
                         }
+                        // Ensure the listener address of the peer is trusted, if the node is in a private network.
+                        let listener_ip = SocketAddr::new(peer_ip.ip(), listener_port);
+                        if !is_trusted_peer(E::trusted_peers(), listener_ip) {
+                            return Err(anyhow!("Dropping {} as {} is not a trusted peer", peer_ip, listener_ip));
+                        }
                         // Send the challenge response.
                         let message = Message::ChallengeResponse(Data::Object(genesis_header.clone()));
                         trace!("Sending '{}-B' to {}", message.name(), peer_ip);
@@ -255,7 +424,11 @@ impl<N: Network, E: Environment> Peer<N, E> {
                         let status = Status::new();
                         status.update(peer_status);
 
-                        (peer_nonce, node_type, status)
+                        // Enable only the protocol extensions that are supported by both sides.
+                        let capabilities = E::capabilities().intersection(peer_capabilities);
+                        debug!("Negotiated capabilities {} with {}", capabilities, peer_ip);
+
+                        (peer_nonce, node_type, status, capabilities, node_id, peer_request)
                     }
                     message => {
                         return Err(anyhow!(
@@ -281,23 +454,134 @@ impl<N: Network, E: Environment> Peer<N, E> {
                     Message::ChallengeResponse(block_header) => {
                         // Perform the deferred non-blocking deserialization of the block header.
                         let block_header = block_header.deserialize().await?;
-                        match &block_header == genesis_header {
-                            true => Ok((peer_ip, peer_nonce, node_type, status)),
-                            false => Err(anyhow!("Challenge response from {} failed, received '{}'", peer_ip, block_header)),
+                        if &block_header != genesis_header {
+                            return Err(anyhow!("Challenge response from {} failed, received '{}'", peer_ip, block_header));
                         }
                     }
-                    message => Err(anyhow!(
-                        "Expected challenge response, received '{}' from {}",
-                        message.name(),
-                        peer_ip
-                    )),
+                    message => {
+                        return Err(anyhow!(
+                            "Expected challenge response, received '{}' from {}",
+                            message.name(),
+                            peer_ip
+                        ))
+                    }
                 }
             }
             // An error occurred.
-            Some(Err(error)) => Err(anyhow!("Failed to get challenge response from {}: {:?}", peer_ip, error)),
+            Some(Err(error)) => return Err(anyhow!("Failed to get challenge response from {}: {:?}", peer_ip, error)),
             // Did not receive anything.
-            None => Err(anyhow!("Failed to get challenge response from {}, peer has disconnected", peer_ip)),
+            None => return Err(anyhow!("Failed to get challenge response from {}, peer has disconnected", peer_ip)),
+        }
+
+        // Upgrade the connection to an encrypted transport, if it is supported by both sides.
+        // The node ID advertised by the peer is only trusted once the encrypted transport authenticates it.
+        let node_id = match capabilities.contains(Capabilities::ENCRYPTION) {
+            true => {
+                // The node with the lower nonce initiates the encryption handshake.
+                let is_initiator = local_nonce < peer_nonce;
+                // Bind both challenge requests to the encrypted transport, so that they cannot be tampered with.
+                let prologue = match is_initiator {
+                    true => [local_request, peer_request].concat(),
+                    false => [peer_request, local_request].concat(),
+                };
+                let peer_key = Peer::encrypt(outbound_socket, local_key, is_initiator, &prologue, peer_ip).await?;
+
+                // Ensure the node ID of the peer is the public key it authenticated with.
+                if node_id != Some(peer_key) {
+                    return Err(anyhow!(
+                        "Dropping {} as its node ID does not match its public key {}",
+                        peer_ip,
+                        peer_key
+                    ));
+                }
+                // Ensure the peer is not this node.
+                if peer_key == local_key.public_key() {
+                    return Err(anyhow!("Attempted to connect to self (node {})", peer_key));
+                }
+                // Ensure the peer is not already connected to this node under another address.
+                if connected_node_ids.contains(&peer_key) {
+                    return Err(anyhow!("Already connected to node {}", peer_key));
+                }
+                debug!("Established an encrypted connection with {} (node {})", peer_ip, peer_key);
+                Some(peer_key)
+            }
+            false if E::require_encryption() => {
+                return Err(anyhow!("Dropping {} as it does not support encryption", peer_ip));
+            }
+            false => None,
+        };
+
+        Ok((peer_ip, peer_nonce, node_type, status, capabilities, node_id))
+    }
+
+    ///
+    /// Upgrades the connection to an encrypted transport with a Noise handshake,
+    /// returning the static public key of the peer upon success.
+    ///
+    /// The given prologue must be identical on both sides, or the handshake fails.
+    ///
+    async fn encrypt(
+        outbound_socket: &mut Framed<TcpStream, MessageCodec<N, E>>,
+        local_key: &NodeKey,
+        is_initiator: bool,
+        prologue: &[u8],
+        peer_ip: SocketAddr,
+    ) -> Result<NodePublicKey> {
+        let builder = snow::Builder::new(NOISE_PARAMS.parse()?)
+            .local_private_key(local_key.private_key())
+            .prologue(prologue);
+        let mut noise = match is_initiator {
+            true => builder.build_initiator()?,
+            false => builder.build_responder()?,
+        };
+
+        // Exchange the handshake messages, in the order determined by the Noise pattern.
+        let mut buffer = vec![0u8; 65535];
+        while !noise.is_handshake_finished() {
+            match noise.is_my_turn() {
+                true => {
+                    let length = noise.write_message(&[], &mut buffer)?;
+                    let message = Message::EncryptionHandshake(buffer[..length].to_vec());
+                    trace!("Sending '{}' to {}", message.name(), peer_ip);
+                    outbound_socket.send(message).await?;
+                }
+                false => match outbound_socket.next().await {
+                    Some(Ok(Message::EncryptionHandshake(noise_message))) => {
+                        trace!("Received 'EncryptionHandshake' from {}", peer_ip);
+                        if let Err(error) = noise.read_message(&noise_message, &mut buffer) {
+                            return Err(anyhow!("Encryption handshake with {} failed: {}", peer_ip, error));
+                        }
+                    }
+                    Some(Ok(message)) => {
+                        return Err(anyhow!(
+                            "Expected encryption handshake, received '{}' from {}",
+                            message.name(),
+                            peer_ip
+                        ))
+                    }
+                    // An error occurred.
+                    Some(Err(error)) => return Err(anyhow!("Failed to get encryption handshake from {}: {:?}", peer_ip, error)),
+                    // Did not receive anything.
+                    None => {
+                        return Err(anyhow!(
+                            "Failed to get encryption handshake from {}, peer has disconnected",
+                            peer_ip
+                        ))
+                    }
+                },
+            }
         }
+
+        // Retrieve the static public key of the peer, which is authenticated by the handshake.
+        let transport = noise.into_transport_mode()?;
+        let peer_key = match transport.get_remote_static() {
+            Some(public_key) => NodePublicKey::from_bytes(public_key)?,
+            None => return Err(anyhow!("Encryption handshake with {} did not reveal a public key", peer_ip)),
+        };
+
+        // Encrypt every subsequent message on this connection.
+        outbound_socket.codec_mut().set_cipher(transport);
+        Ok(peer_key)
     }
 
     /// A handler to process an individual peer.
@@ -306,15 +590,19 @@ impl<N: Network, E: Environment> Peer<N, E> {
         stream: TcpStream,
         local_ip: SocketAddr,
         local_nonce: u64,
+        local_key: NodeKey,
         peers_router: &PeersRouter<N, E>,
         ledger_reader: LedgerReader<N>,
         ledger_router: LedgerRouter<N>,
         prover_router: ProverRouter<N>,
         operator_router: OperatorRouter<N>,
         connected_nonces: Vec<u64>,
+        connected_node_ids: Vec<NodePublicKey>,
         connection_result: Option<ConnectionResult>,
     ) {
         let peers_router = peers_router.clone();
+        // Only the connections that this node dialed report a connection result.
+        let is_outbound = connection_result.is_some();
 
         E::tasks().append(task::spawn(async move {
             // Register our peer with state which internally sets up some channels.
@@ -322,9 +610,12 @@ impl<N: Network, E: Environment> Peer<N, E> {
                 stream,
                 local_ip,
                 local_nonce,
+                &local_key,
                 &peers_router,
                 &ledger_reader,
                 &connected_nonces,
+                &connected_node_ids,
+                is_outbound,
             )
                 .await
             {
@@ -353,29 +644,36 @@ impl<N: Network, E: Environment> Peer<N, E> {
             let peer_ip = peer.peer_ip();
             info!("Connected to {}", peer_ip);
 
+            // Initialize the interval at which pending transaction IDs are announced to the peer.
+            let mut transaction_inventory_interval = interval(Duration::from_millis(TRANSACTION_INVENTORY_INTERVAL_IN_MILLIS));
+
             // Process incoming messages until this stream is disconnected.
             loop {
                 tokio::select! {
                     // Message channel is routing a message outbound to the peer.
                     Some(mut message) = peer.outbound_handler.recv() => {
                         // Disconnect if the peer has not communicated back within the predefined time.
-                        if peer.last_seen.elapsed() > Duration::from_secs(E::RADIO_SILENCE_IN_SECS) {
+                        if peer.last_seen.elapsed() > Duration::from_secs(E::radio_silence_in_secs()) {
                             warn!("Peer {} has not communicated in {} seconds", peer_ip, peer.last_seen.elapsed().as_secs());
                             break;
                         } else {
                             // Ensure sufficient time has passed before needing to send the message.
+                            let mut is_requested = false;
                             let is_ready_to_send = match message {
                                 Message::Ping(_, _, _, _, _, ref mut data) => {
                                     // Perform non-blocking serialisation of the block header.
                                     let serialized_header = Data::serialize(data.clone()).await.expect("Block header serialization is bugged");
                                     let _ = std::mem::replace(data, Data::Buffer(serialized_header));
 
+                                    // Start timing the round trip to the peer's `Pong`.
+                                    peer.ping_sent_at = Some(Instant::now());
+
                                     true
                                 }
                                 Message::UnconfirmedBlock(block_height, block_hash, ref mut data) => {
                                     // Retrieve the last seen timestamp of this block for this peer.
                                     let last_seen = peer.seen_outbound_blocks.entry(block_hash).or_insert(SystemTime::UNIX_EPOCH);
-                                    let is_ready_to_send = last_seen.elapsed().unwrap().as_secs() > E::RADIO_SILENCE_IN_SECS;
+                                    let is_ready_to_send = last_seen.elapsed().unwrap().as_secs() > E::radio_silence_in_secs();
 
                                     // Update the timestamp for the peer and sent block.
                                     peer.seen_outbound_blocks.insert(block_hash, SystemTime::now());
@@ -391,12 +689,15 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                     is_ready_to_send
                                 }
                                 Message::UnconfirmedTransaction(ref transaction) => {
+                                    // If the peer requested this transaction from the memory pool, send it in full.
+                                    is_requested = peer.requested_transactions.remove(&transaction.transaction_id()).is_some();
+
                                     // Retrieve the last seen timestamp of this transaction for this peer.
                                     let last_seen = peer
                                         .seen_outbound_transactions
                                         .entry(transaction.transaction_id())
                                         .or_insert(SystemTime::UNIX_EPOCH);
-                                    let is_ready_to_send = last_seen.elapsed().unwrap().as_secs() > E::RADIO_SILENCE_IN_SECS;
+                                    let is_ready_to_send = is_requested || last_seen.elapsed().unwrap().as_secs() > E::radio_silence_in_secs();
 
                                     // Update the timestamp for the peer and sent transaction.
                                     peer.seen_outbound_transactions.insert(transaction.transaction_id(), SystemTime::now());
@@ -411,22 +712,46 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                     }
                                     is_ready_to_send
                                 }
+                                Message::InventoryRequest(_, ref transaction_ids) => {
+                                    // Record the requested transactions, so that their responses are not mistaken for spam.
+                                    let radio_silence = Duration::from_secs(E::radio_silence_in_secs());
+                                    peer.solicited_transactions.retain(|_, requested_at| requested_at.elapsed() < radio_silence);
+                                    for transaction_id in transaction_ids {
+                                        peer.solicited_transactions.insert(*transaction_id, Instant::now());
+                                    }
+                                    true
+                                }
                                 _ => true,
                             };
                             // Send the message if it is ready.
                             if is_ready_to_send {
+                                // Announce unconfirmed blocks and transactions by their hash or ID, if the peer supports it.
+                                let message = match peer.supports(Capabilities::INVENTORY) && !is_requested {
+                                    true => peer.inventory.announce(message),
+                                    false => Some(message),
+                                };
                                 // Route a message to the peer.
-                                if let Err(error) = peer.send(message).await {
-                                    warn!("[OutboundRouter] {}", error);
+                                if let Some(message) = message {
+                                    if let Err(error) = peer.send(message).await {
+                                        warn!("[OutboundRouter] {}", error);
+                                    }
                                 }
                             }
                         }
                     }
+                    // Announce the pending transaction IDs to the peer.
+                    _ = transaction_inventory_interval.tick() => {
+                        if let Some(message) = peer.inventory.take_transactions() {
+                            if let Err(error) = peer.send(message).await {
+                                warn!("[TransactionInventory] {}", error);
+                            }
+                        }
+                    }
                     result = peer.outbound_socket.next() => match result {
                         // Received a message from the peer.
                         Some(Ok(message)) => {
                             // Disconnect if the peer has not communicated back within the predefined time.
-                            match peer.last_seen.elapsed() > Duration::from_secs(E::RADIO_SILENCE_IN_SECS) {
+                            match peer.last_seen.elapsed() > Duration::from_secs(E::radio_silence_in_secs()) {
                                 true => {
                                     let last_seen = peer.last_seen.elapsed().as_secs();
                                     warn!("Failed to receive a message from {} in {} seconds", peer_ip, last_seen);
@@ -443,10 +768,10 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                 Message::BlockRequest(start_block_height, end_block_height) => {
                                     // Ensure the request is within the accepted limits.
                                     let number_of_blocks = end_block_height.saturating_sub(start_block_height);
-                                    if number_of_blocks > E::MAXIMUM_BLOCK_REQUEST {
+                                    if number_of_blocks > E::maximum_block_request() {
                                         // Route a `Failure` to the ledger.
-                                        let failure = format!("Attempted to request {} blocks", number_of_blocks);
-                                        if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, failure)).await {
+                                        let misbehavior = Misbehavior::OversizedRequest(format!("attempted to request {} blocks", number_of_blocks));
+                                        if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
                                             warn!("[Failure] {}", error);
                                         }
                                         continue;
@@ -456,7 +781,8 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                         Ok(blocks) => blocks,
                                         Err(error) => {
                                             // Route a `Failure` to the ledger.
-                                            if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, format!("{}", error))).await {
+                                            let misbehavior = Misbehavior::Spam(format!("{}", error));
+                                            if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
                                                 warn!("[Failure] {}", error);
                                             }
                                             continue;
@@ -491,12 +817,108 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                             }
                                         },
                                         // Route the `Failure` to the ledger.
-                                        Err(error) => if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, format!("{}", error))).await {
+                                        Err(error) => if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, Misbehavior::MalformedMessage(format!("{}", error)))).await {
+                                            warn!("[Failure] {}", error);
+                                        }
+                                    }
+                                }
+                                Message::BlockHeadersRequest(start_block_height, end_block_height) => {
+                                    // Ensure the request is within the accepted limits.
+                                    let number_of_block_headers = end_block_height.saturating_sub(start_block_height);
+                                    if number_of_block_headers > E::MAXIMUM_BLOCK_HEADERS_REQUEST {
+                                        // Route a `Failure` to the ledger.
+                                        let misbehavior = Misbehavior::OversizedRequest(format!("attempted to request {} block headers", number_of_block_headers));
+                                        if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
+                                            warn!("[Failure] {}", error);
+                                        }
+                                        continue;
+                                    }
+                                    // Retrieve the requested block headers, up to the latest block height of this ledger.
+                                    // If none of the block headers exist, an empty response is sent, so the peer does not wait for it.
+                                    let latest_block_height = ledger_reader.latest_block_height();
+                                    let block_headers = match start_block_height > latest_block_height {
+                                        true => vec![],
+                                        false => match ledger_reader.get_block_headers(start_block_height, std::cmp::min(end_block_height, latest_block_height)) {
+                                            Ok(block_headers) => block_headers,
+                                            Err(error) => {
+                                                // Route a `Failure` to the ledger.
+                                                let misbehavior = Misbehavior::Spam(format!("{}", error));
+                                                if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
+                                                    warn!("[Failure] {}", error);
+                                                }
+                                                continue;
+                                            }
+                                        },
+                                    };
+                                    // Send a `BlockHeadersResponse` message to the peer.
+                                    debug!("Sending 'BlockHeadersResponse {}' to {}", block_headers.len(), peer_ip);
+                                    if let Err(error) = peer.outbound_socket.send(Message::BlockHeadersResponse(Data::Object(block_headers))).await {
+                                        warn!("[BlockHeadersResponse] {}", error);
+                                    }
+                                }
+                                Message::BlockHeadersResponse(block_headers) => {
+                                    // Perform the deferred non-blocking deserialization of the block headers.
+                                    let request = match block_headers.deserialize().await {
+                                        // Route the `BlockHeadersResponse` to the ledger.
+                                        Ok(block_headers) => LedgerRequest::BlockHeadersResponse(peer_ip, block_headers),
+                                        // Route the `Failure` to the ledger.
+                                        Err(error) => LedgerRequest::Failure(peer_ip, Misbehavior::MalformedMessage(format!("{}", error))),
+                                    };
+                                    if let Err(error) = ledger_router.send(request).await {
+                                        warn!("[BlockHeadersResponse] {}", error);
+                                    }
+                                }
+                                Message::BlockRequestByHash(block_hash, number_of_blocks) => {
+                                    // Ensure the request is within the accepted limits.
+                                    if number_of_blocks > E::maximum_block_request() {
+                                        // Route a `Failure` to the ledger.
+                                        let misbehavior = Misbehavior::OversizedRequest(format!("attempted to request {} blocks", number_of_blocks));
+                                        if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
                                             warn!("[Failure] {}", error);
                                         }
+                                        continue;
+                                    }
+                                    // Retrieve the requested blocks descending from the block hash, up to the latest block height of this ledger.
+                                    let latest_block_height = ledger_reader.latest_block_height();
+                                    let blocks = match ledger_reader.get_block_height(&block_hash) {
+                                        Ok(block_height) if block_height < latest_block_height && number_of_blocks > 0 => {
+                                            let end_block_height = std::cmp::min(block_height.saturating_add(number_of_blocks), latest_block_height);
+                                            ledger_reader.get_blocks(block_height + 1, end_block_height).unwrap_or_default()
+                                        }
+                                        _ => vec![],
+                                    };
+                                    // Send a `BlockResponse` message for each block to the peer, as long as each block descends from the previous one.
+                                    // If this ledger reorganized while the blocks were retrieved, the remaining blocks are from a different chain.
+                                    let mut previous_block_hash = block_hash;
+                                    let mut number_of_sent_blocks = 0;
+                                    for block in blocks {
+                                        if block.previous_block_hash() != previous_block_hash {
+                                            break;
+                                        }
+                                        let current_block_hash = block.hash();
+                                        debug!("Sending 'BlockResponse {}' to {}", block.height(), peer_ip);
+                                        if let Err(error) = peer.outbound_socket.send(Message::BlockResponse(Data::Object(block))).await {
+                                            warn!("[BlockResponse] {}", error);
+                                            break;
+                                        }
+                                        previous_block_hash = current_block_hash;
+                                        number_of_sent_blocks += 1;
+                                    }
+                                    // Send a `BlockNotFound` message to the peer, if none of the remaining blocks descend from the last block sent.
+                                    if number_of_sent_blocks < number_of_blocks {
+                                        debug!("Sending 'BlockNotFound {}' to {}", previous_block_hash, peer_ip);
+                                        if let Err(error) = peer.outbound_socket.send(Message::BlockNotFound(previous_block_hash)).await {
+                                            warn!("[BlockNotFound] {}", error);
+                                        }
+                                    }
+                                }
+                                Message::BlockNotFound(block_hash) => {
+                                    // Route the `BlockNotFound` to the ledger.
+                                    if let Err(error) = ledger_router.send(LedgerRequest::BlockNotFound(peer_ip, block_hash)).await {
+                                        warn!("[BlockNotFound] {}", error);
                                     }
                                 }
-                                Message::ChallengeRequest(..) | Message::ChallengeResponse(..) => {
+                                Message::ChallengeRequest(..) | Message::ChallengeResponse(..) | Message::EncryptionHandshake(..) => {
                                     // Peer is not following the protocol.
                                     warn!("Peer {} is not following the protocol", peer_ip);
                                     break;
@@ -582,12 +1004,20 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                     }
                                 },
                                 Message::Pong(is_fork, block_locators) => {
+                                    // Measure the round-trip time of the peer, if this `Pong` answers an outstanding `Ping`.
+                                    if let Some(ping_sent_at) = peer.ping_sent_at.take() {
+                                        let rtt = peer.update_rtt(ping_sent_at.elapsed());
+                                        if let Err(error) = peers_router.send(PeersRequest::PeerRoundTripTime(peer_ip, rtt)).await {
+                                            warn!("[PeerRoundTripTime] {}", error);
+                                        }
+                                    }
+
                                     // Perform the deferred non-blocking deserialization of block locators.
                                     let request = match block_locators.deserialize().await {
                                         // Route the `Pong` to the ledger.
-                                        Ok(block_locators) => LedgerRequest::Pong(peer_ip, peer.node_type, peer.status.get(), is_fork, block_locators),
+                                        Ok(block_locators) => LedgerRequest::Pong(peer_ip, peer.node_type, peer.status.get(), peer.capabilities, peer.rtt, is_fork, block_locators),
                                         // Route the `Failure` to the ledger.
-                                        Err(error) => LedgerRequest::Failure(peer_ip, format!("{}", error)),
+                                        Err(error) => LedgerRequest::Failure(peer_ip, Misbehavior::MalformedMessage(format!("{}", error))),
                                     };
 
                                     // Route the request to the ledger.
@@ -595,12 +1025,21 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                         warn!("[Pong] {}", error);
                                     }
 
+                                    // Request the memory pool of the peer once per connection, so this node does not wait on gossip to fill its memory pool.
+                                    let is_node_ready = E::NODE_TYPE != NodeType::Beacon && E::NODE_TYPE != NodeType::Sync && !E::status().is_peering();
+                                    if is_node_ready && !peer.is_memory_pool_requested && peer.supports(Capabilities::MEMORY_POOL) {
+                                        peer.is_memory_pool_requested = true;
+                                        if let Err(error) = peer.send(Message::MemoryPoolRequest).await {
+                                            warn!("[MemoryPoolRequest] {}", error);
+                                        }
+                                    }
+
                                     // Spawn an asynchronous task for the `Ping` request.
                                     let peers_router = peers_router.clone();
                                     let ledger_reader = ledger_reader.clone();
                                     E::tasks().append(task::spawn(async move {
                                         // Sleep for the preset time before sending a `Ping` request.
-                                        tokio::time::sleep(Duration::from_secs(E::PING_SLEEP_IN_SECS)).await;
+                                        tokio::time::sleep(Duration::from_secs(E::ping_sleep_in_secs())).await;
 
                                         // Retrieve the latest ledger state.
                                         let latest_block_hash = ledger_reader.latest_block_hash();
@@ -627,7 +1066,7 @@ impl<N: Network, E: Environment> Peer<N, E> {
 
                                     // Retrieve the last seen timestamp of the received block.
                                     let last_seen = peer.seen_inbound_blocks.entry(block_hash).or_insert(SystemTime::UNIX_EPOCH);
-                                    let is_router_ready = last_seen.elapsed().unwrap().as_secs() > E::RADIO_SILENCE_IN_SECS;
+                                    let is_router_ready = last_seen.elapsed().unwrap().as_secs() > E::radio_silence_in_secs();
 
                                     // Update the timestamp for the received block.
                                     peer.seen_inbound_blocks.insert(block_hash, SystemTime::now());
@@ -654,10 +1093,10 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                                 // Route the `UnconfirmedBlock` to the ledger.
                                                 true => LedgerRequest::UnconfirmedBlock(peer_ip, block, prover_router.clone()),
                                                 // Route the `Failure` to the ledger.
-                                                false => LedgerRequest::Failure(peer_ip, "Malformed UnconfirmedBlock message".to_string())
+                                                false => LedgerRequest::Failure(peer_ip, Misbehavior::MalformedMessage("malformed UnconfirmedBlock message".to_string()))
                                             },
                                             // Route the `Failure` to the ledger.
-                                            Err(error) => LedgerRequest::Failure(peer_ip, format!("{}", error)),
+                                            Err(error) => LedgerRequest::Failure(peer_ip, Misbehavior::MalformedMessage(format!("{}", error))),
                                         };
 
                                         // Route the request to the ledger.
@@ -666,10 +1105,144 @@ impl<N: Network, E: Environment> Peer<N, E> {
                                         }
                                     }
                                 }
+                                Message::BlockInventory(block_height, block_hash) => {
+                                    // Ensure the announced block has not been received from this peer recently.
+                                    let is_router_ready = match peer.seen_inbound_blocks.get(&block_hash) {
+                                        Some(last_seen) => last_seen.elapsed().unwrap().as_secs() > E::radio_silence_in_secs(),
+                                        None => true,
+                                    };
+
+                                    // Ensure the announced block is within the same range as an unconfirmed block, and is not in the ledger.
+                                    let latest_block_height = ledger_reader.latest_block_height();
+                                    let is_within_range = block_height >= latest_block_height.saturating_sub(2) && block_height <= latest_block_height.saturating_add(20);
+                                    let is_missing = !ledger_reader.contains_block_hash(&block_hash).unwrap_or(true);
+
+                                    // Ensure the node is not peering.
+                                    let is_node_ready = !E::status().is_peering();
+
+                                    // If this node is a beacon or sync node, skip this message.
+                                    if E::NODE_TYPE == NodeType::Beacon || E::NODE_TYPE == NodeType::Sync || !is_router_ready || !is_within_range || !is_missing || !is_node_ready {
+                                        trace!("Skipping 'BlockInventory {}' from {}", block_height, peer_ip);
+                                    } else if let Err(error) = peers_router.send(PeersRequest::InventoryAnnouncement(peer_ip, vec![block_hash], vec![])).await {
+                                        warn!("[BlockInventory] {}", error);
+                                    }
+                                }
+                                Message::TransactionInventory(transaction_ids) => {
+                                    // Ensure the announcement is within the accepted limits.
+                                    if transaction_ids.len() > E::MAXIMUM_INVENTORY_ITEMS {
+                                        // Route a `Failure` to the ledger.
+                                        let misbehavior = Misbehavior::MalformedMessage(format!("announced {} transactions", transaction_ids.len()));
+                                        if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
+                                            warn!("[Failure] {}", error);
+                                        }
+                                        continue;
+                                    }
+
+                                    // If this node is a beacon or sync node, or is peering, skip this message.
+                                    if E::NODE_TYPE == NodeType::Beacon || E::NODE_TYPE == NodeType::Sync || E::status().is_peering() {
+                                        trace!("Skipping 'TransactionInventory' from {}", peer_ip);
+                                        continue;
+                                    }
+
+                                    // Select the announced transactions that have not been received from this peer recently, and are not in the ledger.
+                                    let transaction_ids = transaction_ids
+                                        .into_iter()
+                                        .filter(|transaction_id| match peer.seen_inbound_transactions.get(transaction_id) {
+                                            Some(last_seen) => last_seen.elapsed().unwrap().as_secs() > E::radio_silence_in_secs(),
+                                            None => true,
+                                        })
+                                        .filter(|transaction_id| !ledger_reader.contains_transaction(transaction_id).unwrap_or(true))
+                                        .collect::<Vec<_>>();
+
+                                    if !transaction_ids.is_empty() {
+                                        if let Err(error) = peers_router.send(PeersRequest::InventoryAnnouncement(peer_ip, vec![], transaction_ids)).await {
+                                            warn!("[TransactionInventory] {}", error);
+                                        }
+                                    }
+                                }
+                                Message::InventoryRequest(block_hashes, transaction_ids) => {
+                                    // Ensure the request is within the accepted limits.
+                                    let number_of_items = block_hashes.len() + transaction_ids.len();
+                                    if number_of_items > E::MAXIMUM_INVENTORY_ITEMS {
+                                        // Route a `Failure` to the ledger.
+                                        let misbehavior = Misbehavior::OversizedRequest(format!("attempted to request {} inventory items", number_of_items));
+                                        if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
+                                            warn!("[Failure] {}", error);
+                                        }
+                                        continue;
+                                    }
+
+                                    // Retrieve the requested blocks that were announced to this peer.
+                                    let messages = peer.inventory.take_blocks(&block_hashes);
+
+                                    // Route the request for the transactions to the prover, to be served from the memory pool.
+                                    if !transaction_ids.is_empty() {
+                                        let radio_silence = Duration::from_secs(E::radio_silence_in_secs());
+                                        peer.requested_transactions.retain(|_, requested_at| requested_at.elapsed() < radio_silence);
+                                        for transaction_id in &transaction_ids {
+                                            peer.requested_transactions.insert(*transaction_id, Instant::now());
+                                        }
+                                        if let Err(error) = prover_router.send(ProverRequest::TransactionsRequest(peer_ip, transaction_ids)).await {
+                                            warn!("[InventoryRequest] {}", error);
+                                        }
+                                    }
+
+                                    // Send the requested blocks to the peer.
+                                    for message in messages {
+                                        if let Err(error) = peer.send(message).await {
+                                            warn!("[InventoryRequest] {}", error);
+                                            break;
+                                        }
+                                    }
+                                }
+                                Message::MemoryPoolRequest => {
+                                    // Ensure the peer requests the memory pool at most once per connection.
+                                    if peer.is_memory_pool_served {
+                                        // Route a `Failure` to the ledger.
+                                        let misbehavior = Misbehavior::Spam("received a redundant memory pool request".to_string());
+                                        if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
+                                            warn!("[Failure] {}", error);
+                                        }
+                                        continue;
+                                    }
+                                    peer.is_memory_pool_served = true;
+
+                                    // Route the `MemoryPoolRequest` to the prover.
+                                    if let Err(error) = prover_router.send(ProverRequest::MemoryPoolRequest(peer_ip)).await {
+                                        warn!("[MemoryPoolRequest] {}", error);
+                                    }
+                                }
+                                Message::MemoryPoolResponse(transaction_ids) => {
+                                    // Ensure the memory pool was requested from the peer, and is within the accepted limits.
+                                    let misbehavior = if !peer.is_memory_pool_requested || peer.is_memory_pool_received {
+                                        Some(Misbehavior::Spam("received an unrequested memory pool response".to_string()))
+                                    } else if transaction_ids.len() > E::MAXIMUM_INVENTORY_ITEMS {
+                                        Some(Misbehavior::MalformedMessage(format!("received {} memory pool transactions", transaction_ids.len())))
+                                    } else {
+                                        None
+                                    };
+                                    peer.is_memory_pool_received = true;
+
+                                    let request = match misbehavior {
+                                        // Route the `Failure` to the ledger.
+                                        Some(misbehavior) => {
+                                            if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, misbehavior)).await {
+                                                warn!("[Failure] {}", error);
+                                            }
+                                            continue;
+                                        }
+                                        // Route the `MemoryPoolResponse` to the prover.
+                                        None => ProverRequest::MemoryPoolResponse(peer_ip, transaction_ids),
+                                    };
+                                    if let Err(error) = prover_router.send(request).await {
+                                        warn!("[MemoryPoolResponse] {}", error);
+                                    }
+                                }
                                 Message::UnconfirmedTransaction(transaction) => {
-                                    // Drop the peer, if they have sent more than 500 unconfirmed transactions in the last 5 seconds.
+                                    // Drop the peer, if they have sent more than 500 unsolicited unconfirmed transactions in the last 5 seconds.
+                                    let is_solicited = peer.solicited_transactions.remove(&transaction.transaction_id()).is_some();
                                     let frequency = peer.seen_inbound_transactions.values().filter(|t| t.elapsed().unwrap().as_secs() <= 5).count();
-                                    if frequency >= 500 {
+                                    if !is_solicited && frequency >= 500 {
                                         warn!("Dropping {} for spamming unconfirmed transactions (frequency = {})", peer_ip, frequency);
                                         // Send a `PeerRestricted` message.
                                         if let Err(error) = peers_router.send(PeersRequest::PeerRestricted(peer_ip)).await {
@@ -680,7 +1253,7 @@ impl<N: Network, E: Environment> Peer<N, E> {
 
                                     // Retrieve the last seen timestamp of the received transaction.
                                     let last_seen = peer.seen_inbound_transactions.entry(transaction.transaction_id()).or_insert(SystemTime::UNIX_EPOCH);
-                                    let is_router_ready = last_seen.elapsed().unwrap().as_secs() > E::RADIO_SILENCE_IN_SECS;
+                                    let is_router_ready = last_seen.elapsed().unwrap().as_secs() > E::radio_silence_in_secs();
 
                                     // Update the timestamp for the received transaction.
                                     peer.seen_inbound_transactions.insert(transaction.transaction_id(), SystemTime::now());
@@ -731,7 +1304,15 @@ impl<N: Network, E: Environment> Peer<N, E> {
                             }
                         }
                         // An error occurred.
-                        Some(Err(error)) => error!("Failed to read message from {}: {}", peer_ip, error),
+                        Some(Err(error)) => {
+                            error!("Failed to read message from {}: {}", peer_ip, error);
+                            // Route a `Failure` to the ledger, if the peer sent a message that could not be decoded.
+                            if error.kind() == std::io::ErrorKind::InvalidData {
+                                if let Err(error) = ledger_router.send(LedgerRequest::Failure(peer_ip, Misbehavior::MalformedMessage(format!("{}", error)))).await {
+                                    warn!("[Failure] {}", error);
+                                }
+                            }
+                        }
                         // The stream has been disconnected.
                         None => break,
                     },
@@ -749,3 +1330,149 @@ impl<N: Network, E: Environment> Peer<N, E> {
         }));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Client;
+    use snarkvm::dpc::testnet2::Testnet2;
+    use tokio::net::TcpListener;
+
+    type TestPeer = Peer<Testnet2, Client<Testnet2>>;
+    type TestMessage = Message<Testnet2, Client<Testnet2>>;
+
+    /// Returns a connected pair of sockets, and the listener address that both sides advertise.
+    async fn connect() -> (TcpStream, TcpStream, SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_ip = listener.local_addr().unwrap();
+        let (outbound, inbound) = tokio::join!(TcpStream::connect(local_ip), listener.accept());
+        (outbound.unwrap(), inbound.unwrap().0, local_ip)
+    }
+
+    /// Performs the handshake with the given peer, returning the node ID of the peer upon success.
+    async fn handshake(
+        stream: TcpStream,
+        local_ip: SocketAddr,
+        local_nonce: u64,
+        local_key: &NodeKey,
+        connected_node_ids: &[NodePublicKey],
+    ) -> Result<Option<NodePublicKey>> {
+        let mut socket = Framed::new(stream, MessageCodec::default());
+        let (.., node_id) = TestPeer::handshake(&mut socket, local_ip, local_nonce, local_key, 0, &[], connected_node_ids).await?;
+        Ok(node_id)
+    }
+
+    #[tokio::test]
+    async fn test_handshake_authenticates_node_id() {
+        let (local_key, peer_key) = (NodeKey::new().unwrap(), NodeKey::new().unwrap());
+
+        let (outbound, inbound, local_ip) = connect().await;
+        let (local_result, peer_result) = tokio::join!(
+            handshake(outbound, local_ip, 1, &local_key, &[]),
+            handshake(inbound, local_ip, 2, &peer_key, &[]),
+        );
+        assert_eq!(Some(peer_key.public_key()), local_result.unwrap());
+        assert_eq!(Some(local_key.public_key()), peer_result.unwrap());
+
+        // A node that is already connected is rejected under another address.
+        let (outbound, inbound, local_ip) = connect().await;
+        let (local_result, _) = tokio::join!(
+            handshake(outbound, local_ip, 1, &local_key, &[peer_key.public_key()]),
+            handshake(inbound, local_ip, 2, &peer_key, &[]),
+        );
+        assert!(local_result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handshake_discards_unauthenticated_node_id() {
+        let (local_key, peer_key) = (NodeKey::new().unwrap(), NodeKey::new().unwrap());
+        let (outbound, inbound, local_ip) = connect().await;
+
+        // Act as a peer that does not support encryption, and advertises the node ID of a connected peer.
+        let peer = async move {
+            let mut socket = Framed::new(inbound, MessageCodec::<Testnet2, Client<Testnet2>>::default());
+            let genesis_header = Testnet2::genesis_block().header().clone();
+            socket
+                .send(TestMessage::ChallengeRequest(
+                    Client::<Testnet2>::MESSAGE_VERSION,
+                    Testnet2::ALEO_MAXIMUM_FORK_DEPTH,
+                    NodeType::Client,
+                    State::Ready,
+                    local_ip.port(),
+                    2,
+                    0,
+                    Capabilities::empty(),
+                    Some(peer_key.public_key()),
+                ))
+                .await
+                .unwrap();
+            socket
+                .send(TestMessage::ChallengeResponse(Data::Object(genesis_header)))
+                .await
+                .unwrap();
+            assert!(matches!(socket.next().await, Some(Ok(TestMessage::ChallengeRequest(..)))));
+            assert!(matches!(socket.next().await, Some(Ok(TestMessage::ChallengeResponse(..)))));
+        };
+
+        // The connection is not rejected, as the node ID of the peer is not authenticated.
+        let (local_result, _) = tokio::join!(handshake(outbound, local_ip, 1, &local_key, &[peer_key.public_key()]), peer);
+        assert_eq!(None, local_result.unwrap());
+    }
+
+    #[test]
+    fn test_inventory_batches_transactions() {
+        let mut inventory = Inventory::<Testnet2, Client<Testnet2>>::default();
+        let transaction = Testnet2::genesis_block().transactions().first().unwrap().clone();
+        let transaction_id = transaction.transaction_id();
+
+        // A single transaction is not announced until the pending transaction IDs are taken.
+        assert!(inventory
+            .announce(TestMessage::UnconfirmedTransaction(transaction.clone()))
+            .is_none());
+        match inventory.take_transactions() {
+            Some(TestMessage::TransactionInventory(transaction_ids)) => assert_eq!(vec![transaction_id], transaction_ids),
+            _ => panic!("Failed to announce the pending transaction"),
+        }
+        assert!(inventory.take_transactions().is_none());
+
+        // The transaction IDs are announced in a batch once the maximum number of inventory items is pending.
+        for _ in 1..Client::<Testnet2>::MAXIMUM_INVENTORY_ITEMS {
+            assert!(inventory
+                .announce(TestMessage::UnconfirmedTransaction(transaction.clone()))
+                .is_none());
+        }
+        match inventory.announce(TestMessage::UnconfirmedTransaction(transaction)) {
+            Some(TestMessage::TransactionInventory(transaction_ids)) => {
+                assert_eq!(Client::<Testnet2>::MAXIMUM_INVENTORY_ITEMS, transaction_ids.len())
+            }
+            _ => panic!("Failed to announce the batch of transactions"),
+        }
+        assert!(inventory.take_transactions().is_none());
+
+        // Other messages are returned unchanged.
+        assert!(matches!(
+            inventory.announce(TestMessage::MemoryPoolRequest),
+            Some(TestMessage::MemoryPoolRequest)
+        ));
+    }
+
+    #[test]
+    fn test_inventory_serves_requested_blocks() {
+        let mut inventory = Inventory::<Testnet2, Client<Testnet2>>::default();
+        let block = Testnet2::genesis_block().clone();
+        let (block_height, block_hash) = (block.height(), block.hash());
+
+        // The block is announced by its hash, and retained until the peer requests it.
+        match inventory.announce(TestMessage::UnconfirmedBlock(block_height, block_hash, Data::Object(block))) {
+            Some(TestMessage::BlockInventory(height, hash)) => assert_eq!((block_height, block_hash), (height, hash)),
+            _ => panic!("Failed to announce the block"),
+        }
+        assert!(inventory.take_blocks(&[]).is_empty());
+
+        // The block is served once on request.
+        let messages = inventory.take_blocks(&[block_hash]);
+        assert_eq!(1, messages.len());
+        assert!(matches!(&messages[0], TestMessage::UnconfirmedBlock(height, hash, _) if (*height, *hash) == (block_height, block_hash)));
+        assert!(inventory.take_blocks(&[block_hash]).is_empty());
+    }
+}
@@ -17,22 +17,39 @@
 use crate::{Data, Environment, LedgerReader, LedgerRouter, Message, OperatorRouter, OutboundRouter, Peer, ProverRouter};
 use snarkvm::dpc::prelude::*;
 
-use crate::helpers::NodeType;
+use crate::helpers::{
+    address_group,
+    canonical_ip,
+    canonical_socket_addr,
+    BanEntry,
+    BanList,
+    NodeKey,
+    NodePublicKey,
+    NodeType,
+    PeerBook,
+    PeerInfo,
+};
 use anyhow::Result;
+use ipnet::IpNet;
 use rand::{prelude::IteratorRandom, rngs::OsRng, thread_rng, Rng};
 use std::{
     collections::{HashMap, HashSet},
-    net::SocketAddr,
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
     sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
 use tokio::{
     net::TcpStream,
-    sync::{mpsc, oneshot, RwLock},
+    sync::{mpsc, oneshot, Mutex, RwLock},
     task,
     time::timeout,
 };
 
+/// The duration in seconds after which an inventory request is presumed lost, and the item may be requested from another peer.
+const INVENTORY_REQUEST_TIMEOUT_IN_SECS: u64 = 10;
+
 /// Shorthand for the parent half of the `Peers` message channel.
 pub(crate) type PeersRouter<N, E> = mpsc::Sender<PeersRequest<N, E>>;
 #[allow(unused)]
@@ -58,6 +75,8 @@ pub enum PeersRequest<N: Network, E: Environment> {
     ),
     /// Heartbeat := (ledger_reader, ledger_router, operator_router, prover_router)
     Heartbeat(LedgerReader<N>, LedgerRouter<N>, OperatorRouter<N>, ProverRouter<N>),
+    /// InventoryAnnouncement := (peer_ip, \[block_hash\], \[transaction_id\])
+    InventoryAnnouncement(SocketAddr, Vec<N::BlockHash>, Vec<N::TransactionID>),
     /// MessagePropagate := (peer_ip, message)
     MessagePropagate(SocketAddr, Message<N, E>),
     MessagePropagateProver(Message<N, E>),
@@ -72,13 +91,17 @@ pub enum PeersRequest<N: Network, E: Environment> {
         OperatorRouter<N>,
         ProverRouter<N>,
     ),
-    /// PeerConnected := (peer_ip, peer_nonce, outbound_router)
-    PeerConnected(SocketAddr, u64, OutboundRouter<N, E>),
+    /// PeerConnected := (peer_ip, peer_nonce, node_type, node_id, is_outbound, outbound_router)
+    PeerConnected(SocketAddr, u64, NodeType, Option<NodePublicKey>, bool, OutboundRouter<N, E>),
     PeerIsProver(SocketAddr),
     /// PeerDisconnected := (peer_ip)
     PeerDisconnected(SocketAddr),
     /// PeerRestricted := (peer_ip)
     PeerRestricted(SocketAddr),
+    /// PeerRoundTripTime := (peer_ip, rtt)
+    PeerRoundTripTime(SocketAddr, Duration),
+    /// PeerBanned := (peer_ip, ban_duration_in_secs)
+    PeerBanned(SocketAddr, u64),
     /// SendPeerResponse := (peer_ip)
     SendPeerResponse(SocketAddr),
     /// ReceivePeerResponse := (\[peer_ip\])
@@ -95,46 +118,135 @@ pub struct Peers<N: Network, E: Environment> {
     local_ip: SocketAddr,
     /// The local nonce for this node session.
     local_nonce: u64,
-    /// The map connected peer IPs to their nonce and outbound message router.
-    connected_peers: RwLock<HashMap<SocketAddr, (u64, OutboundRouter<N, E>)>>,
+    /// The static keypair of this node, whose public key is the node ID.
+    local_key: NodeKey,
+    /// The map connected peer IPs to their nonce, node ID (if authenticated), connection direction, and outbound message router.
+    connected_peers: RwLock<HashMap<SocketAddr, (u64, Option<NodePublicKey>, bool, OutboundRouter<N, E>)>>,
     /// The set of candidate peer IPs.
     candidate_peers: RwLock<HashSet<SocketAddr>>,
-    /// The set of restricted peer IPs.
+    /// The map of restricted peer IPs to the time at which their restriction expires.
     restricted_peers: RwLock<HashMap<SocketAddr, Instant>>,
     prover_peers: RwLock<HashSet<SocketAddr>>,
     /// The map of peers to their first-seen port number, number of attempts, and timestamp of the last inbound connection request.
     seen_inbound_connections: RwLock<HashMap<SocketAddr, ((u16, u32), SystemTime)>>,
     /// The map of peers to the timestamp of their last outbound connection request.
     seen_outbound_connections: RwLock<HashMap<SocketAddr, SystemTime>>,
+    /// The set of peers dialed by the heartbeat, whose connection result is pending.
+    pending_outbound_connections: Arc<RwLock<HashSet<SocketAddr>>>,
+    /// The map of announced block hashes to the timestamp of their inventory request.
+    requested_blocks: RwLock<HashMap<N::BlockHash, Instant>>,
+    /// The map of announced transaction IDs to the timestamp of their inventory request.
+    requested_transactions: RwLock<HashMap<N::TransactionID, Instant>>,
+    /// The record of known peers, which is persisted across restarts.
+    peer_book: RwLock<PeerBook>,
+    /// The path of the peer book file, if the peer book is persisted.
+    peer_book_path: Option<PathBuf>,
+    /// The peer book as of its last save, which also serializes the saves of the peer book.
+    saved_peer_book: Mutex<Option<PeerBook>>,
+    /// The list of IP addresses and subnets banned by the node operator.
+    ban_list: RwLock<BanList>,
+    /// The path of the ban list file, if the ban list is persisted.
+    ban_list_path: Option<PathBuf>,
 }
 
 impl<N: Network, E: Environment> Peers<N, E> {
     ///
     /// Initializes a new instance of `Peers`.
     ///
-    pub(crate) async fn new(local_ip: SocketAddr, local_nonce: Option<u64>) -> Arc<Self> {
+    /// If a peer book path is given, the candidate peers and restricted peers
+    /// are seeded from the peer book of the previous session.
+    /// If a ban list path is given, the bans of the previous session remain in effect.
+    /// If a node key is not given, an ephemeral node key is generated for this session.
+    ///
+    pub(crate) async fn new(
+        local_ip: SocketAddr,
+        local_nonce: Option<u64>,
+        local_key: Option<NodeKey>,
+        peer_book_path: Option<PathBuf>,
+        ban_list_path: Option<PathBuf>,
+    ) -> Result<Arc<Self>> {
         // Initialize an mpsc channel for sending requests to the `Peers` struct.
         let (peers_router, mut peers_handler) = mpsc::channel(1024);
 
+        // Generate an ephemeral node key, if one is not given.
+        let local_key = match local_key {
+            Some(local_key) => local_key,
+            None => NodeKey::new()?,
+        };
+
         // Sample the nonce.
         let local_nonce = match local_nonce {
             Some(nonce) => nonce,
             None => thread_rng().gen(),
         };
 
+        // Load the peer book, if it is persisted.
+        let peer_book = match &peer_book_path {
+            Some(path) => match PeerBook::load(path) {
+                Ok(mut peer_book) => {
+                    peer_book.prune(E::maximum_candidate_peers());
+                    peer_book
+                }
+                Err(error) => {
+                    warn!("Starting with an empty peer book: {}", error);
+                    PeerBook::default()
+                }
+            },
+            None => PeerBook::default(),
+        };
+
+        // Load the ban list, if it is persisted.
+        let ban_list = match &ban_list_path {
+            Some(path) => match BanList::load(path) {
+                Ok(mut ban_list) => {
+                    ban_list.prune();
+                    ban_list
+                }
+                Err(error) => {
+                    warn!("Starting with an empty ban list: {}", error);
+                    BanList::default()
+                }
+            },
+            None => BanList::default(),
+        };
+
         // Initialize the peers.
         let peers = Arc::new(Self {
             peers_router,
             local_ip,
             local_nonce,
+            local_key,
             connected_peers: Default::default(),
             candidate_peers: Default::default(),
             restricted_peers: Default::default(),
             prover_peers: Default::default(),
             seen_inbound_connections: Default::default(),
             seen_outbound_connections: Default::default(),
+            pending_outbound_connections: Default::default(),
+            requested_blocks: Default::default(),
+            requested_transactions: Default::default(),
+            peer_book: RwLock::new(peer_book),
+            peer_book_path,
+            saved_peer_book: Default::default(),
+            ban_list: RwLock::new(ban_list),
+            ban_list_path,
         });
 
+        // Seed the candidate peers and restricted peers from the peer book.
+        {
+            let peer_book = peers.peer_book.read().await;
+            peers.add_candidate_peers(peer_book.candidate_peers().iter()).await;
+
+            let mut restricted_peers = peers.restricted_peers.write().await;
+            for (peer_ip, remaining_secs) in peer_book.restricted_peers() {
+                restricted_peers.insert(peer_ip, Instant::now() + Duration::from_secs(remaining_secs));
+            }
+
+            if !peer_book.is_empty() {
+                debug!("Loaded {} peers from the peer book", peer_book.len());
+            }
+        }
+
         // Initialize the peers router process.
         {
             let peers = peers.clone();
@@ -156,7 +268,7 @@ impl<N: Network, E: Environment> Peers<N, E> {
             let _ = handler.await;
         }
 
-        peers
+        Ok(peers)
     }
 
     /// Returns an instance of the peers router.
@@ -164,6 +276,16 @@ impl<N: Network, E: Environment> Peers<N, E> {
         self.peers_router.clone()
     }
 
+    ///
+    /// Returns `true` if the given IP is this node, at its listener address or at the loopback
+    /// or unspecified address of either IP version on the listener port.
+    ///
+    fn is_local_ip(&self, ip: SocketAddr) -> bool {
+        let ip = canonical_socket_addr(ip);
+        ip == canonical_socket_addr(self.local_ip)
+            || (ip.ip().is_unspecified() || ip.ip().is_loopback()) && ip.port() == self.local_ip.port()
+    }
+
     ///
     /// Returns `true` if the node is connected to the given IP.
     ///
@@ -176,11 +298,79 @@ impl<N: Network, E: Environment> Peers<N, E> {
     ///
     pub async fn is_restricted(&self, ip: SocketAddr) -> bool {
         match self.restricted_peers.read().await.get(&ip) {
-            Some(timestamp) => timestamp.elapsed().as_secs() < E::RADIO_SILENCE_IN_SECS,
+            Some(restricted_until) => Instant::now() < *restricted_until,
             None => false,
         }
     }
 
+    ///
+    /// Returns `true` if the node may connect to the given peer IP, which is always the case outside of a private network.
+    ///
+    pub fn is_trusted(&self, ip: SocketAddr) -> bool {
+        is_trusted_peer(E::trusted_peers(), ip)
+    }
+
+    ///
+    /// Returns `true` if the node may accept a connection from the given IP address,
+    /// which is always the case outside of a private network.
+    ///
+    /// An inbound connection arrives from an ephemeral port, so it is only pre-filtered by IP address here.
+    /// The handshake then requires the listener address of the peer to be a trusted peer.
+    ///
+    pub fn is_trusted_ip(&self, ip: IpAddr) -> bool {
+        is_trusted_peer_ip(E::trusted_peers(), ip)
+    }
+
+    ///
+    /// Returns `true` if the given IP address is banned.
+    ///
+    pub async fn is_banned(&self, ip: IpAddr) -> bool {
+        self.ban_list.read().await.is_banned(ip)
+    }
+
+    ///
+    /// Returns the list of active bans.
+    ///
+    pub async fn banned_peers(&self) -> Vec<BanEntry> {
+        self.ban_list.read().await.entries()
+    }
+
+    ///
+    /// Bans the given subnet, for the given number of seconds or permanently if `None`,
+    /// and disconnects from any connected peers in the subnet.
+    ///
+    pub async fn ban(&self, subnet: IpNet, duration_in_secs: Option<u64>) -> Result<()> {
+        {
+            let mut ban_list = self.ban_list.write().await;
+            ban_list.ban(subnet, duration_in_secs);
+            self.save_ban_list(&mut ban_list)?;
+        }
+
+        // Remove the banned candidate peers.
+        self.candidate_peers.write().await.retain(|peer_ip| !subnet.contains(&peer_ip.ip()));
+
+        // Disconnect from the banned connected peers.
+        for peer_ip in self.connected_peers().await {
+            if subnet.contains(&peer_ip.ip()) {
+                info!("Disconnecting from {} (banned)", peer_ip);
+                self.send(peer_ip, Message::Disconnect).await;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Lifts the ban on the given subnet, and returns `true` if the subnet was banned.
+    ///
+    pub async fn unban(&self, subnet: IpNet) -> Result<bool> {
+        let mut ban_list = self.ban_list.write().await;
+        let is_unbanned = ban_list.unban(subnet);
+        if is_unbanned {
+            self.save_ban_list(&mut ban_list)?;
+        }
+        Ok(is_unbanned)
+    }
+
     ///
     /// Returns the list of connected peers.
     ///
@@ -188,6 +378,27 @@ impl<N: Network, E: Environment> Peers<N, E> {
         self.connected_peers.read().await.keys().copied().collect()
     }
 
+    ///
+    /// Returns the information of the connected peers, including their last measured round-trip time.
+    ///
+    pub async fn connected_peers_info(&self) -> Vec<PeerInfo> {
+        let peer_book = self.peer_book.read().await;
+        self.connected_peers
+            .read()
+            .await
+            .iter()
+            .map(|(peer_ip, (_, _, is_outbound, _))| {
+                let record = peer_book.get(peer_ip);
+                PeerInfo {
+                    ip: *peer_ip,
+                    node_type: record.and_then(|record| record.node_type),
+                    is_outbound: *is_outbound,
+                    rtt_in_millis: record.and_then(|record| record.rtt_in_millis),
+                }
+            })
+            .collect()
+    }
+
     ///
     /// Returns the list of candidate peers.
     ///
@@ -220,6 +431,39 @@ impl<N: Network, E: Environment> Peers<N, E> {
         self.connected_peers.read().await.len()
     }
 
+    ///
+    /// Returns the number of connected peers that this node dialed.
+    ///
+    pub async fn number_of_outbound_peers(&self) -> usize {
+        self.connected_peers
+            .read()
+            .await
+            .values()
+            .filter(|(_, _, is_outbound, _)| *is_outbound)
+            .count()
+    }
+
+    ///
+    /// Returns the number of outbound connections that the heartbeat may still dial,
+    /// counting the peers that are connected outbound or are still being dialed.
+    ///
+    async fn number_of_outbound_slots(&self) -> usize {
+        let number_of_outbound_peers = self.number_of_outbound_peers().await + self.pending_outbound_connections.read().await.len();
+        E::maximum_number_of_outbound_peers().saturating_sub(number_of_outbound_peers)
+    }
+
+    ///
+    /// Returns the number of connected peers that dialed this node.
+    ///
+    pub async fn number_of_inbound_peers(&self) -> usize {
+        self.connected_peers
+            .read()
+            .await
+            .values()
+            .filter(|(_, _, is_outbound, _)| !*is_outbound)
+            .count()
+    }
+
     ///
     /// Returns the number of candidate peers.
     ///
@@ -227,6 +471,49 @@ impl<N: Network, E: Environment> Peers<N, E> {
         self.candidate_peers.read().await.len()
     }
 
+    ///
+    /// Returns the number of peers connected in the given direction from each address group.
+    ///
+    async fn address_groups(&self, is_outbound: bool) -> HashMap<IpNet, usize> {
+        let mut address_groups = HashMap::new();
+        for (peer_ip, (_, _, peer_is_outbound, _)) in self.connected_peers.read().await.iter() {
+            if *peer_is_outbound == is_outbound {
+                if let Some(address_group) = address_group(peer_ip.ip()) {
+                    *address_groups.entry(address_group).or_insert(0) += 1;
+                }
+            }
+        }
+        address_groups
+    }
+
+    ///
+    /// Returns `true` if the given IP address is a sync node, beacon node, or trusted peer,
+    /// which are exempt from the limits on address groups.
+    ///
+    fn is_exempt_from_subnet_limits(&self, ip: IpAddr) -> bool {
+        E::sync_nodes()
+            .iter()
+            .chain(E::beacon_nodes().iter())
+            .chain(E::trusted_peers().into_iter().flatten())
+            .any(|peer_ip| canonical_ip(peer_ip.ip()) == canonical_ip(ip))
+    }
+
+    ///
+    /// Returns `true` if the address group of the given IP address has reached the maximum number of inbound connections.
+    ///
+    async fn is_subnet_saturated(&self, ip: IpAddr) -> bool {
+        if self.is_exempt_from_subnet_limits(ip) {
+            return false;
+        }
+        match address_group(ip) {
+            Some(address_group) => {
+                let number_of_inbound_peers = self.address_groups(false).await.get(&address_group).copied().unwrap_or(0);
+                number_of_inbound_peers >= E::maximum_inbound_peers_per_subnet()
+            }
+            None => false,
+        }
+    }
+
     ///
     /// Returns the list of nonces for the connected peers.
     ///
@@ -235,7 +522,26 @@ impl<N: Network, E: Environment> Peers<N, E> {
             .read()
             .await
             .values()
-            .map(|(peer_nonce, _)| *peer_nonce)
+            .map(|(peer_nonce, _, _, _)| *peer_nonce)
+            .collect()
+    }
+
+    ///
+    /// Returns the node ID of this node, which is the public key of its node key.
+    ///
+    pub fn node_id(&self) -> NodePublicKey {
+        self.local_key.public_key()
+    }
+
+    ///
+    /// Returns the node IDs of the connected peers that authenticated one over an encrypted connection.
+    ///
+    pub async fn connected_node_ids(&self) -> HashMap<SocketAddr, NodePublicKey> {
+        self.connected_peers
+            .read()
+            .await
+            .iter()
+            .filter_map(|(peer_ip, (_, node_id, _, _))| node_id.map(|node_id| (*peer_ip, node_id)))
             .collect()
     }
 
@@ -246,10 +552,11 @@ impl<N: Network, E: Environment> Peers<N, E> {
     pub(super) async fn update(&self, request: PeersRequest<N, E>) {
         match request {
             PeersRequest::Connect(peer_ip, ledger_reader, ledger_router, operator_router, prover_router, connection_result) => {
+                // Canonicalize the peer IP, so an IPv4-mapped IPv6 address is treated as the IPv4 address.
+                let peer_ip = canonical_socket_addr(peer_ip);
+
                 // Ensure the peer IP is not this node.
-                if peer_ip == self.local_ip
-                    || (peer_ip.ip().is_unspecified() || peer_ip.ip().is_loopback()) && peer_ip.port() == self.local_ip.port()
-                {
+                if self.is_local_ip(peer_ip) {
                     debug!("Skipping connection request to {} (attempted to self-connect)", peer_ip);
                 }
                 // Ensure the node does not surpass the maximum number of peer connections.
@@ -257,7 +564,7 @@ impl<N: Network, E: Environment> Peers<N, E> {
                     .number_of_connected_peers()
                     .await
                     .saturating_sub(self.prover_peers.read().await.len())
-                    >= E::MAXIMUM_NUMBER_OF_PEERS
+                    >= E::maximum_number_of_peers()
                 {
                     debug!("Skipping connection request to {} (maximum peers reached)", peer_ip);
                 }
@@ -269,6 +576,14 @@ impl<N: Network, E: Environment> Peers<N, E> {
                 else if self.is_restricted(peer_ip).await {
                     debug!("Skipping connection request to {} (restricted)", peer_ip);
                 }
+                // Ensure the peer is not banned.
+                else if self.is_banned(peer_ip.ip()).await {
+                    debug!("Skipping connection request to {} (banned)", peer_ip);
+                }
+                // Ensure the peer is trusted, if the node is in a private network.
+                else if !self.is_trusted(peer_ip) {
+                    debug!("Skipping connection request to {} (not a trusted peer)", peer_ip);
+                }
                 // Attempt to open a TCP stream.
                 else {
                     // Lock seen_outbound_connections for further processing.
@@ -277,7 +592,7 @@ impl<N: Network, E: Environment> Peers<N, E> {
                     // Ensure the node respects the connection frequency limit.
                     let last_seen = seen_outbound_connections.entry(peer_ip).or_insert(SystemTime::UNIX_EPOCH);
                     let elapsed = last_seen.elapsed().unwrap_or(Duration::MAX).as_secs();
-                    if elapsed < E::RADIO_SILENCE_IN_SECS {
+                    if elapsed < E::radio_silence_in_secs() {
                         trace!("Skipping connection request to {} (tried {} secs ago)", peer_ip, elapsed);
                     } else {
                         debug!("Connecting to {}...", peer_ip);
@@ -288,19 +603,26 @@ impl<N: Network, E: Environment> Peers<N, E> {
                         drop(seen_outbound_connections);
 
                         // Initialize the peer handler.
-                        match timeout(Duration::from_millis(E::CONNECTION_TIMEOUT_IN_MILLIS), TcpStream::connect(peer_ip)).await {
+                        match timeout(
+                            Duration::from_millis(E::connection_timeout_in_millis()),
+                            TcpStream::connect(peer_ip),
+                        )
+                        .await
+                        {
                             Ok(stream) => match stream {
                                 Ok(stream) => {
                                     Peer::handler(
                                         stream,
                                         self.local_ip,
                                         self.local_nonce,
+                                        self.local_key.clone(),
                                         &self.peers_router,
                                         ledger_reader,
                                         ledger_router,
                                         prover_router,
                                         operator_router,
                                         self.connected_nonces().await,
+                                        self.connected_node_ids().await.into_values().collect(),
                                         Some(connection_result),
                                     )
                                     .await
@@ -308,27 +630,43 @@ impl<N: Network, E: Environment> Peers<N, E> {
                                 Err(error) => {
                                     trace!("Failed to connect to '{}': '{:?}'", peer_ip, error);
                                     self.candidate_peers.write().await.remove(&peer_ip);
+                                    self.peer_book.write().await.record_failure(peer_ip);
                                 }
                             },
                             Err(error) => {
                                 error!("Unable to reach '{}': '{:?}'", peer_ip, error);
                                 self.candidate_peers.write().await.remove(&peer_ip);
+                                self.peer_book.write().await.record_failure(peer_ip);
                             }
                         };
                     }
                 }
             }
             PeersRequest::Heartbeat(ledger_reader, ledger_router, operator_router, prover_router) => {
+                // Persist the peer book.
+                self.save_peer_book().await;
+
+                // Remove the inventory requests that are presumed lost.
+                let inventory_request_timeout = Duration::from_secs(INVENTORY_REQUEST_TIMEOUT_IN_SECS);
+                self.requested_blocks
+                    .write()
+                    .await
+                    .retain(|_, requested_at| requested_at.elapsed() < inventory_request_timeout);
+                self.requested_transactions
+                    .write()
+                    .await
+                    .retain(|_, requested_at| requested_at.elapsed() < inventory_request_timeout);
+
                 // Obtain the number of connected peers.
                 let number_of_connected_peers = self.number_of_connected_peers().await;
                 // Ensure the number of connected peers is below the maximum threshold.
-                if number_of_connected_peers.saturating_sub(self.prover_peers.read().await.len()) > E::MAXIMUM_NUMBER_OF_PEERS {
+                if number_of_connected_peers.saturating_sub(self.prover_peers.read().await.len()) > E::maximum_number_of_peers() {
                     debug!("Exceeded maximum number of connected peers");
 
                     // Determine the peers to disconnect from.
                     let num_excess_peers = number_of_connected_peers
                         .saturating_sub(self.prover_peers.read().await.len())
-                        .saturating_sub(E::MAXIMUM_NUMBER_OF_PEERS);
+                        .saturating_sub(E::maximum_number_of_peers());
                     let peer_ips_to_disconnect = self
                         .connected_peers
                         .read()
@@ -353,7 +691,7 @@ impl<N: Network, E: Environment> Peers<N, E> {
                         info!("Disconnecting from {} (exceeded maximum connections)", peer_ip);
                         self.send(peer_ip, Message::Disconnect).await;
                         // Add an entry for this `Peer` in the restricted peers.
-                        self.restricted_peers.write().await.insert(peer_ip, Instant::now());
+                        self.restrict(peer_ip, E::radio_silence_in_secs()).await;
                     }
                 }
 
@@ -374,21 +712,27 @@ impl<N: Network, E: Environment> Peers<N, E> {
                         info!("Disconnecting from {} (exceeded maximum connections)", peer_ip);
                         self.send(peer_ip, Message::Disconnect).await;
                         // Add an entry for this `Peer` in the restricted peers.
-                        self.restricted_peers.write().await.insert(peer_ip, Instant::now());
+                        self.restrict(peer_ip, E::radio_silence_in_secs()).await;
                     }
                 }
 
-                // Skip if the number of connected peers is above the minimum threshold.
-                match number_of_connected_peers < E::MINIMUM_NUMBER_OF_PEERS {
-                    true => {
-                        trace!("Sending request for more peer connections");
-                        // Request more peers if the number of connected peers is below the threshold.
-                        for peer_ip in self.connected_peers().await.iter().choose_multiple(&mut OsRng::default(), 3) {
-                            self.send(*peer_ip, Message::PeerRequest).await;
-                        }
+                // Skip if the number of connected peers is above the minimum threshold, and the outbound connections are full.
+                // The peers that are still being dialed are counted, so that the outbound connections are not overfilled.
+                let number_of_outbound_slots = self.number_of_outbound_slots().await;
+                if number_of_connected_peers >= E::minimum_number_of_peers() && number_of_outbound_slots == 0 {
+                    return;
+                }
+
+                // Request more peers if the number of connected peers is below the threshold, or there are no candidate peers
+                // to fill the outbound connections, unless the node is in a private network, where peers are not gossiped.
+                if (number_of_connected_peers < E::minimum_number_of_peers() || self.number_of_candidate_peers().await == 0)
+                    && E::trusted_peers().is_none()
+                {
+                    trace!("Sending request for more peer connections");
+                    for peer_ip in self.connected_peers().await.iter().choose_multiple(&mut OsRng::default(), 3) {
+                        self.send(*peer_ip, Message::PeerRequest).await;
                     }
-                    false => return,
-                };
+                }
 
                 // Add the sync nodes to the list of candidate peers.
                 if number_of_connected_sync_nodes == 0 {
@@ -398,24 +742,60 @@ impl<N: Network, E: Environment> Peers<N, E> {
                 // Add the beacon nodes to the list of candidate peers.
                 self.add_candidate_peers(E::beacon_nodes().iter()).await;
 
-                // Attempt to connect to more peers if the number of connected peers is below the minimum threshold.
-                // Select the peers randomly from the list of candidate peers.
-                let midpoint_number_of_peers = E::MINIMUM_NUMBER_OF_PEERS.saturating_add(E::MAXIMUM_NUMBER_OF_PEERS) / 2;
-                for peer_ip in self
+                // Add the trusted peers to the list of candidate peers, if the node is in a private network.
+                if let Some(trusted_peers) = E::trusted_peers() {
+                    self.add_candidate_peers(trusted_peers.iter()).await;
+                }
+
+                // Attempt to connect to more peers to fill the outbound connections.
+                // Select the peers randomly from the list of candidate peers, and prefer those with the lowest round-trip time.
+                // Dial at most one peer from each address group, so that the outbound connections span diverse subnets.
+                // The sync nodes, beacon nodes, and trusted peers are exempt, and do not take up the outbound connections.
+                let midpoint_number_of_peers = E::minimum_number_of_peers().saturating_add(E::maximum_number_of_peers()) / 2;
+                let number_of_peers_to_dial = midpoint_number_of_peers.min(number_of_outbound_slots);
+                let candidate_peers = self
                     .candidate_peers()
                     .await
                     .iter()
                     .copied()
-                    .choose_multiple(&mut OsRng::default(), midpoint_number_of_peers)
-                {
+                    .choose_multiple(&mut OsRng::default(), midpoint_number_of_peers.saturating_mul(2));
+                let candidate_peers = self.peer_book.read().await.rank_by_round_trip_time(candidate_peers);
+                let mut outbound_address_groups = self.address_groups(true).await.into_keys().collect::<HashSet<_>>();
+                let mut number_of_dialed_peers = 0;
+                for peer_ip in candidate_peers {
+                    let is_exempt = self.is_exempt_from_subnet_limits(peer_ip.ip());
+                    if !is_exempt && number_of_dialed_peers >= number_of_peers_to_dial {
+                        continue;
+                    }
+
                     // Ensure this node is not connected to more than the permitted number of sync nodes.
                     if E::sync_nodes().contains(&peer_ip) && number_of_connected_sync_nodes >= 1 {
                         continue;
                     }
 
+                    // Ensure this node is not already dialing this peer.
+                    if self.pending_outbound_connections.read().await.contains(&peer_ip) {
+                        continue;
+                    }
+
+                    // Ensure this node is not connected to another outbound peer from the address group of this peer.
+                    if !is_exempt {
+                        if let Some(address_group) = address_group(peer_ip.ip()) {
+                            if !outbound_address_groups.insert(address_group) {
+                                continue;
+                            }
+                        }
+                    }
+
                     if !self.is_connected_to(peer_ip).await {
+                        if !is_exempt {
+                            number_of_dialed_peers += 1;
+                        }
                         trace!("Attempting connection to {}...", peer_ip);
 
+                        // Count the peer towards the outbound connections until its connection result is known.
+                        self.pending_outbound_connections.write().await.insert(peer_ip);
+
                         // Initialize the connection process.
                         let (router, handler) = oneshot::channel();
                         let request = PeersRequest::Connect(
@@ -430,12 +810,17 @@ impl<N: Network, E: Environment> Peers<N, E> {
                             warn!("Failed to transmit the request: '{}'", error);
                         }
                         // Do not wait for the result of each connection.
+                        let pending_outbound_connections = self.pending_outbound_connections.clone();
                         E::tasks().append(task::spawn(async move {
                             let _ = handler.await;
+                            pending_outbound_connections.write().await.remove(&peer_ip);
                         }));
                     }
                 }
             }
+            PeersRequest::InventoryAnnouncement(peer_ip, block_hashes, transaction_ids) => {
+                self.request_inventory(peer_ip, block_hashes, transaction_ids).await;
+            }
             PeersRequest::MessagePropagate(sender, message) => {
                 self.propagate(sender, message).await;
             }
@@ -446,16 +831,29 @@ impl<N: Network, E: Environment> Peers<N, E> {
                 self.send(sender, message).await;
             }
             PeersRequest::PeerConnecting(stream, peer_ip, ledger_reader, ledger_router, operator_router, prover_router) => {
+                // Canonicalize the peer IP, as a dual-stack listener reports IPv4 peers by their IPv4-mapped IPv6 address.
+                let peer_ip = canonical_socket_addr(peer_ip);
+
                 // Ensure the peer IP is not this node.
-                if peer_ip == self.local_ip
-                    || (peer_ip.ip().is_unspecified() || peer_ip.ip().is_loopback()) && peer_ip.port() == self.local_ip.port()
-                {
+                if self.is_local_ip(peer_ip) {
                     debug!("Skipping connection request to {} (attempted to self-connect)", peer_ip);
                 }
                 // Ensure the node does not surpass the maximum number of peer connections.
-                else if E::NODE_TYPE != NodeType::Operator && self.number_of_connected_peers().await >= E::MAXIMUM_NUMBER_OF_PEERS {
+                else if E::NODE_TYPE != NodeType::Operator && self.number_of_connected_peers().await >= E::maximum_number_of_peers() {
                     debug!("Dropping connection request from {} (maximum peers reached)", peer_ip);
                 }
+                // Ensure the node does not surpass the maximum number of inbound connections.
+                else if E::NODE_TYPE != NodeType::Operator && self.number_of_inbound_peers().await >= E::maximum_number_of_inbound_peers()
+                {
+                    debug!("Dropping connection request from {} (maximum inbound peers reached)", peer_ip);
+                }
+                // Ensure the node does not surpass the maximum number of inbound connections from the subnet of the peer.
+                else if E::NODE_TYPE != NodeType::Operator && self.is_subnet_saturated(peer_ip.ip()).await {
+                    debug!(
+                        "Dropping connection request from {} (maximum inbound peers from its subnet reached)",
+                        peer_ip
+                    );
+                }
                 // Ensure the node is not already connected to this peer.
                 else if self.is_connected_to(peer_ip).await {
                     debug!("Dropping connection request from {} (already connected)", peer_ip);
@@ -464,6 +862,14 @@ impl<N: Network, E: Environment> Peers<N, E> {
                 else if self.is_restricted(peer_ip).await {
                     debug!("Dropping connection request from {} (restricted)", peer_ip);
                 }
+                // Ensure the peer is not banned.
+                else if self.is_banned(peer_ip.ip()).await {
+                    debug!("Dropping connection request from {} (banned)", peer_ip);
+                }
+                // Ensure the peer is trusted, if the node is in a private network.
+                else if !self.is_trusted_ip(peer_ip.ip()) {
+                    debug!("Dropping connection request from {} (not a trusted peer)", peer_ip);
+                }
                 // Spawn a handler to be run asynchronously.
                 else {
                     // Sanitize the port from the peer, if it is a remote IP address.
@@ -484,7 +890,7 @@ impl<N: Network, E: Environment> Peers<N, E> {
                     let elapsed = last_seen.elapsed().unwrap_or(Duration::MAX).as_secs();
 
                     // Reset the inbound tracker entry for this peer, if the predefined elapsed time has passed.
-                    if elapsed > E::RADIO_SILENCE_IN_SECS {
+                    if elapsed > E::radio_silence_in_secs() {
                         // Reset the initial port for this peer.
                         *initial_port = peer_port;
                         // Reset the number of attempts for this peer.
@@ -494,10 +900,10 @@ impl<N: Network, E: Environment> Peers<N, E> {
                     }
 
                     // Ensure the connecting peer has not surpassed the connection attempt limit.
-                    if *initial_port < peer_port && *num_attempts > E::MAXIMUM_CONNECTION_FAILURES {
+                    if *initial_port < peer_port && *num_attempts > E::maximum_connection_failures() {
                         trace!("Dropping connection request from {} (tried {} secs ago)", peer_ip, elapsed);
                         // Add an entry for this `Peer` in the restricted peers.
-                        self.restricted_peers.write().await.insert(peer_ip, Instant::now());
+                        self.restrict(peer_ip, E::radio_silence_in_secs()).await;
                     } else {
                         debug!("Received a connection request from {}", peer_ip);
                         // Update the number of attempts for this peer.
@@ -511,23 +917,30 @@ impl<N: Network, E: Environment> Peers<N, E> {
                             stream,
                             self.local_ip,
                             self.local_nonce,
+                            self.local_key.clone(),
                             &self.peers_router,
                             ledger_reader,
                             ledger_router,
                             prover_router,
                             operator_router,
                             self.connected_nonces().await,
+                            self.connected_node_ids().await.into_values().collect(),
                             None,
                         )
                         .await;
                     }
                 }
             }
-            PeersRequest::PeerConnected(peer_ip, peer_nonce, outbound) => {
+            PeersRequest::PeerConnected(peer_ip, peer_nonce, node_type, node_id, is_outbound, outbound) => {
                 // Add an entry for this `Peer` in the connected peers.
-                self.connected_peers.write().await.insert(peer_ip, (peer_nonce, outbound));
+                self.connected_peers
+                    .write()
+                    .await
+                    .insert(peer_ip, (peer_nonce, node_id, is_outbound, outbound));
                 // Remove an entry for this `Peer` in the candidate peers, if it exists.
                 self.candidate_peers.write().await.remove(&peer_ip);
+                // Record the successful connection in the peer book.
+                self.peer_book.write().await.record_success(peer_ip, node_type);
             }
             PeersRequest::PeerIsProver(peer_ip) => {
                 // Add an entry for this `Peer` in the prover peers.
@@ -544,19 +957,116 @@ impl<N: Network, E: Environment> Peers<N, E> {
                 // Remove an entry for this `Peer` in the connected peers, if it exists.
                 self.connected_peers.write().await.remove(&peer_ip);
                 // Add an entry for this `Peer` in the restricted peers.
-                self.restricted_peers.write().await.insert(peer_ip, Instant::now());
+                self.restrict(peer_ip, E::radio_silence_in_secs()).await;
+            }
+            PeersRequest::PeerRoundTripTime(peer_ip, rtt) => {
+                // Record the round-trip time of the peer in the peer book.
+                self.peer_book.write().await.record_round_trip_time(peer_ip, rtt);
+            }
+            PeersRequest::PeerBanned(peer_ip, ban_duration_in_secs) => {
+                // Remove an entry for this `Peer` in the connected peers, if it exists.
+                self.connected_peers.write().await.remove(&peer_ip);
+
+                // Ban the IP address of the peer for the duration of the ban, so that it may not reconnect from another port.
+                // A local or private IP address may be shared by several nodes, so only the peer IP is restricted instead.
+                let ip = canonical_ip(peer_ip.ip());
+                match address_group(ip) {
+                    Some(_) => {
+                        let mut ban_list = self.ban_list.write().await;
+                        // Retain an existing ban, which may be longer or permanent.
+                        if !ban_list.is_banned(ip) {
+                            ban_list.ban(IpNet::from(ip), Some(ban_duration_in_secs));
+                            if let Err(error) = self.save_ban_list(&mut ban_list) {
+                                warn!("Failed to save the ban list: {}", error);
+                            }
+                        }
+                    }
+                    None => self.restrict(peer_ip, ban_duration_in_secs).await,
+                }
             }
             PeersRequest::SendPeerResponse(recipient) => {
+                // Ensure the addresses of the peers are not shared, if the node is in a private network.
+                if E::trusted_peers().is_some() {
+                    trace!("Skipping peer request from {} (private network)", recipient);
+                    return;
+                }
                 // Send a `PeerResponse` message.
                 let connected_peers = self.connected_peers().await;
                 self.send(recipient, Message::PeerResponse(connected_peers)).await;
             }
             PeersRequest::ReceivePeerResponse(peer_ips) => {
+                // Ensure the addresses of other peers are not learned, if the node is in a private network.
+                if E::trusted_peers().is_some() {
+                    return;
+                }
                 self.add_candidate_peers(peer_ips.iter()).await;
             }
         }
     }
 
+    ///
+    /// Adds the given peer IP to the restricted peers for the given duration, and records the restriction in the peer book.
+    ///
+    async fn restrict(&self, peer_ip: SocketAddr, duration_in_secs: u64) {
+        let restricted_until = Instant::now() + Duration::from_secs(duration_in_secs);
+        // Retain a longer restriction that is already in effect.
+        self.restricted_peers
+            .write()
+            .await
+            .entry(peer_ip)
+            .and_modify(|until| *until = (*until).max(restricted_until))
+            .or_insert(restricted_until);
+        self.peer_book.write().await.record_restriction(peer_ip, duration_in_secs);
+    }
+
+    ///
+    /// Ages the peer book and saves it to disk, if the peer book is persisted and has changed since its last save.
+    ///
+    /// The peer book is written from a snapshot on a blocking thread, so the peer book lock is not held during the write.
+    ///
+    async fn save_peer_book(&self) {
+        if let Some(path) = &self.peer_book_path {
+            let peer_book = {
+                let mut peer_book = self.peer_book.write().await;
+                peer_book.prune(E::maximum_candidate_peers());
+                peer_book.clone()
+            };
+
+            // Skip the save if the peer book is unchanged.
+            let mut saved_peer_book = self.saved_peer_book.lock().await;
+            if saved_peer_book.as_ref() == Some(&peer_book) {
+                return;
+            }
+
+            let path = path.clone();
+            match task::spawn_blocking(move || peer_book.save(path).map(|_| peer_book)).await {
+                Ok(Ok(peer_book)) => *saved_peer_book = Some(peer_book),
+                Ok(Err(error)) => warn!("Failed to save the peer book: {}", error),
+                Err(error) => warn!("Failed to save the peer book: {}", error),
+            }
+        }
+    }
+
+    ///
+    /// Removes the expired bans and saves the ban list to disk, if the ban list is persisted.
+    ///
+    fn save_ban_list(&self, ban_list: &mut BanList) -> Result<()> {
+        ban_list.prune();
+        match &self.ban_list_path {
+            Some(path) => ban_list.save(path),
+            None => Ok(()),
+        }
+    }
+
+    ///
+    /// Saves the peer book before the node shuts down.
+    ///
+    pub(super) async fn shut_down(&self) {
+        debug!("Peers are shutting down...");
+        self.save_peer_book().await;
+        trace!("[ShuttingDown] Peer book has been saved");
+    }
+
     ///
     /// Adds the given peer IPs to the set of candidate peers.
     ///
@@ -564,16 +1074,20 @@ impl<N: Network, E: Environment> Peers<N, E> {
     /// as the peer providing this list could be subverting the protocol.
     ///
     async fn add_candidate_peers<'a, T: ExactSizeIterator<Item = &'a SocketAddr> + IntoIterator>(&self, peers: T) {
-        // Acquire the candidate peers write lock.
+        // Acquire the ban list read lock and the candidate peers write lock.
+        let ban_list = self.ban_list.read().await;
         let mut candidate_peers = self.candidate_peers.write().await;
         // Ensure the combined number of peers does not surpass the threshold.
-        for peer_ip in peers.take(E::MAXIMUM_CANDIDATE_PEERS.saturating_sub(candidate_peers.len())) {
-            // Ensure the peer is not self and is a new candidate peer.
-            let is_self = *peer_ip == self.local_ip
-                || (peer_ip.ip().is_unspecified() || peer_ip.ip().is_loopback()) && peer_ip.port() == self.local_ip.port();
-            if !is_self && !self.is_connected_to(*peer_ip).await {
+        for peer_ip in peers.take(E::maximum_candidate_peers().saturating_sub(candidate_peers.len())) {
+            let peer_ip = canonical_socket_addr(*peer_ip);
+            // Ensure the peer is not self, is trusted and not banned, and is a new candidate peer.
+            if !self.is_local_ip(peer_ip)
+                && self.is_trusted(peer_ip)
+                && !ban_list.is_banned(peer_ip.ip())
+                && !self.is_connected_to(peer_ip).await
+            {
                 // Proceed to insert each new candidate peer IP.
-                candidate_peers.insert(*peer_ip);
+                candidate_peers.insert(peer_ip);
             }
         }
     }
@@ -584,7 +1098,7 @@ impl<N: Network, E: Environment> Peers<N, E> {
     async fn send(&self, peer: SocketAddr, message: Message<N, E>) {
         let target_peer = self.connected_peers.read().await.get(&peer).cloned();
         match target_peer {
-            Some((_, outbound)) => {
+            Some((_, _, _, outbound)) => {
                 if let Err(error) = outbound.send(message).await {
                     trace!("Outbound channel failed: {}", error);
                     self.connected_peers.write().await.remove(&peer);
@@ -617,6 +1131,19 @@ impl<N: Network, E: Environment> Peers<N, E> {
         }
     }
 
+    ///
+    /// Requests the announced blocks and transactions from the given peer,
+    /// excluding the items that have already been requested from another peer.
+    ///
+    async fn request_inventory(&self, peer_ip: SocketAddr, block_hashes: Vec<N::BlockHash>, transaction_ids: Vec<N::TransactionID>) {
+        let block_hashes = select_unrequested(&mut *self.requested_blocks.write().await, block_hashes);
+        let transaction_ids = select_unrequested(&mut *self.requested_transactions.write().await, transaction_ids);
+
+        if !block_hashes.is_empty() || !transaction_ids.is_empty() {
+            self.send(peer_ip, Message::InventoryRequest(block_hashes, transaction_ids)).await;
+        }
+    }
+
     async fn propagate_prover(&self, message: Message<N, E>) {
         // Iterate through all provers.
         for peer in self.connected_peers().await.iter() {
@@ -635,5 +1162,165 @@ impl<N: Network, E: Environment> Peers<N, E> {
         self.restricted_peers.write().await.clear();
         self.seen_inbound_connections.write().await.clear();
         self.seen_outbound_connections.write().await.clear();
+        *self.peer_book.write().await = PeerBook::default();
+    }
+}
+
+/// Returns `true` if the given peer IP is one of the given trusted peers, or if there are no trusted peers.
+pub(crate) fn is_trusted_peer(trusted_peers: Option<&HashSet<SocketAddr>>, peer_ip: SocketAddr) -> bool {
+    match trusted_peers {
+        Some(trusted_peers) => {
+            let peer_ip = canonical_socket_addr(peer_ip);
+            trusted_peers
+                .iter()
+                .any(|trusted_peer| canonical_socket_addr(*trusted_peer) == peer_ip)
+        }
+        None => true,
+    }
+}
+
+/// Returns `true` if the given IP address is the IP address of a trusted peer, or if there are no trusted peers.
+pub(crate) fn is_trusted_peer_ip(trusted_peers: Option<&HashSet<SocketAddr>>, ip: IpAddr) -> bool {
+    match trusted_peers {
+        Some(trusted_peers) => trusted_peers.iter().any(|peer_ip| canonical_ip(peer_ip.ip()) == canonical_ip(ip)),
+        None => true,
+    }
+}
+
+/// Returns the given items that have not been requested within `INVENTORY_REQUEST_TIMEOUT_IN_SECS`, and marks them as requested.
+fn select_unrequested<T: Clone + Eq + Hash>(requested: &mut HashMap<T, Instant>, items: Vec<T>) -> Vec<T> {
+    let inventory_request_timeout = Duration::from_secs(INVENTORY_REQUEST_TIMEOUT_IN_SECS);
+    items
+        .into_iter()
+        .filter(|item| {
+            let is_requested = matches!(requested.get(item), Some(requested_at) if requested_at.elapsed() < inventory_request_timeout);
+            if !is_requested {
+                requested.insert(item.clone(), Instant::now());
+            }
+            !is_requested
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Client;
+    use snarkvm::dpc::testnet2::Testnet2;
+
+    type TestPeers = Peers<Testnet2, Client<Testnet2>>;
+
+    /// Initializes the peers of a new node, without a peer book or ban list.
+    async fn new_peers() -> Arc<TestPeers> {
+        TestPeers::new("127.0.0.1:4130".parse().unwrap(), None, None, None, None)
+            .await
+            .unwrap()
+    }
+
+    /// Adds a connected peer in the given direction.
+    async fn add_connected_peer(peers: &TestPeers, peer_ip: &str, is_outbound: bool) {
+        let (outbound_router, _) = mpsc::channel(1);
+        let connected_peer = (thread_rng().gen(), None, is_outbound, outbound_router);
+        peers.connected_peers.write().await.insert(peer_ip.parse().unwrap(), connected_peer);
+    }
+
+    fn trusted_peers() -> HashSet<SocketAddr> {
+        ["1.2.3.4:4132", "127.0.0.1:4133"].iter().map(|ip| ip.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_candidate_peers_must_be_trusted() {
+        let trusted_peers = trusted_peers();
+        let is_trusted = |ip: &str| is_trusted_peer(Some(&trusted_peers), ip.parse().unwrap());
+
+        assert!(is_trusted("1.2.3.4:4132"));
+        assert!(is_trusted("127.0.0.1:4133"));
+        // Another node on the IP address of a trusted peer is not trusted.
+        assert!(!is_trusted("1.2.3.4:4133"));
+        assert!(!is_trusted("127.0.0.1:4134"));
+        assert!(!is_trusted("5.6.7.8:4132"));
+
+        // An IPv4-mapped IPv6 address is trusted as its IPv4 address.
+        assert!(is_trusted("[::ffff:1.2.3.4]:4132"));
+        assert!(!is_trusted("[::ffff:1.2.3.4]:4133"));
+        let mapped_trusted_peers = ["[::ffff:1.2.3.4]:4132".parse().unwrap()].iter().copied().collect();
+        assert!(is_trusted_peer(Some(&mapped_trusted_peers), "1.2.3.4:4132".parse().unwrap()));
+
+        // Outside of a private network, every peer is trusted.
+        assert!(is_trusted_peer(None, "5.6.7.8:4132".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_inbound_peers_must_be_trusted() {
+        let trusted_peers = trusted_peers();
+        let is_trusted_ip = |ip: &str| is_trusted_peer_ip(Some(&trusted_peers), ip.parse().unwrap());
+
+        // Inbound connections are pre-filtered by IP address, as they arrive from an ephemeral port.
+        assert!(is_trusted_ip("1.2.3.4"));
+        assert!(is_trusted_ip("::ffff:1.2.3.4"));
+        assert!(is_trusted_ip("127.0.0.1"));
+        assert!(!is_trusted_ip("5.6.7.8"));
+
+        // The listener address from the handshake must then match a trusted peer exactly.
+        let listener_ip = SocketAddr::new("127.0.0.1".parse().unwrap(), 4134);
+        assert!(!is_trusted_peer(Some(&trusted_peers), listener_ip));
+
+        // Outside of a private network, every peer is trusted.
+        assert!(is_trusted_peer_ip(None, "5.6.7.8".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_inbound_and_outbound_peers() {
+        let peers = new_peers().await;
+        let maximum_number_of_outbound_peers = Client::<Testnet2>::maximum_number_of_outbound_peers();
+        assert_eq!(maximum_number_of_outbound_peers, peers.number_of_outbound_slots().await);
+
+        add_connected_peer(&peers, "1.2.3.4:4132", true).await;
+        add_connected_peer(&peers, "5.6.7.8:4132", false).await;
+        add_connected_peer(&peers, "9.10.11.12:4132", false).await;
+        assert_eq!(3, peers.number_of_connected_peers().await);
+        assert_eq!(1, peers.number_of_outbound_peers().await);
+        assert_eq!(2, peers.number_of_inbound_peers().await);
+
+        // The peers that are still being dialed take up the outbound connections.
+        peers
+            .pending_outbound_connections
+            .write()
+            .await
+            .insert("13.14.15.16:4132".parse().unwrap());
+        assert_eq!(maximum_number_of_outbound_peers - 2, peers.number_of_outbound_slots().await);
+
+        // The inbound peers do not take up the outbound connections.
+        add_connected_peer(&peers, "17.18.19.20:4132", false).await;
+        assert_eq!(maximum_number_of_outbound_peers - 2, peers.number_of_outbound_slots().await);
+    }
+
+    #[tokio::test]
+    async fn test_is_subnet_saturated() {
+        let peers = new_peers().await;
+        let maximum_inbound_peers_per_subnet = Client::<Testnet2>::maximum_inbound_peers_per_subnet();
+
+        // Fill the address group of 1.2.0.0/16 with inbound peers.
+        for i in 0..maximum_inbound_peers_per_subnet {
+            assert!(!peers.is_subnet_saturated(format!("1.2.3.{}", i).parse().unwrap()).await);
+            add_connected_peer(&peers, &format!("1.2.3.{}:4132", i), false).await;
+        }
+        assert!(peers.is_subnet_saturated("1.2.200.1".parse().unwrap()).await);
+        assert!(peers.is_subnet_saturated("::ffff:1.2.200.1".parse().unwrap()).await);
+
+        // Other address groups are not affected.
+        assert!(!peers.is_subnet_saturated("1.3.0.1".parse().unwrap()).await);
+
+        // The outbound peers do not count towards the inbound connections of an address group.
+        for i in 0..maximum_inbound_peers_per_subnet {
+            add_connected_peer(&peers, &format!("5.6.7.{}:4132", i), true).await;
+        }
+        assert!(!peers.is_subnet_saturated("5.6.200.1".parse().unwrap()).await);
+
+        // Local and private addresses have no address group, and are never saturated.
+        for i in 0..maximum_inbound_peers_per_subnet {
+            add_connected_peer(&peers, &format!("192.168.0.{}:4132", i), false).await;
+        }
+        assert!(!peers.is_subnet_saturated("192.168.0.200".parse().unwrap()).await);
     }
 }
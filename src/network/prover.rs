@@ -19,6 +19,7 @@ use crate::{
     Data,
     Environment,
     LedgerReader,
+    LedgerRequest,
     LedgerRouter,
     Message,
     PeersRequest,
@@ -31,6 +32,7 @@ use anyhow::{anyhow, Result};
 use rand::thread_rng;
 use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::{
+    collections::HashSet,
     net::SocketAddr,
     path::Path,
     sync::{atomic::Ordering, Arc},
@@ -56,6 +58,12 @@ pub enum ProverRequest<N: Network> {
     PoolRequest(SocketAddr, u64, BlockTemplate<N>),
     /// MemoryPoolClear := (block)
     MemoryPoolClear(Option<Block<N>>),
+    /// MemoryPoolRequest := (peer_ip)
+    MemoryPoolRequest(SocketAddr),
+    /// MemoryPoolResponse := (peer_ip, \[transaction_id\])
+    MemoryPoolResponse(SocketAddr, Vec<N::TransactionID>),
+    /// TransactionsRequest := (peer_ip, \[transaction_id\])
+    TransactionsRequest(SocketAddr, Vec<N::TransactionID>),
     /// UnconfirmedTransaction := (peer_ip, transaction)
     UnconfirmedTransaction(SocketAddr, Transaction<N>),
     OperatorConnected(SocketAddr),
@@ -70,6 +78,8 @@ pub struct Prover<N: Network, E: Environment> {
     state: Arc<ProverState<N>>,
     /// The Aleo address of the prover.
     address: Option<Address<N>>,
+    /// The local address of this node.
+    local_ip: SocketAddr,
     /// The IP address of the connected pool.
     pool: Option<SocketAddr>,
     /// The thread pool for the prover.
@@ -83,7 +93,7 @@ pub struct Prover<N: Network, E: Environment> {
     /// The ledger state of the node.
     ledger_reader: LedgerReader<N>,
     /// The ledger router of the node.
-    _ledger_router: LedgerRouter<N>,
+    ledger_router: LedgerRouter<N>,
     current_block: Arc<RwLock<u32>>,
 }
 
@@ -92,7 +102,7 @@ impl<N: Network, E: Environment> Prover<N, E> {
     pub async fn open<S: Storage, P: AsRef<Path> + Copy>(
         path: P,
         address: Option<Address<N>>,
-        _local_ip: SocketAddr,
+        local_ip: SocketAddr,
         pool_ip: Option<SocketAddr>,
         peers_router: PeersRouter<N, E>,
         ledger_reader: LedgerReader<N>,
@@ -110,13 +120,14 @@ impl<N: Network, E: Environment> Prover<N, E> {
         let prover = Arc::new(Self {
             state: Arc::new(ProverState::open_writer::<S, P>(path)?),
             address,
+            local_ip,
             pool: pool_ip,
             thread_pool: Arc::new(thread_pool),
             prover_router,
             memory_pool: Arc::new(RwLock::new(MemoryPool::new())),
             peers_router,
             ledger_reader,
-            _ledger_router: ledger_router,
+            ledger_router,
             current_block: Arc::new(RwLock::new(0)),
         });
 
@@ -137,6 +148,9 @@ impl<N: Network, E: Environment> Prover<N, E> {
             let _ = handler.await;
         }
 
+        // Initialize the miner, if the node type is a miner.
+        prover.initialize_miner().await;
+
         // terminator init
         task::spawn(async move {
             let mut counter = false;
@@ -187,6 +201,18 @@ impl<N: Network, E: Environment> Prover<N, E> {
                 Some(block) => self.memory_pool.write().await.remove_transactions(block.transactions()),
                 None => *self.memory_pool.write().await = MemoryPool::new(),
             },
+            ProverRequest::MemoryPoolRequest(peer_ip) => {
+                // Send the transaction IDs of the memory pool to the peer.
+                self.send_memory_pool(peer_ip).await;
+            }
+            ProverRequest::MemoryPoolResponse(peer_ip, transaction_ids) => {
+                // Fetch the transactions that are missing from the memory pool.
+                self.request_missing_transactions(peer_ip, transaction_ids).await;
+            }
+            ProverRequest::TransactionsRequest(peer_ip, transaction_ids) => {
+                // Send the requested transactions in the memory pool to the peer.
+                self.send_transactions(peer_ip, transaction_ids).await;
+            }
             ProverRequest::UnconfirmedTransaction(peer_ip, transaction) => {
                 // Ensure the node is not peering.
                 if !E::status().is_peering() {
@@ -204,6 +230,82 @@ impl<N: Network, E: Environment> Prover<N, E> {
         }
     }
 
+    ///
+    /// Initializes the solo-mining loop, if the node type is a miner.
+    ///
+    async fn initialize_miner(self: &Arc<Self>) {
+        if E::NODE_TYPE == NodeType::Miner {
+            if let Some(recipient) = self.address {
+                let prover = self.clone();
+                let (router, handler) = oneshot::channel();
+                E::tasks().append(task::spawn(async move {
+                    // Notify the outer function that the task is ready.
+                    let _ = router.send(());
+                    loop {
+                        // If the terminator is `false` and the node is neither peering, syncing, nor mining, mine the next block.
+                        if !E::terminator().load(Ordering::SeqCst)
+                            && !E::status().is_peering()
+                            && !E::status().is_syncing()
+                            && !E::status().is_mining()
+                        {
+                            // Set the status to `Mining`.
+                            E::status().update(State::Mining);
+
+                            // Prepare the unconfirmed transactions for the block template.
+                            let transactions = prover.memory_pool.read().await.transactions();
+                            let ledger_reader = prover.ledger_reader.clone();
+                            let thread_pool = prover.thread_pool.clone();
+
+                            // Mine the next block.
+                            let result = task::spawn_blocking(move || {
+                                thread_pool.install(move || {
+                                    ledger_reader.mine_next_block(
+                                        recipient,
+                                        E::COINBASE_IS_PUBLIC,
+                                        &transactions,
+                                        E::terminator(),
+                                        &mut thread_rng(),
+                                    )
+                                })
+                            })
+                            .await;
+
+                            // Set the status to `Ready`, unless the ledger has changed it in the meantime.
+                            if E::status().is_mining() {
+                                E::status().update(State::Ready);
+                            }
+
+                            match result {
+                                Ok(Ok((block, coinbase_record))) => {
+                                    info!("Miner has found unconfirmed block {} ({})", block.height(), block.hash());
+
+                                    // Store the coinbase record.
+                                    if let Err(error) = prover.state.add_coinbase_record(block.height(), coinbase_record) {
+                                        warn!("[Miner] Failed to store coinbase record - {}", error);
+                                    }
+
+                                    // Route the unconfirmed block to the ledger.
+                                    let request = LedgerRequest::UnconfirmedBlock(prover.local_ip, block, prover.prover_router.clone());
+                                    if let Err(error) = prover.ledger_router.send(request).await {
+                                        warn!("[UnconfirmedBlock] {}", error);
+                                    }
+                                }
+                                Ok(Err(error)) => trace!("{}", error),
+                                Err(error) => trace!("{}", anyhow!("Failed to mine the next block {}", error)),
+                            }
+                        }
+                        // Sleep for 1 second.
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }));
+                // Wait until the miner task is ready.
+                let _ = handler.await;
+            } else {
+                error!("Missing miner address. Please specify an Aleo address in order to mine");
+            }
+        }
+    }
+
     ///
     /// Sends a `PoolRegister` message to the pool IP address.
     ///
@@ -322,6 +424,85 @@ impl<N: Network, E: Environment> Prover<N, E> {
         }
     }
 
+    ///
+    /// Sends the transaction IDs in the memory pool to the given peer, up to `E::MAXIMUM_INVENTORY_ITEMS`.
+    ///
+    async fn send_memory_pool(&self, peer_ip: SocketAddr) {
+        let transaction_ids = self
+            .memory_pool
+            .read()
+            .await
+            .transactions()
+            .iter()
+            .take(E::MAXIMUM_INVENTORY_ITEMS)
+            .map(|transaction| transaction.transaction_id())
+            .collect::<Vec<_>>();
+
+        debug!("Sending 'MemoryPoolResponse {}' to {}", transaction_ids.len(), peer_ip);
+        let request = PeersRequest::MessageSend(peer_ip, Message::MemoryPoolResponse(transaction_ids));
+        if let Err(error) = self.peers_router.send(request).await {
+            warn!("[MemoryPoolResponse] {}", error);
+        }
+    }
+
+    ///
+    /// Requests the given transactions from the memory pool of the given peer,
+    /// if they are neither in the memory pool of this node nor in the ledger.
+    ///
+    async fn request_missing_transactions(&self, peer_ip: SocketAddr, transaction_ids: Vec<N::TransactionID>) {
+        let known_transaction_ids = self
+            .memory_pool
+            .read()
+            .await
+            .transactions()
+            .iter()
+            .map(|transaction| transaction.transaction_id())
+            .collect::<HashSet<_>>();
+
+        let missing_transaction_ids = transaction_ids
+            .into_iter()
+            .filter(|transaction_id| !known_transaction_ids.contains(transaction_id))
+            .filter(|transaction_id| matches!(self.ledger_reader.contains_transaction(transaction_id), Ok(false)))
+            .collect::<Vec<_>>();
+
+        // The missing transactions are fetched through an inventory request, and are added to the
+        // memory pool as unconfirmed transactions once they are received.
+        if !missing_transaction_ids.is_empty() {
+            debug!(
+                "Requesting {} memory pool transactions from {}",
+                missing_transaction_ids.len(),
+                peer_ip
+            );
+            let request = PeersRequest::InventoryAnnouncement(peer_ip, vec![], missing_transaction_ids);
+            if let Err(error) = self.peers_router.send(request).await {
+                warn!("[MemoryPoolResponse] {}", error);
+            }
+        }
+    }
+
+    ///
+    /// Sends the given transactions in the memory pool to the given peer.
+    /// Transactions that are no longer in the memory pool are skipped.
+    ///
+    async fn send_transactions(&self, peer_ip: SocketAddr, transaction_ids: Vec<N::TransactionID>) {
+        let transaction_ids = transaction_ids.into_iter().collect::<HashSet<_>>();
+        let transactions = self
+            .memory_pool
+            .read()
+            .await
+            .transactions()
+            .into_iter()
+            .filter(|transaction| transaction_ids.contains(&transaction.transaction_id()))
+            .collect::<Vec<_>>();
+
+        for transaction in transactions {
+            let request = PeersRequest::MessageSend(peer_ip, Message::UnconfirmedTransaction(transaction));
+            if let Err(error) = self.peers_router.send(request).await {
+                warn!("[UnconfirmedTransaction] {}", error);
+            }
+        }
+    }
+
     ///
     /// Adds the given unconfirmed transaction to the memory pool.
     ///
@@ -345,3 +526,51 @@ impl<N: Network, E: Environment> Prover<N, E> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{environment::Client, network::ledger::Ledger};
+    use snarkos_storage::storage::rocksdb::RocksDB;
+    use snarkvm::dpc::testnet2::Testnet2;
+
+    type TestProver = Prover<Testnet2, Client<Testnet2>>;
+    type TestLedger = Ledger<Testnet2, Client<Testnet2>>;
+
+    #[tokio::test]
+    async fn test_send_transactions_from_memory_pool() {
+        let (ledger_path, prover_path) = (
+            tempfile::tempdir().expect("Failed to open temporary directory").into_path(),
+            tempfile::tempdir().expect("Failed to open temporary directory").into_path(),
+        );
+        let (ledger_peers_router, _ledger_peers_handler) = mpsc::channel(1024);
+        let ledger = TestLedger::open::<RocksDB, _>(&ledger_path, ledger_peers_router)
+            .await
+            .expect("Failed to initialize ledger");
+
+        let (peers_router, mut peers_handler) = mpsc::channel(1024);
+        let local_ip = "127.0.0.1:4130".parse().unwrap();
+        let prover = TestProver::open::<RocksDB, _>(&prover_path, None, local_ip, None, peers_router, ledger.reader(), ledger.router())
+            .await
+            .expect("Failed to initialize prover");
+
+        let peer_ip = "127.0.0.1:4131".parse().unwrap();
+        let transaction = Testnet2::genesis_block().transactions().first().unwrap().clone();
+
+        // A transaction that is not in the memory pool is skipped.
+        prover.send_transactions(peer_ip, vec![transaction.transaction_id()]).await;
+        assert!(peers_handler.try_recv().is_err());
+
+        // A transaction in the memory pool is sent to the peer.
+        prover.memory_pool().write().await.add_transaction(&transaction).unwrap();
+        prover.send_transactions(peer_ip, vec![transaction.transaction_id()]).await;
+        match peers_handler.try_recv() {
+            Ok(PeersRequest::MessageSend(ip, Message::UnconfirmedTransaction(sent))) => {
+                assert_eq!(peer_ip, ip);
+                assert_eq!(transaction.transaction_id(), sent.transaction_id());
+            }
+            _ => panic!("Failed to send the transaction from the memory pool"),
+        }
+        assert!(peers_handler.try_recv().is_err());
+    }
+}
@@ -17,7 +17,7 @@
 use crate::{
     display::notification_message,
     environment::Environment,
-    helpers::{NodeType, State},
+    helpers::{NodeKey, NodeType, RewardsReport, State},
     ledger::{Ledger, LedgerRequest, LedgerRouter},
     operator::{Operator, OperatorRouter},
     peers::{Peers, PeersRequest, PeersRouter},
@@ -29,7 +29,13 @@ use snarkos_storage::{storage::rocksdb::RocksDB, LedgerState};
 use snarkvm::prelude::*;
 
 use anyhow::Result;
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::{
     net::TcpListener,
     sync::{oneshot, RwLock},
@@ -62,7 +68,15 @@ impl<N: Network, E: Environment> Server<N, E> {
     #[inline]
     pub async fn initialize(node: &Node, address: Option<Address<N>>, pool_ip: Option<SocketAddr>) -> Result<Self> {
         // Initialize a new TCP listener at the given IP.
-        let (local_ip, listener) = match TcpListener::bind(node.node).await {
+        let listener = match Self::bind_listener(node.node) {
+            // If IPv6 is unavailable on this host, fall back to listening on IPv4 only.
+            Err(error) if node.node.is_ipv6() && node.node.ip().is_unspecified() => {
+                warn!("Failed to bind an IPv6 listener ({}), falling back to IPv4", error);
+                Self::bind_listener(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), node.node.port()))
+            }
+            result => result,
+        };
+        let (local_ip, listener) = match listener {
             Ok(listener) => (listener.local_addr().expect("Failed to fetch the local IP"), listener),
             Err(error) => panic!("Failed to bind listener: {:?}. Check if another Aleo node is running", error),
         };
@@ -74,8 +88,19 @@ impl<N: Network, E: Environment> Server<N, E> {
         // Initialize the prover storage path.
         let prover_storage_path = node.prover_storage_path(local_ip);
 
+        // Load the node key, or generate a new node key on the first run.
+        let node_key = NodeKey::load_or_generate(node.node_key_path(local_ip))?;
+        info!("Node ID: {}", node_key.public_key());
+
         // Initialize a new instance for managing peers.
-        let peers = Peers::new(local_ip, None).await;
+        let peers = Peers::new(
+            local_ip,
+            None,
+            Some(node_key),
+            Some(node.peer_book_path(local_ip)),
+            Some(node.ban_list_path(local_ip)),
+        )
+        .await?;
         // Initialize a new instance for managing the ledger.
         let ledger = Ledger::<N, E>::open::<RocksDB, _>(&ledger_storage_path, peers.router()).await?;
         // Initialize a new instance for managing the prover.
@@ -165,6 +190,7 @@ impl<N: Network, E: Environment> Server<N, E> {
             ledger.router(),
             operator.clone(),
             operator.router(),
+            prover.clone(),
             prover.router(),
             prover.memory_pool(),
         )
@@ -225,6 +251,10 @@ impl<N: Network, E: Environment> Server<N, E> {
         // Update the node status.
         E::status().update(State::ShuttingDown);
 
+        // Save the peer book.
+        trace!("Proceeding to save the peer book...");
+        self.peers.shut_down().await;
+
         // Shut down the ledger.
         trace!("Proceeding to shut down the ledger...");
         let (canon_lock, block_requests_lock, storage_map_lock) = self.ledger.shut_down().await;
@@ -241,6 +271,25 @@ impl<N: Network, E: Environment> Server<N, E> {
         trace!("Node has shut down.");
     }
 
+    ///
+    /// Returns a new TCP listener at the given IP.
+    ///
+    /// A listener at the unspecified IPv6 address (`[::]`) is dual-stack, and also accepts connections from IPv4 peers,
+    /// which are reported with their IPv4-mapped IPv6 address.
+    ///
+    fn bind_listener(ip: SocketAddr) -> io::Result<TcpListener> {
+        let socket = Socket::new(Domain::for_address(ip), Type::STREAM, Some(Protocol::TCP))?;
+        if ip.is_ipv6() && ip.ip().is_unspecified() {
+            socket.set_only_v6(false)?;
+        }
+        #[cfg(unix)]
+        socket.set_reuse_address(true)?;
+        socket.bind(&ip.into())?;
+        socket.listen(1024)?;
+        socket.set_nonblocking(true)?;
+        TcpListener::from_std(socket.into())
+    }
+
     ///
     /// Initialize the connection listener for new peers.
     ///
@@ -262,7 +311,7 @@ impl<N: Network, E: Environment> Server<N, E> {
             info!("Listening for peers at {}", local_ip);
             loop {
                 // Don't accept connections if the node is breaching the configured peer limit.
-                if E::NODE_TYPE == NodeType::Operator || peers.number_of_connected_peers().await < E::MAXIMUM_NUMBER_OF_PEERS {
+                if E::NODE_TYPE == NodeType::Operator || peers.number_of_connected_peers().await < E::maximum_number_of_peers() {
                     // Asynchronously wait for an inbound TcpStream.
                     match listener.accept().await {
                         // Process the inbound connection request.
@@ -324,8 +373,8 @@ impl<N: Network, E: Environment> Server<N, E> {
                 if let Err(error) = peers_router.send(request).await {
                     error!("Failed to send heartbeat to peers: {}", error)
                 }
-                // Sleep for `E::HEARTBEAT_IN_SECS` seconds.
-                tokio::time::sleep(Duration::from_secs(E::HEARTBEAT_IN_SECS)).await;
+                // Sleep for `E::heartbeat_in_secs()` seconds.
+                tokio::time::sleep(Duration::from_secs(E::heartbeat_in_secs())).await;
             }
         }));
         // Wait until the heartbeat task is ready.
@@ -336,6 +385,7 @@ impl<N: Network, E: Environment> Server<N, E> {
     /// Initialize a new instance of the RPC server.
     ///
     #[inline]
+    #[allow(clippy::too_many_arguments)]
     async fn initialize_rpc(
         node: &Node,
         address: Option<Address<N>>,
@@ -344,6 +394,7 @@ impl<N: Network, E: Environment> Server<N, E> {
         ledger_router: LedgerRouter<N>,
         operator: Arc<Operator<N, E>>,
         operator_router: OperatorRouter<N>,
+        prover: Arc<Prover<N, E>>,
         prover_router: ProverRouter<N>,
         memory_pool: Arc<RwLock<MemoryPool<N>>>,
     ) {
@@ -360,6 +411,7 @@ impl<N: Network, E: Environment> Server<N, E> {
                     ledger_router,
                     operator,
                     operator_router,
+                    prover,
                     prover_router,
                     memory_pool,
                 )
@@ -383,34 +435,8 @@ impl<N: Network, E: Environment> Server<N, E> {
 
                 if E::NODE_TYPE == NodeType::Miner {
                     if let Some(miner_address) = address {
-                        // Retrieve the latest block height.
-                        let latest_block_height = ledger.latest_block_height();
-
-                        // Prepare a list of confirmed and pending coinbase records.
-                        let mut confirmed = vec![];
-                        let mut pending = vec![];
-
-                        // Iterate through the coinbase records from storage.
-                        for (block_height, record) in prover.to_coinbase_records() {
-                            // Filter the coinbase records by determining if they exist on the canonical chain.
-                            if let Ok(true) = ledger.contains_commitment(&record.commitment()) {
-                                // Ensure the record owner matches.
-                                if record.owner() == miner_address {
-                                    // Add the block to the appropriate list.
-                                    match block_height + 2048 < latest_block_height {
-                                        true => confirmed.push((block_height, record)),
-                                        false => pending.push((block_height, record)),
-                                    }
-                                }
-                            }
-                        }
-
-                        info!(
-                            "Mining Report (confirmed_blocks = {}, pending_blocks = {}, miner_address = {})",
-                            confirmed.len(),
-                            pending.len(),
-                            miner_address
-                        );
+                        let report = RewardsReport::new(&ledger, prover.to_coinbase_records(), miner_address);
+                        info!("{}", report);
                     }
                 }
 
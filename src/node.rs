@@ -15,19 +15,29 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    environment::{Client, ClientTrial, Environment, Miner, MinerTrial, Operator, OperatorTrial, Prover, ProverTrial, SyncNode},
-    helpers::{NodeType, Updater},
+    config::Config,
+    environment::{Client, Environment, Miner, Operator, Parameters, Prover, SyncNode},
+    helpers::{parse_subnet, BanList, Keystore, NodeType, RewardsReport, Updater},
     network::Server,
     Display,
 };
-use snarkos_storage::storage::rocksdb::RocksDB;
+use snarkos_storage::storage::{rocksdb::RocksDB, Storage};
 use snarkvm::dpc::{prelude::*, testnet2::Testnet2};
 
 use anyhow::{anyhow, Result};
 use colored::*;
-use crossterm::tty::IsTty;
-use std::{io, net::SocketAddr, path::PathBuf, str::FromStr};
-use structopt::StructOpt;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    terminal::{disable_raw_mode, enable_raw_mode},
+    tty::IsTty,
+};
+use std::{
+    io::{self, BufRead, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use structopt::{clap::ArgMatches, StructOpt};
 use tokio::{signal, sync::mpsc, task};
 use tracing_subscriber::EnvFilter;
 
@@ -37,13 +47,13 @@ pub struct Node {
     /// Specify the IP address and port of a peer to connect to.
     #[structopt(long = "connect")]
     pub connect: Option<String>,
-    /// Specify this as a mining node, with the given miner address.
+    /// Specify this as a mining node, with the given miner address or keystore file.
     #[structopt(long = "miner")]
     pub miner: Option<String>,
-    /// Specify this as an operating node, with the given operator address.
+    /// Specify this as an operating node, with the given operator address or keystore file.
     #[structopt(long = "operator")]
     pub operator: Option<String>,
-    /// Specify this as a prover node, with the given prover address.
+    /// Specify this as a prover node, with the given prover address or keystore file.
     #[structopt(long = "prover")]
     pub prover: Option<String>,
     /// Specify the pool that a prover node is contributing to.
@@ -53,7 +63,7 @@ pub struct Node {
     #[structopt(default_value = "2", long = "network")]
     pub network: u16,
     /// Specify the IP address and port for the node server.
-    #[structopt(parse(try_from_str), default_value = "0.0.0.0:4132", long = "node")]
+    #[structopt(parse(try_from_str), default_value = "[::]:4132", long = "node")]
     pub node: SocketAddr,
     /// Specify the IP address and port for the RPC server.
     #[structopt(parse(try_from_str), default_value = "0.0.0.0:3032", long = "rpc")]
@@ -76,16 +86,164 @@ pub struct Node {
     /// If the flag is set, the node will not initialize the RPC server.
     #[structopt(long)]
     pub norpc: bool,
+    /// Specify the IP addresses and ports of the sync nodes to bootstrap with, separated by commas.
+    #[structopt(long = "sync-nodes", use_delimiter = true)]
+    pub sync_nodes: Option<Vec<SocketAddr>>,
+    /// Specify the IP addresses and ports of the beacon nodes to bootstrap with, separated by commas.
+    #[structopt(long = "beacon-nodes", use_delimiter = true)]
+    pub beacon_nodes: Option<Vec<SocketAddr>>,
+    /// Specify the IP addresses and ports of the only peers to connect with, separated by commas, to run a private network.
+    #[structopt(long = "trusted-peers", use_delimiter = true)]
+    pub trusted_peers: Option<Vec<SocketAddr>>,
+    /// If the flag is set, the node will only connect with peers that negotiate an encrypted transport.
+    #[structopt(long = "require-encryption")]
+    pub require_encryption: bool,
+    /// Specify the minimum number of peers to maintain connections with.
+    #[structopt(long = "min-peers")]
+    pub min_peers: Option<usize>,
+    /// Specify the maximum number of peers to maintain connections with.
+    #[structopt(long = "max-peers")]
+    pub max_peers: Option<usize>,
     #[structopt(hidden = true, long)]
     pub trial: bool,
     #[structopt(hidden = true, long)]
     pub sync: bool,
+    /// Specify the path to a TOML config file for the node; command-line flags take precedence.
+    #[structopt(parse(from_os_str), long = "config")]
+    pub config: Option<PathBuf>,
     /// Specify an optional subcommand.
     #[structopt(subcommand)]
     commands: Option<Command>,
 }
 
 impl Node {
+    ///
+    /// Parses the command-line arguments, layered on top of the `SNARKOS_*` environment variables
+    /// and the config file, if one is given. The runtime parameters of the node are initialized here.
+    ///
+    pub fn load() -> Result<Self> {
+        let (node, environment) = Self::layer(&Self::clap().get_matches(), |key| std::env::var(key))?;
+
+        node.validate()?;
+
+        // Layer the runtime parameters, starting from the incentivized testnet parameters, if enabled.
+        let mut parameters = match node.trial && node.node_type() != NodeType::Sync {
+            true => Parameters::trial(node.node_type()),
+            false => Parameters::default(),
+        };
+        parameters.merge(environment);
+        parameters.merge(node.parameters());
+        match node.node_type() {
+            NodeType::Client => Parameters::initialize::<Client<Testnet2>>(parameters)?,
+            NodeType::Miner => Parameters::initialize::<Miner<Testnet2>>(parameters)?,
+            NodeType::Operator => Parameters::initialize::<Operator<Testnet2>>(parameters)?,
+            NodeType::Prover => Parameters::initialize::<Prover<Testnet2>>(parameters)?,
+            NodeType::Sync => Parameters::initialize::<SyncNode<Testnet2>>(parameters)?,
+            NodeType::Beacon => return Err(anyhow!("Unsupported node configuration")),
+        }
+
+        Ok(node)
+    }
+
+    ///
+    /// Returns the node options of the given command-line arguments, layered on top of the `SNARKOS_*`
+    /// environment variables and the config file, along with the `[environment]` table of the config.
+    /// The environment variables are read with the given lookup.
+    ///
+    fn layer<F: Fn(&str) -> Result<String, std::env::VarError>>(matches: &ArgMatches, var: F) -> Result<(Self, Parameters)> {
+        let mut node = Self::from_clap(matches);
+
+        // Load the config file, if one is given, followed by the environment variables.
+        let mut config = match &node.config {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+        config.load_env_vars(var)?;
+
+        // Apply the config to any option that was not explicitly set on the command line.
+        // The arguments are named after their field in kebab case (e.g. `rpc-username`).
+        let is_unset = |field: &str| matches.occurrences_of(field.replace('_', "-")) == 0;
+        macro_rules! layer {
+            ($($field:ident),*) => {
+                $(if is_unset(stringify!($field)) {
+                    if let Some(value) = config.$field.take() {
+                        node.$field = value;
+                    }
+                })*
+            };
+        }
+        macro_rules! layer_optional {
+            ($($field:ident),*) => {
+                $(if is_unset(stringify!($field)) && config.$field.is_some() {
+                    node.$field = config.$field.take();
+                })*
+            };
+        }
+        layer!(
+            network,
+            node,
+            rpc,
+            rpc_username,
+            rpc_password,
+            verbosity,
+            display,
+            norpc,
+            trial,
+            sync
+        );
+        layer_optional!(connect, miner, operator, prover, pool, dev);
+
+        Ok((node, config.environment))
+    }
+
+    /// Returns the node type, as specified by the options of the node.
+    pub fn node_type(&self) -> NodeType {
+        match (&self.miner, &self.operator, &self.prover, self.sync) {
+            (_, _, _, true) => NodeType::Sync,
+            (Some(_), _, _, _) => NodeType::Miner,
+            (_, Some(_), _, _) => NodeType::Operator,
+            (_, _, Some(_), _) => NodeType::Prover,
+            _ => NodeType::Client,
+        }
+    }
+
+    /// Returns the runtime parameters that are specified on the command line.
+    fn parameters(&self) -> Parameters {
+        Parameters {
+            beacon_nodes: self.beacon_nodes.clone(),
+            sync_nodes: self.sync_nodes.clone(),
+            trusted_peers: self.trusted_peers.clone(),
+            require_encryption: self.require_encryption.then(|| true),
+            minimum_number_of_peers: self.min_peers,
+            maximum_number_of_peers: self.max_peers,
+            ..Default::default()
+        }
+    }
+
+    /// Returns an error if the options of the node are inconsistent with each other.
+    fn validate(&self) -> Result<()> {
+        let roles = [&self.miner, &self.operator, &self.prover]
+            .iter()
+            .filter(|role| role.is_some())
+            .count();
+        if roles > 1 {
+            return Err(anyhow!("Only one of 'miner', 'operator', or 'prover' may be specified"));
+        }
+        if roles > 0 && self.sync {
+            return Err(anyhow!("A sync node may not be specified with 'miner', 'operator', or 'prover'"));
+        }
+        if self.pool.is_some() && self.prover.is_none() {
+            return Err(anyhow!("'pool' may only be specified for a prover node"));
+        }
+        if self.verbosity > 3 {
+            return Err(anyhow!("'verbosity' must be one of 0, 1, 2, or 3 (found {})", self.verbosity));
+        }
+        if self.network != 2 {
+            return Err(anyhow!("'network' {} is not supported", self.network));
+        }
+        Ok(())
+    }
+
     /// Starts the node.
     pub async fn start(self) -> Result<()> {
         // Parse optional subcommands first.
@@ -94,16 +252,12 @@ impl Node {
                 println!("{}", command.parse()?);
                 Ok(())
             }
-            None => match (self.network, &self.miner, &self.operator, &self.prover, self.trial, self.sync) {
-                (2, None, None, None, false, false) => self.start_server::<Testnet2, Client<Testnet2>>(&None).await,
-                (2, Some(_), None, None, false, false) => self.start_server::<Testnet2, Miner<Testnet2>>(&self.miner).await,
-                (2, None, Some(_), None, false, false) => self.start_server::<Testnet2, Operator<Testnet2>>(&self.operator).await,
-                (2, None, None, Some(_), false, false) => self.start_server::<Testnet2, Prover<Testnet2>>(&self.prover).await,
-                (2, None, None, None, true, false) => self.start_server::<Testnet2, ClientTrial<Testnet2>>(&None).await,
-                (2, Some(_), None, None, true, false) => self.start_server::<Testnet2, MinerTrial<Testnet2>>(&self.miner).await,
-                (2, None, Some(_), None, true, false) => self.start_server::<Testnet2, OperatorTrial<Testnet2>>(&self.operator).await,
-                (2, None, None, Some(_), true, false) => self.start_server::<Testnet2, ProverTrial<Testnet2>>(&self.prover).await,
-                (2, None, None, None, _, true) => self.start_server::<Testnet2, SyncNode<Testnet2>>(&None).await,
+            None => match (self.network, self.node_type()) {
+                (2, NodeType::Client) => self.start_server::<Testnet2, Client<Testnet2>>(&None).await,
+                (2, NodeType::Miner) => self.start_server::<Testnet2, Miner<Testnet2>>(&self.miner).await,
+                (2, NodeType::Operator) => self.start_server::<Testnet2, Operator<Testnet2>>(&self.operator).await,
+                (2, NodeType::Prover) => self.start_server::<Testnet2, Prover<Testnet2>>(&self.prover).await,
+                (2, NodeType::Sync) => self.start_server::<Testnet2, SyncNode<Testnet2>>(&None).await,
                 _ => panic!("Unsupported node configuration"),
             },
         }
@@ -148,12 +302,48 @@ impl Node {
         }
     }
 
+    /// Returns the path of the peer book.
+    pub(crate) fn peer_book_path(&self, local_ip: SocketAddr) -> PathBuf {
+        self.storage_artifact_path(local_ip, "peers")
+    }
+
+    /// Returns the path of the ban list.
+    pub(crate) fn ban_list_path(&self, local_ip: SocketAddr) -> PathBuf {
+        self.storage_artifact_path(local_ip, "bans")
+    }
+
+    /// Returns the path of the node key.
+    pub(crate) fn node_key_path(&self, local_ip: SocketAddr) -> PathBuf {
+        self.storage_artifact_path(local_ip, "key")
+    }
+
+    /// Returns the path of the JSON file with the given name, which is stored alongside the ledger.
+    fn storage_artifact_path(&self, _local_ip: SocketAddr, name: &str) -> PathBuf {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "test")] {
+                // Tests may use any available ports, so the file is named after the port of the node.
+                PathBuf::from(format!("/tmp/snarkos-test-{}-{}.json", name, _local_ip.port()))
+            } else {
+                storage_artifact_path(self.network, self.dev, name)
+            }
+        }
+    }
+
     async fn start_server<N: Network, E: Environment>(&self, address: &Option<String>) -> Result<()> {
         println!("{}", crate::display::welcome_message());
 
         let address = match (E::NODE_TYPE, address) {
             (NodeType::Miner, Some(address)) | (NodeType::Operator, Some(address)) | (NodeType::Prover, Some(address)) => {
-                let address = Address::<N>::from_str(address)?;
+                let address = match Address::<N>::from_str(address) {
+                    Ok(address) => address,
+                    // If the given string is not an address, load the address from the keystore file at the given path.
+                    Err(_) if Path::new(address).is_file() => {
+                        let private_key =
+                            Keystore::load(address)?.decrypt::<N>(&read_keystore_password("Enter the keystore password: ")?)?;
+                        Address::from_private_key(&private_key)
+                    }
+                    Err(_) => return Err(anyhow!("'{}' is neither a valid address nor a keystore file", address)),
+                };
                 println!("Your Aleo address is {}.\n", address);
                 Some(address)
             }
@@ -226,6 +416,12 @@ pub enum Command {
     Experimental(Experimental),
     #[structopt(name = "miner", about = "Miner commands and settings")]
     Miner(MinerSubcommand),
+    #[structopt(name = "ledger", about = "Ledger commands and settings")]
+    Ledger(LedgerSubcommand),
+    #[structopt(name = "account", about = "Account commands and keystores")]
+    Account(AccountSubcommand),
+    #[structopt(name = "ban", about = "Ban list commands")]
+    Ban(BanSubcommand),
 }
 
 impl Command {
@@ -235,6 +431,9 @@ impl Command {
             Self::Update(command) => command.parse(),
             Self::Experimental(command) => command.parse(),
             Self::Miner(command) => command.parse(),
+            Self::Ledger(command) => command.parse(),
+            Self::Account(command) => command.parse(),
+            Self::Ban(command) => command.parse(),
         }
     }
 }
@@ -416,6 +615,9 @@ pub enum MinerCommands {
 pub struct MinerStats {
     #[structopt()]
     address: String,
+    /// If the flag is set, the report is printed as JSON, with the details of each coinbase record.
+    #[structopt(long)]
+    json: bool,
 }
 
 impl MinerStats {
@@ -430,43 +632,580 @@ impl MinerStats {
 
         // Initialize the ledger storage.
         let ledger_storage_path = node.ledger_storage_path(ip);
-        let ledger = snarkos_storage::LedgerState::<Testnet2>::open_reader::<RocksDB, _>(ledger_storage_path).unwrap();
+        let ledger = snarkos_storage::LedgerState::<Testnet2>::open_reader::<RocksDB, _>(ledger_storage_path)?;
 
         // Initialize the prover storage.
         let prover_storage_path = node.prover_storage_path(ip);
-        let prover = snarkos_storage::ProverState::<Testnet2>::open_writer::<RocksDB, _>(prover_storage_path).unwrap();
-
-        // Retrieve the latest block height.
-        let latest_block_height = ledger.latest_block_height();
-
-        // Prepare a list of confirmed and pending coinbase records.
-        let mut confirmed = vec![];
-        let mut pending = vec![];
-
-        // Iterate through the coinbase records from storage.
-        for (block_height, record) in prover.to_coinbase_records() {
-            // Filter the coinbase records by determining if they exist on the canonical chain.
-            if let Ok(true) = ledger.contains_commitment(&record.commitment()) {
-                // Ensure the record owner matches.
-                if record.owner() == miner {
-                    // Add the block to the appropriate list.
-                    match block_height + 2048 < latest_block_height {
-                        true => confirmed.push((block_height, record)),
-                        false => pending.push((block_height, record)),
-                    }
-                }
+        let prover = snarkos_storage::ProverState::<Testnet2>::open_writer::<RocksDB, _>(prover_storage_path)?;
+
+        // Prepare the rewards report from the coinbase records in storage.
+        let report = RewardsReport::new(&ledger, prover.to_coinbase_records(), miner);
+
+        match self.json {
+            true => Ok(serde_json::to_string_pretty(&report)?),
+            false => Ok(report.to_string()),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct LedgerSubcommand {
+    #[structopt(subcommand)]
+    commands: LedgerCommands,
+}
+
+impl LedgerSubcommand {
+    pub fn parse(self) -> Result<String> {
+        match self.commands {
+            LedgerCommands::Export(command) => command.parse(),
+            LedgerCommands::Import(command) => command.parse(),
+            LedgerCommands::Verify(command) => command.parse(),
+            LedgerCommands::Revert(command) => command.parse(),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub enum LedgerCommands {
+    #[structopt(name = "export", about = "Exports the blocks of the ledger to a file.")]
+    Export(ExportLedger),
+    #[structopt(name = "import", about = "Imports the blocks from a file into the ledger.")]
+    Import(ImportLedger),
+    #[structopt(name = "verify", about = "Verifies the integrity of the ledger, and reports any inconsistencies.")]
+    Verify(VerifyLedger),
+    #[structopt(
+        name = "revert",
+        about = "Repairs the ledger, and optionally reverts it to the given block height."
+    )]
+    Revert(RevertLedger),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExportLedger {
+    /// Specify the path of the file to export the blocks to.
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+    /// Specify the block height to start exporting from.
+    #[structopt(default_value = "0", long = "start")]
+    pub start: u32,
+    /// Specify the network of the ledger to export.
+    #[structopt(default_value = "2", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node to export.
+    #[structopt(long)]
+    pub dev: Option<u16>,
+}
+
+impl ExportLedger {
+    pub fn parse(self) -> Result<String> {
+        if self.network != 2 {
+            return Err(anyhow!("Network {} is not supported", self.network));
+        }
+
+        // Open the ledger in read-only mode, so that a running node may continue to use it.
+        let path = aleo_std::aleo_ledger_dir(self.network, self.dev);
+        let ledger = snarkos_storage::LedgerState::<Testnet2>::open_reader::<RocksDB, _>(&path)?;
+
+        // Export the blocks to the given file.
+        let file = std::fs::File::create(&self.file)?;
+        let number_of_blocks = ledger.export_blocks(io::BufWriter::new(file), self.start)?;
+
+        Ok(format!(
+            "Successfully exported {} blocks from the ledger to {}",
+            number_of_blocks,
+            self.file.display()
+        ))
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ImportLedger {
+    /// Specify the path of the file to import the blocks from.
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+    /// Specify the network of the ledger to import into.
+    #[structopt(default_value = "2", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node to import into.
+    #[structopt(long)]
+    pub dev: Option<u16>,
+}
+
+impl ImportLedger {
+    pub fn parse(self) -> Result<String> {
+        if self.network != 2 {
+            return Err(anyhow!("Network {} is not supported", self.network));
+        }
+
+        // Open the ledger, which must not be in use by a running node.
+        let path = aleo_std::aleo_ledger_dir(self.network, self.dev);
+        let ledger = snarkos_storage::LedgerState::<Testnet2>::open_writer::<RocksDB, _>(&path)?;
+
+        // Import the blocks from the given file. Blocks already in the ledger are skipped,
+        // so an interrupted import may be resumed by running the same command again.
+        let file = std::fs::File::open(&self.file)?;
+        match ledger.import_blocks(io::BufReader::new(file)) {
+            Ok(number_of_blocks) => Ok(format!(
+                "Successfully imported {} blocks into the ledger, which is now at block {}",
+                number_of_blocks,
+                ledger.latest_block_height()
+            )),
+            Err(error) => Err(anyhow!(
+                "Failed to import the blocks, the ledger is at block {}. Run the same command to resume the import.\n{}",
+                ledger.latest_block_height(),
+                error
+            )),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct VerifyLedger {
+    /// Enables full verification, which also checks the validity of every block.
+    #[structopt(long)]
+    pub full: bool,
+    /// Specify the network of the ledger to verify.
+    #[structopt(default_value = "2", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node to verify.
+    #[structopt(long)]
+    pub dev: Option<u16>,
+}
+
+impl VerifyLedger {
+    pub fn parse(self) -> Result<String> {
+        if self.network != 2 {
+            return Err(anyhow!("Network {} is not supported", self.network));
+        }
+
+        // Open the ledger in read-only mode, so that a running node may continue to use it.
+        let path = aleo_std::aleo_ledger_dir(self.network, self.dev);
+        let ledger = snarkos_storage::LedgerState::<Testnet2>::open_reader::<RocksDB, _>(&path)?;
+
+        // Verify the ledger, and report every inconsistency by block height.
+        let report = ledger.verify(self.full)?;
+        if report.is_empty() {
+            return Ok(format!(
+                "Successfully verified the ledger up to block {}, no inconsistencies were found",
+                ledger.latest_block_height()
+            ));
+        }
+
+        let number_of_issues: usize = report.values().map(Vec::len).sum();
+        let mut output = format!("Ledger verification found {} inconsistencies:", number_of_issues);
+        for (block_height, issues) in report {
+            for issue in issues {
+                output += &format!("\n  Block {}: {}", block_height, issue);
             }
         }
+        Err(anyhow!(output))
+    }
+}
 
-        return Ok(format!(
-            "Mining Report (confirmed_blocks = {}, pending_blocks = {}, miner_address = {})",
-            confirmed.len(),
-            pending.len(),
-            miner
-        ));
+#[derive(StructOpt, Debug)]
+pub struct RevertLedger {
+    /// Specify the block height to revert the ledger to.
+    #[structopt(long = "to")]
+    pub to: Option<u32>,
+    /// Specify the network of the ledger to revert.
+    #[structopt(default_value = "2", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node to revert.
+    #[structopt(long)]
+    pub dev: Option<u16>,
+}
+
+impl RevertLedger {
+    pub fn parse(self) -> Result<String> {
+        if self.network != 2 {
+            return Err(anyhow!("Network {} is not supported", self.network));
+        }
+
+        // Open the ledger, which must not be in use by a running node, and repair any inconsistent state.
+        let path = aleo_std::aleo_ledger_dir(self.network, self.dev);
+        let ledger = snarkos_storage::LedgerState::<Testnet2>::open_writer_with_repair::<RocksDB, _>(&path)?;
+
+        // Revert the ledger to the given block height, if one is specified.
+        match self.to {
+            Some(block_height) => {
+                let latest_block_height = ledger.latest_block_height();
+                let number_of_blocks = ledger.rewind_to_block_height(block_height)?;
+                Ok(format!(
+                    "Successfully reverted the ledger from block {} to block {} ({} blocks removed)",
+                    latest_block_height, block_height, number_of_blocks
+                ))
+            }
+            None => Ok(format!(
+                "Successfully opened the ledger at block {}, no revert was requested",
+                ledger.latest_block_height()
+            )),
+        }
     }
 }
 
+#[derive(StructOpt, Debug)]
+pub struct AccountSubcommand {
+    #[structopt(subcommand)]
+    commands: AccountCommands,
+}
+
+impl AccountSubcommand {
+    pub fn parse(self) -> Result<String> {
+        match self.commands {
+            AccountCommands::New(command) => command.parse(),
+            AccountCommands::Import(command) => command.parse(),
+            AccountCommands::Derive(command) => command.parse(),
+            AccountCommands::Sign(command) => command.parse(),
+            AccountCommands::Verify(command) => command.parse(),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub enum AccountCommands {
+    #[structopt(name = "new", about = "Generates a new Aleo account, and optionally saves it to a keystore.")]
+    New(AccountNew),
+    #[structopt(name = "import", about = "Saves an existing private key to a password-encrypted keystore.")]
+    Import(AccountImport),
+    #[structopt(name = "derive", about = "Derives the view key and address from a private key.")]
+    Derive(AccountDerive),
+    #[structopt(name = "sign", about = "Signs a message with a private key.")]
+    Sign(AccountSign),
+    #[structopt(name = "verify", about = "Verifies the signature of a message for an address.")]
+    Verify(AccountVerify),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct AccountNew {
+    /// Specify the path of a keystore file to save the new account to, instead of printing its private key.
+    #[structopt(parse(from_os_str), long = "keystore")]
+    pub keystore: Option<PathBuf>,
+}
+
+impl AccountNew {
+    pub fn parse(self) -> Result<String> {
+        match self.keystore {
+            Some(path) => {
+                let account = Account::<Testnet2>::new(&mut rand::thread_rng());
+                save_keystore(account.private_key(), &path)?;
+                Ok(format!(
+                    "Saved the account {} to the keystore {}",
+                    account.address(),
+                    path.display()
+                ))
+            }
+            None => NewAccount {}.parse(),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct AccountImport {
+    /// Specify the path of the keystore file to save the private key to.
+    #[structopt(parse(from_os_str))]
+    pub keystore: PathBuf,
+}
+
+impl AccountImport {
+    pub fn parse(self) -> Result<String> {
+        let private_key = PrivateKey::<Testnet2>::from_str(&read_secret("Enter the private key: ")?)?;
+        save_keystore(&private_key, &self.keystore)?;
+        Ok(format!(
+            "Saved the account {} to the keystore {}",
+            Address::from_private_key(&private_key),
+            self.keystore.display()
+        ))
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct AccountDerive {
+    /// Specify the path of the keystore file to load the private key from, instead of entering it.
+    #[structopt(parse(from_os_str), long = "keystore")]
+    pub keystore: Option<PathBuf>,
+}
+
+impl AccountDerive {
+    pub fn parse(self) -> Result<String> {
+        let account = Account::<Testnet2>::from(load_private_key(&self.keystore)?);
+
+        let mut output = "".to_string();
+        output += &format!("\n {:>12}  {}\n", "View Key".cyan().bold(), account.view_key());
+        output += &format!(" {:>12}  {}\n", "Address".cyan().bold(), account.address());
+        Ok(output)
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct AccountSign {
+    /// Specify the message to sign.
+    pub message: String,
+    /// Specify the path of the keystore file to load the private key from, instead of entering it.
+    #[structopt(parse(from_os_str), long = "keystore")]
+    pub keystore: Option<PathBuf>,
+}
+
+impl AccountSign {
+    pub fn parse(self) -> Result<String> {
+        let private_key = load_private_key(&self.keystore)?;
+        let signature = private_key.sign(&to_message_bits(&self.message), &mut rand::thread_rng())?;
+        Ok(signature.to_string())
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct AccountVerify {
+    /// Specify the address of the signer.
+    pub address: String,
+    /// Specify the message that was signed.
+    pub message: String,
+    /// Specify the signature of the message.
+    pub signature: String,
+}
+
+impl AccountVerify {
+    pub fn parse(self) -> Result<String> {
+        let address = Address::<Testnet2>::from_str(&self.address)?;
+        let signature = <Testnet2 as Network>::AccountSignature::from_str(&self.signature)
+            .map_err(|_| anyhow!("Invalid signature '{}'", self.signature))?;
+
+        match address.verify_signature(&to_message_bits(&self.message), &signature)? {
+            true => Ok(format!("The signature is valid for {}", address)),
+            false => Err(anyhow!("The signature is invalid for {}", address)),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct BanSubcommand {
+    #[structopt(subcommand)]
+    commands: BanCommands,
+}
+
+impl BanSubcommand {
+    pub fn parse(self) -> Result<String> {
+        match self.commands {
+            BanCommands::Add(command) => command.parse(),
+            BanCommands::Remove(command) => command.parse(),
+            BanCommands::List(command) => command.parse(),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub enum BanCommands {
+    #[structopt(name = "add", about = "Bans an IP address or CIDR subnet, optionally for a number of seconds.")]
+    Add(AddBan),
+    #[structopt(name = "remove", about = "Lifts the ban on an IP address or CIDR subnet.")]
+    Remove(RemoveBan),
+    #[structopt(name = "list", about = "Lists the IP addresses and subnets that are banned.")]
+    List(ListBans),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct AddBan {
+    /// Specify the IP address or CIDR subnet to ban, e.g. `1.2.3.4` or `1.2.3.0/24`.
+    pub subnet: String,
+    /// Specify the duration of the ban in seconds; the ban is permanent if unspecified.
+    #[structopt(long = "duration")]
+    pub duration: Option<u64>,
+    /// Specify the network of the ban list.
+    #[structopt(default_value = "2", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node.
+    #[structopt(long)]
+    pub dev: Option<u16>,
+}
+
+impl AddBan {
+    pub fn parse(self) -> Result<String> {
+        let subnet = parse_subnet(&self.subnet)?;
+        let path = ban_list_path(self.network, self.dev)?;
+        ensure_ledger_not_in_use(self.network, self.dev)?;
+
+        let mut ban_list = BanList::load(&path)?;
+        ban_list.ban(subnet, self.duration);
+        ban_list.prune();
+        ban_list.save(&path)?;
+
+        match self.duration {
+            Some(duration) => Ok(format!("Banned {} for {} seconds", subnet, duration)),
+            None => Ok(format!("Banned {}", subnet)),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct RemoveBan {
+    /// Specify the IP address or CIDR subnet to unban, exactly as it was banned.
+    pub subnet: String,
+    /// Specify the network of the ban list.
+    #[structopt(default_value = "2", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node.
+    #[structopt(long)]
+    pub dev: Option<u16>,
+}
+
+impl RemoveBan {
+    pub fn parse(self) -> Result<String> {
+        let subnet = parse_subnet(&self.subnet)?;
+        let path = ban_list_path(self.network, self.dev)?;
+        ensure_ledger_not_in_use(self.network, self.dev)?;
+
+        let mut ban_list = BanList::load(&path)?;
+        if !ban_list.unban(subnet) {
+            return Err(anyhow!("{} is not banned", subnet));
+        }
+        ban_list.prune();
+        ban_list.save(&path)?;
+
+        Ok(format!("Unbanned {}", subnet))
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ListBans {
+    /// Specify the network of the ban list.
+    #[structopt(default_value = "2", long = "network")]
+    pub network: u16,
+    /// Enables development mode, specify the unique ID of the local node.
+    #[structopt(long)]
+    pub dev: Option<u16>,
+    /// If the flag is set, the ban list is printed as JSON.
+    #[structopt(long)]
+    pub json: bool,
+}
+
+impl ListBans {
+    pub fn parse(self) -> Result<String> {
+        let entries = BanList::load(ban_list_path(self.network, self.dev)?)?.entries();
+        if self.json {
+            return Ok(serde_json::to_string_pretty(&entries)?);
+        }
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        match entries.is_empty() {
+            true => Ok("The ban list is empty".to_string()),
+            false => Ok(entries
+                .iter()
+                .map(|entry| match entry.expires_at {
+                    Some(expires_at) => format!("{} (expires in {} secs)", entry.subnet, expires_at.saturating_sub(now)),
+                    None => format!("{} (permanent)", entry.subnet),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")),
+        }
+    }
+}
+
+/// Returns the path of the ban list for the given network, which a running node loads on startup.
+fn ban_list_path(network: u16, dev: Option<u16>) -> Result<PathBuf> {
+    if network != 2 {
+        return Err(anyhow!("Network {} is not supported", network));
+    }
+    Ok(storage_artifact_path(network, dev, "bans"))
+}
+
+///
+/// Returns the path of the JSON file with the given name, which is stored alongside the ledger
+/// of the given network (e.g. `~/.aleo/storage/ledger-2.peers.json` for the name `peers`).
+///
+fn storage_artifact_path(network: u16, dev: Option<u16>, name: &str) -> PathBuf {
+    aleo_std::aleo_ledger_dir(network, dev).with_extension(format!("{}.json", name))
+}
+
+///
+/// Returns an error if the ledger of the given network is in use by a running node.
+///
+/// A running node does not reload its ban list, and overwrites the ban list file on its next change,
+/// so its ban list must be changed with the `banpeer` and `unbanpeer` RPC methods instead.
+///
+fn ensure_ledger_not_in_use(network: u16, dev: Option<u16>) -> Result<()> {
+    let path = aleo_std::aleo_ledger_dir(network, dev);
+    if path.exists() {
+        // Opening the ledger storage for writing fails while a running node holds its lock.
+        if RocksDB::open(&path, network, false).is_err() {
+            return Err(anyhow!(
+                "The ledger ({}) is in use by a running node. Use the `banpeer` and `unbanpeer` RPC methods to change its ban list",
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the little-endian bits of the given message.
+fn to_message_bits(message: &str) -> Vec<bool> {
+    message
+        .as_bytes()
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect()
+}
+
+/// Returns the private key from the given keystore file if one is specified, or otherwise prompts for it.
+fn load_private_key(keystore: &Option<PathBuf>) -> Result<PrivateKey<Testnet2>> {
+    match keystore {
+        Some(path) => Keystore::load(path)?.decrypt(&read_keystore_password("Enter the keystore password: ")?),
+        None => Ok(PrivateKey::from_str(&read_secret("Enter the private key: ")?)?),
+    }
+}
+
+/// Encrypts the given private key with a new password, and saves it to the given keystore file.
+fn save_keystore(private_key: &PrivateKey<Testnet2>, path: &Path) -> Result<()> {
+    let password = read_keystore_password("Enter a new keystore password: ")?;
+    if password != read_keystore_password("Confirm the keystore password: ")? {
+        return Err(anyhow!("The keystore passwords do not match"));
+    }
+    Keystore::new(private_key, &password, &mut rand::thread_rng())?.save(path)
+}
+
+/// Returns the keystore password from the `SNARKOS_KEYSTORE_PASSWORD` environment variable if it is set,
+/// so that a node may load its keystore without a terminal, or otherwise prompts for it.
+fn read_keystore_password(prompt: &str) -> Result<String> {
+    match std::env::var("SNARKOS_KEYSTORE_PASSWORD") {
+        Ok(password) => Ok(password),
+        Err(_) => read_secret(prompt),
+    }
+}
+
+/// Reads a secret from the terminal without echoing it, or from standard input if it is not a terminal.
+fn read_secret(prompt: &str) -> Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+
+    // If standard input is not a terminal, read the secret from the next line.
+    if !io::stdin().is_tty() {
+        let mut secret = String::new();
+        io::stdin().lock().read_line(&mut secret)?;
+        return Ok(secret.trim_end_matches(&['\r', '\n'][..]).to_string());
+    }
+
+    // Otherwise, read the secret from the terminal in raw mode, so that it is not echoed.
+    enable_raw_mode()?;
+    let mut secret = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(KeyEvent { code, modifiers })) => match code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break Err(anyhow!("Interrupted")),
+                KeyCode::Char(c) => secret.push(c),
+                KeyCode::Backspace => {
+                    secret.pop();
+                }
+                _ => (),
+            },
+            Ok(_) => (),
+            Err(error) => break Err(error.into()),
+        }
+    };
+    disable_raw_mode()?;
+    eprintln!();
+
+    result.map(|_| secret)
+}
+
 // This function is responsible for handling OS signals in order for the node to be able to intercept them
 // and perform a clean shutdown.
 // note: only Ctrl-C is currently supported, but it should work on both Unix-family systems and Windows.
@@ -481,3 +1220,35 @@ fn handle_signals<N: Network, E: Environment>(server: Server<N, E>) {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line_takes_precedence() {
+        let path = tempfile::tempdir().unwrap().into_path().join("node.toml");
+        std::fs::write(&path, "rpc_username = \"config\"\nrpc_password = \"config\"\nverbosity = 3\n").unwrap();
+        let var = |key: &str| match key {
+            "SNARKOS_RPC_PASSWORD" => Ok("env".to_string()),
+            _ => Err(std::env::VarError::NotPresent),
+        };
+
+        let layer = |args: &[&str]| {
+            let args = ["snarkos", "--config", path.to_str().unwrap()].iter().chain(args.iter()).copied();
+            Node::layer(&Node::clap().get_matches_from(args), var).unwrap().0
+        };
+
+        // The environment variables take precedence over the config file.
+        let node = layer(&[]);
+        assert_eq!("config", node.rpc_username);
+        assert_eq!("env", node.rpc_password);
+        assert_eq!(3, node.verbosity);
+
+        // The command-line flags take precedence over the environment variables and the config file.
+        let node = layer(&["--username", "cli", "--password", "cli", "--verbosity", "1"]);
+        assert_eq!("cli", node.rpc_username);
+        assert_eq!("cli", node.rpc_password);
+        assert_eq!(1, node.verbosity);
+    }
+}
@@ -17,7 +17,7 @@
 //! Logic for instantiating the RPC server.
 
 use crate::{
-    network::Operator,
+    network::{Operator, Prover},
     rpc::{rpc_impl::RpcImpl, rpc_trait::RpcFunctions},
     Environment,
     LedgerReader,
@@ -57,7 +57,7 @@ pub struct Meta {
 
 impl Metadata for Meta {}
 
-const METHODS_EXPECTING_PARAMS: [&str; 14] = [
+const METHODS_EXPECTING_PARAMS: [&str; 16] = [
     // public
     "getblock",
     "getblocks",
@@ -78,8 +78,13 @@ const METHODS_EXPECTING_PARAMS: [&str; 14] = [
     // "disconnect",
     "connect",
     "getshareforprover",
+    "banpeer",
+    "unbanpeer",
 ];
 
+/// The methods that require the RPC credentials in the authorization header.
+const PROTECTED_METHODS: [&str; 3] = ["banpeer", "unbanpeer", "getbannedpeers"];
+
 /// Starts a local RPC HTTP server at `rpc_port` in a dedicated `tokio` task.
 /// RPC failures do not affect the rest of the node.
 #[allow(clippy::too_many_arguments)]
@@ -93,6 +98,7 @@ pub async fn initialize_rpc_server<N: Network, E: Environment>(
     ledger_router: LedgerRouter<N>,
     operator: Arc<Operator<N, E>>,
     operator_router: OperatorRouter<N>,
+    prover: Arc<Prover<N, E>>,
     prover_router: ProverRouter<N>,
     memory_pool: Arc<RwLock<MemoryPool<N>>>,
 ) -> tokio::task::JoinHandle<()> {
@@ -105,6 +111,7 @@ pub async fn initialize_rpc_server<N: Network, E: Environment>(
         ledger_router,
         operator,
         operator_router,
+        prover,
         prover_router,
         memory_pool,
     );
@@ -139,7 +146,7 @@ async fn handle_rpc<N: Network, E: Environment>(
         .headers()
         .get(hyper::header::AUTHORIZATION)
         .map(|h| h.to_str().unwrap_or("").to_owned());
-    let _meta = Meta { auth };
+    let meta = Meta { auth };
 
     // Save the headers.
     let headers = req.headers().clone();
@@ -186,6 +193,18 @@ async fn handle_rpc<N: Network, E: Environment>(
         }
     };
 
+    // Ensure the caller is authorized, if the request method is protected.
+    if PROTECTED_METHODS.contains(&&*req.method) && !rpc.is_authorized(&meta) {
+        let resp = jrt::Response::<(), ()>::error(
+            jrt::Version::V2,
+            jrt::Error::with_custom_msg(jrt::ErrorCode::ServerError(-32001), "Unauthorized"),
+            req.id.clone(),
+        );
+        let body = serde_json::to_vec(&resp).unwrap_or_default();
+
+        return Ok(hyper::Response::new(body.into()));
+    }
+
     // Handle the request method.
     let response = match &*req.method {
         // Public
@@ -325,6 +344,10 @@ async fn handle_rpc<N: Network, E: Environment>(
             let result = rpc.get_connected_peers().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
+        "getpeerinfo" => {
+            let result = rpc.get_peer_info().await.map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
         "getnodestate" => {
             let result = rpc.get_node_state().await.map_err(convert_crate_err);
             result_to_response(&req, result)
@@ -381,6 +404,36 @@ async fn handle_rpc<N: Network, E: Environment>(
             let result = rpc.get_provers().await.map_err(convert_crate_err);
             result_to_response(&req, result)
         }
+        "getminingrewards" => {
+            let result = rpc.get_mining_rewards().await.map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
+        // Protected
+        "banpeer" => {
+            let subnet = params.remove(0);
+            let duration_in_secs = match params.is_empty() {
+                true => Ok(None),
+                false => serde_json::from_value::<Option<u64>>(params.remove(0)),
+            };
+            match duration_in_secs {
+                Ok(duration_in_secs) => {
+                    let result = rpc.ban_peer(subnet, duration_in_secs).await.map_err(convert_crate_err);
+                    result_to_response(&req, result)
+                }
+                Err(_) => {
+                    let err = jrt::Error::with_custom_msg(jrt::ErrorCode::ParseError, "Invalid ban duration!");
+                    jrt::Response::error(jrt::Version::V2, err, req.id.clone())
+                }
+            }
+        }
+        "unbanpeer" => {
+            let result = rpc.unban_peer(params.remove(0)).await.map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
+        "getbannedpeers" => {
+            let result = rpc.get_banned_peers().await.map_err(convert_crate_err);
+            result_to_response(&req, result)
+        }
         _ => {
             let err = jrt::Error::from_code(jrt::ErrorCode::MethodNotFound);
             jrt::Response::error(jrt::Version::V2, err, req.id.clone())
@@ -436,7 +489,11 @@ fn result_to_response<T: Serialize>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{environment::Client, helpers::State, ledger::Ledger, network::Prover};
+    use crate::{
+        environment::Client,
+        helpers::{NodePublicKey, PeerInfo, State},
+        network::{ledger::Ledger, Operator, Prover},
+    };
     use snarkos_storage::{
         storage::{rocksdb::RocksDB, Storage},
         LedgerState,
@@ -481,9 +538,9 @@ mod tests {
         };
 
         // Derive the storage paths.
-        let (ledger_path, prover_path) = match &path {
-            Some(p) => (p.as_ref().to_path_buf(), temp_dir()),
-            None => (temp_dir(), temp_dir()),
+        let (ledger_path, prover_path, operator_path) = match &path {
+            Some(p) => (p.as_ref().to_path_buf(), temp_dir(), temp_dir()),
+            None => (temp_dir(), temp_dir(), temp_dir()),
         };
 
         // Initialize the node.
@@ -493,7 +550,7 @@ mod tests {
         E::status().update(State::Ready);
 
         // Initialize a new instance for managing peers.
-        let peers = Peers::new(local_ip, None).await;
+        let peers = Peers::new(local_ip, None, None, None, None).await.unwrap();
         // Initialize a new instance for managing the ledger.
         let ledger = Ledger::<N, E>::open::<S, _>(&ledger_path, peers.router())
             .await
@@ -511,16 +568,40 @@ mod tests {
         )
         .await
         .expect("Failed to initialize prover");
+        // Initialize a new instance for managing the operator.
+        let operator = Operator::open::<S, _>(
+            &operator_path,
+            None,
+            local_ip,
+            prover.memory_pool(),
+            peers.router(),
+            ledger.reader(),
+            ledger.router(),
+            prover.router(),
+        )
+        .await
+        .expect("Failed to initialize operator");
 
-        RpcImpl::<N, E>::new(credentials, None, peers, ledger.reader(), prover.router(), prover.memory_pool())
+        RpcImpl::<N, E>::new(
+            credentials,
+            None,
+            peers,
+            ledger.reader(),
+            ledger.router(),
+            operator.clone(),
+            operator.router(),
+            prover.clone(),
+            prover.router(),
+            prover.memory_pool(),
+        )
     }
 
     /// Initializes a new instance of the rpc.
     async fn new_rpc_server<N: Network, E: Environment, S: Storage, P: AsRef<Path>>(path: Option<P>) {
         // Derive the storage paths.
-        let (ledger_path, prover_path) = match &path {
-            Some(p) => (p.as_ref().to_path_buf(), temp_dir()),
-            None => (temp_dir(), temp_dir()),
+        let (ledger_path, prover_path, operator_path) = match &path {
+            Some(p) => (p.as_ref().to_path_buf(), temp_dir(), temp_dir()),
+            None => (temp_dir(), temp_dir(), temp_dir()),
         };
 
         // Initialize the node.
@@ -530,7 +611,7 @@ mod tests {
         E::status().update(State::Ready);
 
         // Initialize a new instance for managing peers.
-        let peers = Peers::new(local_ip, None).await;
+        let peers = Peers::new(local_ip, None, None, None, None).await.unwrap();
         // Initialize a new instance for managing the ledger.
         let ledger = Ledger::<N, E>::open::<S, _>(&ledger_path, peers.router())
             .await
@@ -548,6 +629,19 @@ mod tests {
         )
         .await
         .expect("Failed to initialize prover");
+        // Initialize a new instance for managing the operator.
+        let operator = Operator::open::<S, _>(
+            &operator_path,
+            None,
+            local_ip,
+            prover.memory_pool(),
+            peers.router(),
+            ledger.reader(),
+            ledger.router(),
+            prover.router(),
+        )
+        .await
+        .expect("Failed to initialize operator");
 
         E::tasks().append(
             initialize_rpc_server(
@@ -557,6 +651,10 @@ mod tests {
                 None,
                 &peers,
                 ledger.reader(),
+                ledger.router(),
+                operator.clone(),
+                operator.router(),
+                prover.clone(),
                 prover.router(),
                 prover.memory_pool(),
             )
@@ -1177,6 +1275,7 @@ mod tests {
             "latest_block_height": 0,
             "latest_cumulative_weight": 0,
             "launched": format!("{} minutes ago", 0),
+            "node_id": null,
             "number_of_candidate_peers": 0,
             "number_of_connected_peers": 0,
             "number_of_connected_sync_nodes": 0,
@@ -1201,10 +1300,15 @@ mod tests {
             .expect("Test RPC failed to process request");
 
         // Process the response into a ledger root.
-        let actual: serde_json::Value = process_response(response).await;
+        let mut actual: serde_json::Value = process_response(response).await;
 
         println!("get_node_state: {:?}", actual);
 
+        // Check the node ID, which is generated for the test node.
+        let node_id = actual["node_id"].take();
+        assert!(node_id.as_str().unwrap().parse::<NodePublicKey>().is_ok());
+        actual["node_id"] = serde_json::json!(null);
+
         // Check the node state.
         assert_eq!(expected, actual);
     }
@@ -1332,6 +1436,33 @@ mod tests {
         assert_eq!(actual, Vec::<String>::new());
     }
 
+    #[tokio::test]
+    async fn test_get_peer_info() {
+        // Initialize a new RPC.
+        let rpc = new_rpc::<Testnet2, Client<Testnet2>, RocksDB, PathBuf>(None).await;
+
+        // Initialize a new request that calls the `getpeerinfo` endpoint.
+        let request = Request::new(Body::from(
+            r#"{
+	"jsonrpc": "2.0",
+	"id": "1",
+	"method": "getpeerinfo",
+	"params": []
+}"#,
+        ));
+
+        // Send the request to the RPC.
+        let response = handle_rpc(caller(), rpc, request)
+            .await
+            .expect("Test RPC failed to process request");
+
+        // Process the response into the peer information.
+        let actual: Vec<PeerInfo> = process_response(response).await;
+
+        // Check the peer information.
+        assert!(actual.is_empty());
+    }
+
     #[tokio::test]
     async fn test_send_transaction() {
         let mut rng = ChaChaRng::seed_from_u64(123456789);
@@ -19,7 +19,8 @@
 //! See [RpcFunctions](../trait.RpcFunctions.html) for documentation of public endpoints.
 
 use crate::{
-    network::Operator,
+    helpers::{parse_subnet, BanEntry, PeerInfo, RewardsReport},
+    network::{Operator, Prover},
     rpc::{rpc::*, rpc_trait::RpcFunctions},
     Environment,
     LedgerReader,
@@ -74,10 +75,10 @@ pub struct RpcInner<N: Network, E: Environment> {
     ledger_router: LedgerRouter<N>,
     operator: Arc<Operator<N, E>>,
     operator_router: OperatorRouter<N>,
+    prover: Arc<Prover<N, E>>,
     prover_router: ProverRouter<N>,
     memory_pool: Arc<RwLock<MemoryPool<N>>>,
     /// RPC credentials for accessing guarded endpoints
-    #[allow(unused)]
     pub(crate) credentials: RpcCredentials,
     launched: Instant,
 }
@@ -96,6 +97,7 @@ impl<N: Network, E: Environment> Deref for RpcImpl<N, E> {
 
 impl<N: Network, E: Environment> RpcImpl<N, E> {
     /// Creates a new struct for calling public and private RPC endpoints.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         credentials: RpcCredentials,
         address: Option<Address<N>>,
@@ -104,6 +106,7 @@ impl<N: Network, E: Environment> RpcImpl<N, E> {
         ledger_router: LedgerRouter<N>,
         operator: Arc<Operator<N, E>>,
         operator_router: OperatorRouter<N>,
+        prover: Arc<Prover<N, E>>,
         prover_router: ProverRouter<N>,
         memory_pool: Arc<RwLock<MemoryPool<N>>>,
     ) -> Self {
@@ -114,12 +117,28 @@ impl<N: Network, E: Environment> RpcImpl<N, E> {
             ledger_router,
             operator,
             operator_router,
+            prover,
             prover_router,
             memory_pool,
             credentials,
             launched: Instant::now(),
         }))
     }
+
+    /// Returns `true` if the given metadata contains the RPC credentials, as a basic authorization header.
+    pub(crate) fn is_authorized(&self, meta: &Meta) -> bool {
+        let expected_auth = format!(
+            "Basic {}",
+            base64::encode(format!("{}:{}", self.credentials.username, self.credentials.password))
+        );
+        match &meta.auth {
+            // Compare in constant time, so that the credentials are not leaked through timing.
+            Some(auth) => {
+                auth.len() == expected_auth.len() && auth.bytes().zip(expected_auth.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+            }
+            None => false,
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -166,7 +185,7 @@ impl<N: Network, E: Environment> RpcFunctions<N> for RpcImpl<N, E> {
 
     /// Returns up to `MAXIMUM_BLOCK_REQUEST` blocks from the given `start_block_height` to `end_block_height` (inclusive).
     async fn get_blocks(&self, start_block_height: u32, end_block_height: u32) -> Result<Vec<Block<N>>, RpcError> {
-        let safe_start_height = max(start_block_height, end_block_height.saturating_sub(E::MAXIMUM_BLOCK_REQUEST - 1));
+        let safe_start_height = max(start_block_height, end_block_height.saturating_sub(E::maximum_block_request() - 1));
         Ok(self.ledger.get_blocks(safe_start_height, end_block_height)?)
     }
 
@@ -183,7 +202,7 @@ impl<N: Network, E: Environment> RpcFunctions<N> for RpcImpl<N, E> {
 
     /// Returns up to `MAXIMUM_BLOCK_REQUEST` block hashes from the given `start_block_height` to `end_block_height` (inclusive).
     async fn get_block_hashes(&self, start_block_height: u32, end_block_height: u32) -> Result<Vec<N::BlockHash>, RpcError> {
-        let safe_start_height = max(start_block_height, end_block_height.saturating_sub(E::MAXIMUM_BLOCK_REQUEST - 1));
+        let safe_start_height = max(start_block_height, end_block_height.saturating_sub(E::maximum_block_request() - 1));
         Ok(self.ledger.get_block_hashes(safe_start_height, end_block_height)?)
     }
 
@@ -306,6 +325,11 @@ impl<N: Network, E: Environment> RpcFunctions<N> for RpcImpl<N, E> {
         Ok(self.peers.connected_peers().await)
     }
 
+    /// Returns the information of the peers currently connected to this node.
+    async fn get_peer_info(&self) -> Result<Vec<PeerInfo>, RpcError> {
+        Ok(self.peers.connected_peers_info().await)
+    }
+
     /// Returns the current state of this node.
     async fn get_node_state(&self) -> Result<Value, RpcError> {
         let candidate_peers = self.peers.candidate_peers().await;
@@ -326,6 +350,7 @@ impl<N: Network, E: Environment> RpcFunctions<N> for RpcImpl<N, E> {
             "latest_block_height": latest_block_height,
             "latest_cumulative_weight": latest_cumulative_weight,
             "launched": format!("{} minutes ago", self.launched.elapsed().as_secs() / 60),
+            "node_id": self.peers.node_id(),
             "number_of_candidate_peers": number_of_candidate_peers,
             "number_of_connected_peers": number_of_connected_peers,
             "number_of_connected_sync_nodes": number_of_connected_sync_nodes,
@@ -396,6 +421,34 @@ impl<N: Network, E: Environment> RpcFunctions<N> for RpcImpl<N, E> {
         Ok(serde_json::json!(provers))
     }
 
+    /// Returns the mining rewards report for the address of this node.
+    async fn get_mining_rewards(&self) -> Result<Value, RpcError> {
+        let address = match self.address {
+            Some(address) => address,
+            None => return Err(RpcError::Message("This node does not have an address".to_string())),
+        };
+        let report = RewardsReport::new(&self.ledger, self.prover.to_coinbase_records(), address);
+        Ok(serde_json::to_value(report)?)
+    }
+
+    /// Bans the given IP address or subnet, for the given number of seconds or permanently.
+    async fn ban_peer(&self, subnet: Value, duration_in_secs: Option<u64>) -> Result<bool, RpcError> {
+        let subnet = parse_subnet(&serde_json::from_value::<String>(subnet)?)?;
+        self.peers.ban(subnet, duration_in_secs).await?;
+        Ok(true)
+    }
+
+    /// Lifts the ban on the given IP address or subnet, and returns `true` if it was banned.
+    async fn unban_peer(&self, subnet: Value) -> Result<bool, RpcError> {
+        let subnet = parse_subnet(&serde_json::from_value::<String>(subnet)?)?;
+        Ok(self.peers.unban(subnet).await?)
+    }
+
+    /// Returns the IP addresses and subnets that are banned.
+    async fn get_banned_peers(&self) -> Result<Vec<BanEntry>, RpcError> {
+        Ok(self.peers.banned_peers().await)
+    }
+
     // /// Returns the current mempool and sync information known by this node.
     // async fn get_block_template(&self) -> Result<BlockTemplate, RpcError> {
     //     let canon = self.storage.canon().await?;
@@ -16,7 +16,10 @@
 
 //! Definition of the public and private RPC endpoints.
 
-use crate::rpc::rpc_impl::RpcError;
+use crate::{
+    helpers::{BanEntry, PeerInfo},
+    rpc::rpc_impl::RpcError,
+};
 use snarkvm::dpc::{Block, BlockHeader, Network, Transaction, Transactions, Transition};
 
 use std::net::SocketAddr;
@@ -87,6 +90,9 @@ pub trait RpcFunctions<N: Network> {
     #[doc = include_str!("./documentation/public_endpoints/getconnectedpeers.md")]
     async fn get_connected_peers(&self) -> Result<Vec<SocketAddr>, RpcError>;
 
+    #[doc = include_str!("./documentation/public_endpoints/getpeerinfo.md")]
+    async fn get_peer_info(&self) -> Result<Vec<PeerInfo>, RpcError>;
+
     #[doc = include_str!("./documentation/public_endpoints/getnodestate.md")]
     async fn get_node_state(&self) -> Result<serde_json::Value, RpcError>;
 
@@ -100,6 +106,18 @@ pub trait RpcFunctions<N: Network> {
     async fn get_shares(&self) -> Result<u64, RpcError>;
 
     async fn get_provers(&self) -> Result<serde_json::Value, RpcError>;
+
+    #[doc = include_str!("./documentation/public_endpoints/getminingrewards.md")]
+    async fn get_mining_rewards(&self) -> Result<serde_json::Value, RpcError>;
+
+    #[doc = include_str!("./documentation/private_endpoints/banpeer.md")]
+    async fn ban_peer(&self, subnet: serde_json::Value, duration_in_secs: Option<u64>) -> Result<bool, RpcError>;
+
+    #[doc = include_str!("./documentation/private_endpoints/unbanpeer.md")]
+    async fn unban_peer(&self, subnet: serde_json::Value) -> Result<bool, RpcError>;
+
+    #[doc = include_str!("./documentation/private_endpoints/getbannedpeers.md")]
+    async fn get_banned_peers(&self) -> Result<Vec<BanEntry>, RpcError>;
 }
 
 // /// Definition of private RPC endpoints that require authentication.
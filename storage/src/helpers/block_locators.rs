@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::MAXIMUM_BLOCK_LOCATORS;
 use snarkvm::{
     dpc::{BlockHeader, Network},
     utilities::{
@@ -29,7 +30,11 @@ use snarkvm::{
 
 use rayon::prelude::*;
 use serde::{de, ser, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
-use std::{collections::BTreeMap, ops::Deref};
+use std::{
+    collections::BTreeMap,
+    io::{Error as IoError, ErrorKind},
+    ops::Deref,
+};
 
 ///
 /// A helper struct to represent block locators from the ledger.
@@ -76,6 +81,15 @@ impl<N: Network> FromBytes for BlockLocators<N> {
     fn read_le<R: Read>(mut reader: R) -> IoResult<Self> {
         let num_locators: u32 = FromBytes::read_le(&mut reader)?;
 
+        // Ensure the number of block locators is within bounds (including the genesis locator),
+        // before allocating for them, as the block locators may be received from an untrusted peer.
+        if num_locators > MAXIMUM_BLOCK_LOCATORS.saturating_add(1) {
+            return Err(IoError::new(
+                ErrorKind::InvalidData,
+                format!("Invalid number of block locators ({})", num_locators),
+            ));
+        }
+
         let mut block_headers_bytes = Vec::with_capacity(num_locators as usize);
 
         for _ in 0..num_locators {
@@ -94,8 +108,11 @@ impl<N: Network> FromBytes for BlockLocators<N> {
 
         let block_locators = block_headers_bytes
             .into_par_iter()
-            .map(|(height, hash, bytes)| (height, (hash, bytes.map(|bytes| BlockHeader::<N>::read_le(&bytes[..]).unwrap()))))
-            .collect::<BTreeMap<_, (_, _)>>();
+            .map(|(height, hash, bytes)| match bytes {
+                Some(bytes) => Ok((height, (hash, Some(BlockHeader::<N>::read_le(&bytes[..])?)))),
+                None => Ok((height, (hash, None))),
+            })
+            .collect::<IoResult<BTreeMap<_, (_, _)>>>()?;
 
         Ok(Self::from(block_locators))
     }
@@ -220,4 +237,30 @@ mod tests {
         assert_eq!(expected_block_locators, BlockLocators::read_le(&expected_bytes[..]).unwrap());
         assert_eq!(expected_block_locators, bincode::deserialize(&candidate_bytes[..]).unwrap());
     }
+
+    #[test]
+    fn test_block_locators_read_le_invalid() {
+        let block_locators = BlockLocators::<Testnet2>::from(
+            [(
+                0,
+                (Testnet2::genesis_block().hash(), Some(Testnet2::genesis_block().header().clone())),
+            )]
+            .into(),
+        );
+        let bytes = block_locators.to_bytes_le().unwrap();
+
+        // Ensure every truncation of the block locators is rejected, instead of panicking.
+        for length in 0..bytes.len() {
+            assert!(BlockLocators::<Testnet2>::read_le(&bytes[..length]).is_err());
+        }
+
+        // Ensure a corrupted block header is rejected, instead of panicking.
+        let mut corrupted_bytes = bytes.clone();
+        let header_start = bytes.len() - Testnet2::HEADER_SIZE_IN_BYTES;
+        corrupted_bytes[header_start..].iter_mut().for_each(|byte| *byte = u8::MAX);
+        assert!(BlockLocators::<Testnet2>::read_le(&corrupted_bytes[..]).is_err());
+
+        // Ensure an excessive number of block locators is rejected before allocating for them.
+        assert!(BlockLocators::<Testnet2>::read_le(&u32::MAX.to_le_bytes()[..]).is_err());
+    }
 }
@@ -0,0 +1,258 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm::{
+    dpc::{Block, Network},
+    utilities::{FromBytes, ToBytes},
+};
+
+use anyhow::{anyhow, Result};
+use crc32fast::Hasher;
+use std::{
+    io::{ErrorKind, Read, Write},
+    marker::PhantomData,
+};
+
+/// The magic bytes at the start of every block stream.
+const MAGIC: &[u8; 8] = b"snarkOS\0";
+/// The version of the block stream format.
+const FORMAT_VERSION: u16 = 1;
+/// The block height that marks the trailer of a block stream.
+const TRAILER_MARKER: u32 = u32::MAX;
+/// The maximum size of a serialized block in a block stream.
+const MAXIMUM_BLOCK_SIZE: usize = 128 * 1024 * 1024; // 128 MiB
+
+///
+/// A writer for a stream of blocks in increasing height order.
+///
+/// The format of a block stream is:
+///     header  := MAGIC || FORMAT_VERSION (u16) || NETWORK_ID (u16)
+///     record  := block_height (u32) || length (u32) || block_bytes || checksum (u32)
+///     trailer := TRAILER_MARKER (u32) || number_of_blocks (u32) || stream_checksum (u32)
+///
+/// The checksum of a record is the CRC32 of its height, length, and block bytes,
+/// while the checksum of the stream is the CRC32 of every byte that precedes it.
+///
+pub struct BlockStreamWriter<N: Network, W: Write> {
+    writer: W,
+    stream_hasher: Hasher,
+    number_of_blocks: u32,
+    last_block_height: Option<u32>,
+    _phantom: PhantomData<N>,
+}
+
+impl<N: Network, W: Write> BlockStreamWriter<N, W> {
+    /// Initializes a new block stream, writing the header to the given writer.
+    pub fn new(writer: W) -> Result<Self> {
+        let mut stream = Self {
+            writer,
+            stream_hasher: Hasher::new(),
+            number_of_blocks: 0,
+            last_block_height: None,
+            _phantom: PhantomData,
+        };
+
+        stream.write_all(MAGIC)?;
+        stream.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        stream.write_all(&N::NETWORK_ID.to_le_bytes())?;
+        Ok(stream)
+    }
+
+    /// Appends the given block to the stream. Blocks must be written in increasing height order.
+    pub fn write_block(&mut self, block: &Block<N>) -> Result<()> {
+        let block_height = block.height();
+        if let Some(last_block_height) = self.last_block_height {
+            if block_height != last_block_height + 1 {
+                return Err(anyhow!("Expected block {}, found block {}", last_block_height + 1, block_height));
+            }
+        }
+
+        let block_bytes = block.to_bytes_le()?;
+
+        let mut record_hasher = Hasher::new();
+        record_hasher.update(&block_height.to_le_bytes());
+        record_hasher.update(&(block_bytes.len() as u32).to_le_bytes());
+        record_hasher.update(&block_bytes);
+
+        self.write_all(&block_height.to_le_bytes())?;
+        self.write_all(&(block_bytes.len() as u32).to_le_bytes())?;
+        self.write_all(&block_bytes)?;
+        self.write_all(&record_hasher.finalize().to_le_bytes())?;
+
+        self.number_of_blocks += 1;
+        self.last_block_height = Some(block_height);
+        Ok(())
+    }
+
+    /// Writes the trailer of the stream, and returns the number of blocks written.
+    pub fn finish(mut self) -> Result<u32> {
+        let number_of_blocks = self.number_of_blocks;
+        self.write_all(&TRAILER_MARKER.to_le_bytes())?;
+        self.write_all(&number_of_blocks.to_le_bytes())?;
+        let checksum = self.stream_hasher.clone().finalize();
+        self.writer.write_all(&checksum.to_le_bytes())?;
+        self.writer.flush()?;
+        Ok(number_of_blocks)
+    }
+
+    /// Writes the given bytes to the writer, and updates the stream checksum.
+    fn write_all(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes)?;
+        self.stream_hasher.update(bytes);
+        Ok(())
+    }
+}
+
+///
+/// A reader for a stream of blocks written by `BlockStreamWriter`.
+///
+/// Each block is verified against its record checksum as it is read, and the stream
+/// checksum is verified once the trailer is reached. A stream that ends without a
+/// trailer is reported as truncated, after yielding every block that precedes the cut.
+///
+pub struct BlockStreamReader<N: Network, R: Read> {
+    reader: R,
+    stream_hasher: Hasher,
+    number_of_blocks: u32,
+    is_finished: bool,
+    _phantom: PhantomData<N>,
+}
+
+impl<N: Network, R: Read> BlockStreamReader<N, R> {
+    /// Initializes a new block stream, reading and checking the header from the given reader.
+    pub fn new(reader: R) -> Result<Self> {
+        let mut stream = Self {
+            reader,
+            stream_hasher: Hasher::new(),
+            number_of_blocks: 0,
+            is_finished: false,
+            _phantom: PhantomData,
+        };
+
+        let mut magic = [0u8; 8];
+        stream.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(anyhow!("The given file is not a block stream"));
+        }
+
+        let format_version = u16::from_le_bytes(stream.read_array()?);
+        if format_version != FORMAT_VERSION {
+            return Err(anyhow!("Unsupported block stream version {}", format_version));
+        }
+
+        let network_id = u16::from_le_bytes(stream.read_array()?);
+        if network_id != N::NETWORK_ID {
+            return Err(anyhow!(
+                "The block stream is for network {}, expected network {}",
+                network_id,
+                N::NETWORK_ID
+            ));
+        }
+
+        Ok(stream)
+    }
+
+    /// Returns the next block in the stream, or `None` once the trailer has been read and verified.
+    pub fn read_block(&mut self) -> Result<Option<Block<N>>> {
+        if self.is_finished {
+            return Ok(None);
+        }
+
+        let block_height = u32::from_le_bytes(self.read_array()?);
+
+        // Check the trailer, if it has been reached.
+        if block_height == TRAILER_MARKER {
+            let number_of_blocks = u32::from_le_bytes(self.read_array()?);
+            let expected_checksum = self.stream_hasher.clone().finalize();
+            let checksum = u32::from_le_bytes(self.read_array()?);
+
+            if number_of_blocks != self.number_of_blocks {
+                return Err(anyhow!(
+                    "The block stream declares {} blocks, found {}",
+                    number_of_blocks,
+                    self.number_of_blocks
+                ));
+            }
+            if checksum != expected_checksum {
+                return Err(anyhow!("The block stream checksum is invalid"));
+            }
+
+            self.is_finished = true;
+            return Ok(None);
+        }
+
+        let length = u32::from_le_bytes(self.read_array()?);
+        if length as usize > MAXIMUM_BLOCK_SIZE {
+            return Err(anyhow!("Block {} exceeds the maximum block size ({} bytes)", block_height, length));
+        }
+
+        let mut block_bytes = vec![0u8; length as usize];
+        self.read_exact(&mut block_bytes)?;
+        let checksum = u32::from_le_bytes(self.read_array()?);
+
+        // Ensure the record checksum matches.
+        let mut record_hasher = Hasher::new();
+        record_hasher.update(&block_height.to_le_bytes());
+        record_hasher.update(&length.to_le_bytes());
+        record_hasher.update(&block_bytes);
+        if checksum != record_hasher.finalize() {
+            return Err(anyhow!("Block {} in the block stream has an invalid checksum", block_height));
+        }
+
+        let block = Block::<N>::from_bytes_le(&block_bytes)?;
+        if block.height() != block_height {
+            return Err(anyhow!("Block {} in the block stream has a mismatching height", block_height));
+        }
+
+        self.number_of_blocks += 1;
+        Ok(Some(block))
+    }
+
+    /// Reads exactly `SIZE` bytes from the reader.
+    fn read_array<const SIZE: usize>(&mut self) -> Result<[u8; SIZE]> {
+        let mut bytes = [0u8; SIZE];
+        self.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Reads exactly enough bytes to fill the given buffer, and updates the stream checksum.
+    fn read_exact(&mut self, buffer: &mut [u8]) -> Result<()> {
+        match self.reader.read_exact(buffer) {
+            Ok(()) => {
+                self.stream_hasher.update(buffer);
+                Ok(())
+            }
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => Err(anyhow!("The block stream is truncated")),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl<N: Network, R: Read> Iterator for BlockStreamReader<N, R> {
+    type Item = Result<Block<N>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_block() {
+            Ok(Some(block)) => Some(Ok(block)),
+            Ok(None) => None,
+            Err(error) => {
+                // Stop iterating after the first error.
+                self.is_finished = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
@@ -16,3 +16,6 @@
 
 pub mod block_locators;
 pub use block_locators::*;
+
+pub mod block_stream;
+pub use block_stream::*;
@@ -21,10 +21,11 @@
 extern crate tracing;
 
 mod helpers;
-pub use helpers::BlockLocators;
+pub use helpers::{BlockLocators, BlockStreamReader, BlockStreamWriter};
 
 pub(crate) mod state;
 pub use state::{
+    InvalidBlockError,
     LedgerState,
     Metadata,
     OperatorState,
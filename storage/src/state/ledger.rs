@@ -15,7 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    helpers::BlockLocators,
+    helpers::{BlockLocators, BlockStreamReader, BlockStreamWriter},
     storage::{DataMap, Map, MapId, Storage},
 };
 use snarkvm::dpc::prelude::*;
@@ -29,6 +29,8 @@ use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{BTreeMap, HashSet},
+    fmt,
+    io::{Read, Write},
     path::Path,
     sync::{
         atomic::{AtomicBool, AtomicU32, Ordering},
@@ -45,6 +47,25 @@ pub const MAXIMUM_QUADRATIC_BLOCK_LOCATORS: u32 = 32;
 /// The total maximum number of block locators.
 pub const MAXIMUM_BLOCK_LOCATORS: u32 = MAXIMUM_LINEAR_BLOCK_LOCATORS.saturating_add(MAXIMUM_QUADRATIC_BLOCK_LOCATORS);
 
+/// An error for a block that fails validation against the ledger, as opposed to a failure of the ledger storage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidBlockError(pub String);
+
+impl fmt::Display for InvalidBlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidBlockError {}
+
+/// Returns an `InvalidBlockError` with the given formatted message.
+macro_rules! invalid_block {
+    ($($arg:tt)*) => {
+        anyhow::Error::new(InvalidBlockError(format!($($arg)*)))
+    };
+}
+
 ///
 /// A helper struct containing transaction metadata.
 ///
@@ -102,6 +123,22 @@ impl<N: Network> LedgerState<N> {
     /// a read-only instance of `LedgerState` may only call immutable methods.
     ///
     pub fn open_writer<S: Storage, P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_writer_internal::<S, P>(path, false)
+    }
+
+    ///
+    /// Opens a new writable instance of `LedgerState` from the given storage path,
+    /// repairing any inconsistent ledger state that `LedgerState::open_writer` is unable to resolve.
+    ///
+    /// To repair the ledger, any block that is missing its ledger root is removed from storage.
+    /// This is intended for offline recovery, as the removed blocks must be synced again.
+    ///
+    pub fn open_writer_with_repair<S: Storage, P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_writer_internal::<S, P>(path, true)
+    }
+
+    /// Opens a new writable instance of `LedgerState` from the given storage path, repairing its state if `is_repair` is `true`.
+    fn open_writer_internal<S: Storage, P: AsRef<Path>>(path: P, is_repair: bool) -> Result<Self> {
         // Open storage.
         let context = N::NETWORK_ID;
         let is_read_only = false;
@@ -124,13 +161,16 @@ impl<N: Network> LedgerState<N> {
         let mut latest_block_height = match (ledger.ledger_roots.values().max(), ledger.blocks.block_heights.keys().max()) {
             (Some(latest_block_height_0), Some(latest_block_height_1)) => match latest_block_height_0 == latest_block_height_1 {
                 true => latest_block_height_0,
-                false => match ledger.try_fixing_inconsistent_state() {
-                    Ok(current_block_height) => current_block_height,
-                    Err(error) => return Err(error),
+                false => match is_repair {
+                    true => ledger.repair_inconsistent_state()?,
+                    false => ledger.try_fixing_inconsistent_state()?,
                 },
             },
             (None, None) => 0u32,
-            _ => return Err(anyhow!("Ledger storage state is inconsistent")),
+            _ => match is_repair {
+                true => ledger.repair_inconsistent_state()?,
+                false => return Err(anyhow!("Ledger storage state is inconsistent")),
+            },
         };
 
         // If this is new storage, initialize it with the genesis block.
@@ -725,7 +765,12 @@ impl<N: Network> LedgerState<N> {
         }
     }
 
+    ///
     /// Adds the given block as the next block in the ledger to storage.
+    ///
+    /// If the block fails validation against the ledger, the returned error is an `InvalidBlockError`,
+    /// which distinguishes an invalid block from a failure of the ledger storage.
+    ///
     pub fn add_next_block(&self, block: &Block<N>) -> Result<()> {
         // If the storage is in read-only mode, this method cannot be called.
         if self.is_read_only() {
@@ -734,7 +779,7 @@ impl<N: Network> LedgerState<N> {
 
         // Ensure the block itself is valid.
         if !block.is_valid() {
-            return Err(anyhow!("Block {} is invalid", block.height()));
+            return Err(invalid_block!("Block {} is invalid", block.height()));
         }
 
         // Retrieve the current block.
@@ -743,7 +788,7 @@ impl<N: Network> LedgerState<N> {
         // Ensure the block height increments by one.
         let block_height = block.height();
         if block_height != current_block.height() + 1 {
-            return Err(anyhow!(
+            return Err(invalid_block!(
                 "Block {} should have block height {}",
                 block_height,
                 current_block.height() + 1
@@ -752,7 +797,7 @@ impl<N: Network> LedgerState<N> {
 
         // Ensure the previous block hash matches.
         if block.previous_block_hash() != current_block.hash() {
-            return Err(anyhow!(
+            return Err(invalid_block!(
                 "Block {} has an incorrect previous block hash in the canon chain",
                 block_height
             ));
@@ -761,12 +806,12 @@ impl<N: Network> LedgerState<N> {
         // Ensure the next block timestamp is within the declared time limit.
         let now = chrono::Utc::now().timestamp();
         if block.timestamp() > (now + N::ALEO_FUTURE_TIME_LIMIT_IN_SECS) {
-            return Err(anyhow!("The given block timestamp exceeds the time limit"));
+            return Err(invalid_block!("The given block timestamp exceeds the time limit"));
         }
 
         // Ensure the next block timestamp is after the current block timestamp.
         if block.timestamp() <= current_block.timestamp() {
-            return Err(anyhow!("The given block timestamp is before the current timestamp"));
+            return Err(invalid_block!("The given block timestamp is before the current timestamp"));
         }
 
         // Compute the expected difficulty target.
@@ -781,7 +826,7 @@ impl<N: Network> LedgerState<N> {
 
         // Ensure the expected difficulty target is met.
         if block.difficulty_target() != expected_difficulty_target {
-            return Err(anyhow!(
+            return Err(invalid_block!(
                 "Block {} has an incorrect difficulty target. Found {}, but expected {}",
                 block_height,
                 block.difficulty_target(),
@@ -794,7 +839,7 @@ impl<N: Network> LedgerState<N> {
             .cumulative_weight()
             .saturating_add((u64::MAX / expected_difficulty_target) as u128);
         if block.cumulative_weight() != expected_cumulative_weight {
-            return Err(anyhow!(
+            return Err(invalid_block!(
                 "The given cumulative weight is incorrect. Found {}, but expected {}",
                 block.cumulative_weight(),
                 expected_cumulative_weight
@@ -803,30 +848,30 @@ impl<N: Network> LedgerState<N> {
 
         // Ensure the block height does not already exist.
         if self.contains_block_height(block_height)? {
-            return Err(anyhow!("Block {} already exists in the canon chain", block_height));
+            return Err(invalid_block!("Block {} already exists in the canon chain", block_height));
         }
 
         // Ensure the block hash does not already exist.
         if self.contains_block_hash(&block.hash())? {
-            return Err(anyhow!("Block {} has a repeat block hash in the canon chain", block_height));
+            return Err(invalid_block!("Block {} has a repeat block hash in the canon chain", block_height));
         }
 
         // Ensure the ledger root in the block matches the current ledger root.
         if block.previous_ledger_root() != self.latest_ledger_root() {
-            return Err(anyhow!("Block {} declares an incorrect ledger root", block_height));
+            return Err(invalid_block!("Block {} declares an incorrect ledger root", block_height));
         }
 
         // Ensure the canon chain does not already contain the given serial numbers.
         for serial_number in block.serial_numbers() {
             if self.contains_serial_number(serial_number)? {
-                return Err(anyhow!("Serial number {} already exists in the ledger", serial_number));
+                return Err(invalid_block!("Serial number {} already exists in the ledger", serial_number));
             }
         }
 
         // Ensure the canon chain does not already contain the given commitments.
         for commitment in block.commitments() {
             if self.contains_commitment(commitment)? {
-                return Err(anyhow!("Commitment {} already exists in the ledger", commitment));
+                return Err(invalid_block!("Commitment {} already exists in the ledger", commitment));
             }
         }
 
@@ -834,7 +879,7 @@ impl<N: Network> LedgerState<N> {
         for transaction in block.transactions().iter() {
             // Ensure the transactions in the given block do not already exist.
             if self.contains_transaction(&transaction.transaction_id())? {
-                return Err(anyhow!(
+                return Err(invalid_block!(
                     "Transaction {} in block {} has a duplicate transaction in the ledger",
                     transaction.transaction_id(),
                     block_height
@@ -843,7 +888,7 @@ impl<N: Network> LedgerState<N> {
 
             // Ensure the transaction in the block references a valid past or current ledger root.
             if !self.contains_ledger_root(&transaction.ledger_root())? {
-                return Err(anyhow!(
+                return Err(invalid_block!(
                     "Transaction {} in block {} references non-existent ledger root {}",
                     transaction.transaction_id(),
                     block_height,
@@ -886,7 +931,7 @@ impl<N: Network> LedgerState<N> {
         }
 
         // Fetch the blocks to be removed. This ensures the blocks to be removed exist in the ledger,
-        // and is used during the removal process to expedite the procedure.
+        // and they are returned once removed.
         let start_block_height = latest_block_height.saturating_sub(number_of_blocks);
         let blocks: BTreeMap<u32, Block<N>> = self
             .get_blocks(start_block_height, latest_block_height)?
@@ -898,27 +943,7 @@ impl<N: Network> LedgerState<N> {
         let _map_lock = self.map_lock.read();
 
         // Process the block removals.
-        let mut current_block_height = latest_block_height;
-        let mut current_block = blocks.get(&current_block_height);
-        while current_block_height > block_height {
-            match current_block {
-                Some(block) => {
-                    // Update the internal storage state of the ledger.
-                    self.blocks.remove_block(current_block_height)?;
-                    self.ledger_roots.remove(&block.previous_ledger_root())?;
-                    // Decrement the current block height, and update the current block.
-                    current_block_height = current_block_height.saturating_sub(1);
-                    current_block = blocks.get(&current_block_height);
-                }
-                None => match self.try_fixing_inconsistent_state() {
-                    Ok(block_height) => {
-                        current_block_height = block_height;
-                        break;
-                    }
-                    Err(error) => return Err(error),
-                },
-            }
-        }
+        let current_block_height = self.remove_blocks(latest_block_height, block_height)?;
 
         // Update the latest block.
         *self.latest_block.write() = self.get_block(current_block_height)?;
@@ -933,6 +958,350 @@ impl<N: Network> LedgerState<N> {
         Ok(blocks.values().skip(1).cloned().collect())
     }
 
+    ///
+    /// Reverts the ledger state back to the given block height, returning the number of removed blocks on success.
+    ///
+    /// Unlike `LedgerState::revert_to_block_height`, the number of blocks that may be removed is not
+    /// bounded by the maximum fork depth, as this is intended for offline recovery of the ledger.
+    ///
+    pub fn rewind_to_block_height(&self, block_height: u32) -> Result<u32> {
+        // If the storage is in read-only mode, this method cannot be called.
+        if self.is_read_only() {
+            return Err(anyhow!("Ledger is in read-only mode"));
+        }
+
+        // Ensure the reverted block height exists in the ledger.
+        let latest_block_height = self.latest_block_height();
+        if block_height > latest_block_height || self.get_block(block_height).is_err() {
+            return Err(anyhow!("Attempted to return to block height {}, which is invalid", block_height));
+        }
+
+        // Acquire the map lock to ensure the following operations aren't interrupted by a shutdown.
+        let _map_lock = self.map_lock.read();
+
+        // Process the block removals.
+        let current_block_height = self.remove_blocks(latest_block_height, block_height)?;
+
+        // Update the latest block.
+        *self.latest_block.write() = self.get_block(current_block_height)?;
+        // Regenerate the latest ledger state.
+        self.regenerate_latest_ledger_state()?;
+        // Regenerate the ledger tree.
+        self.regenerate_ledger_tree()?;
+
+        Ok(latest_block_height - current_block_height)
+    }
+
+    ///
+    /// Removes the blocks from the latest block height down to the given block height, in decreasing order,
+    /// and returns the block height that the ledger was reverted to. The caller must hold the map lock.
+    ///
+    /// If a block is missing, the removal stops, and the inconsistent state of the ledger is resolved instead.
+    ///
+    fn remove_blocks(&self, latest_block_height: u32, block_height: u32) -> Result<u32> {
+        let mut current_block_height = latest_block_height;
+        while current_block_height > block_height {
+            match self.get_previous_ledger_root(current_block_height) {
+                Ok(previous_ledger_root) => {
+                    // Update the internal storage state of the ledger.
+                    self.blocks.remove_block(current_block_height)?;
+                    self.ledger_roots.remove(&previous_ledger_root)?;
+                    // Decrement the current block height.
+                    current_block_height = current_block_height.saturating_sub(1);
+
+                    if current_block_height % 1000 == 0 {
+                        debug!("Reverted the ledger to block {}", current_block_height);
+                    }
+                }
+                Err(_) => return self.try_fixing_inconsistent_state(),
+            }
+        }
+        Ok(current_block_height)
+    }
+
+    ///
+    /// Writes the canonical blocks from the given start block height to the latest block height
+    /// as a block stream to the given writer, returning the number of blocks written.
+    ///
+    pub fn export_blocks<W: Write>(&self, writer: W, start_block_height: u32) -> Result<u32> {
+        let latest_block_height = self.latest_block_height();
+        if start_block_height > latest_block_height {
+            return Err(anyhow!(
+                "Attempted to export from block {}, but the latest block is {}",
+                start_block_height,
+                latest_block_height
+            ));
+        }
+
+        let mut stream = BlockStreamWriter::<N, W>::new(writer)?;
+        for block_height in start_block_height..=latest_block_height {
+            stream.write_block(&self.get_block(block_height)?)?;
+
+            if block_height % 1000 == 0 {
+                debug!("Exported the ledger up to block {}", block_height);
+            }
+        }
+        stream.finish()
+    }
+
+    ///
+    /// Adds the blocks from the given block stream to the ledger, returning the number of blocks added.
+    ///
+    /// Blocks that already exist in the ledger are skipped, which allows an interrupted import
+    /// to resume from the same block stream. If a block in the stream does not match the block
+    /// at the same height in the ledger, the import is aborted.
+    ///
+    pub fn import_blocks<R: Read>(&self, reader: R) -> Result<u32> {
+        // If the storage is in read-only mode, this method cannot be called.
+        if self.is_read_only() {
+            return Err(anyhow!("Ledger is in read-only mode"));
+        }
+
+        let mut number_of_blocks = 0u32;
+        for block in BlockStreamReader::<N, R>::new(reader)? {
+            let block = block?;
+
+            // Skip the block if it already exists in the ledger.
+            if block.height() <= self.latest_block_height() {
+                if self.get_block_hash(block.height())? != block.hash() {
+                    return Err(anyhow!("Block {} in the block stream does not match the ledger", block.height()));
+                }
+                continue;
+            }
+
+            self.add_next_block(&block)?;
+            number_of_blocks += 1;
+
+            if block.height() % 1000 == 0 {
+                debug!("Imported the ledger up to block {}", block.height());
+            }
+        }
+        Ok(number_of_blocks)
+    }
+
+    ///
+    /// Verifies the integrity of the ledger in storage, returning every inconsistency found, by block height.
+    ///
+    /// This checks that each block is linked to its predecessor, that the ledger roots match the ledger tree,
+    /// and that each transaction, transition, serial number, and commitment is indexed with its block.
+    /// If `is_full` is `true`, each block is also reconstructed from storage and checked with `Block::is_valid`.
+    ///
+    pub fn verify(&self, is_full: bool) -> Result<BTreeMap<u32, Vec<String>>> {
+        let mut report: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        let mut report_issue = |block_height: u32, issue: String| report.entry(block_height).or_default().push(issue);
+
+        // Determine the latest block height in storage.
+        let latest_block_height = match self.blocks.block_heights.keys().max() {
+            Some(latest_block_height) => latest_block_height,
+            None => return Err(anyhow!("Ledger storage is empty")),
+        };
+
+        let mut ledger_tree = LedgerTree::<N>::new()?;
+        let mut previous_block_hash = None;
+        let (mut number_of_transactions, mut number_of_transitions) = (0usize, 0usize);
+        let (mut number_of_serial_numbers, mut number_of_commitments) = (0usize, 0usize);
+
+        for block_height in 0..=latest_block_height {
+            // Retrieve the block hash.
+            let block_hash = match self.blocks.block_heights.get(&block_height)? {
+                Some(block_hash) => block_hash,
+                None => {
+                    report_issue(block_height, "Block is missing from the block heights map".to_string());
+                    previous_block_hash = None;
+                    continue;
+                }
+            };
+
+            // Retrieve the block header.
+            let block_header = match self.blocks.block_headers.get(&block_hash)? {
+                Some(block_header) => block_header,
+                None => {
+                    report_issue(block_height, format!("Block {} is missing from the block headers map", block_hash));
+                    previous_block_hash = None;
+                    continue;
+                }
+            };
+            if block_header.height() != block_height {
+                report_issue(
+                    block_height,
+                    format!("Block header declares block height {}", block_header.height()),
+                );
+            }
+
+            // Ensure the block is linked to the previous block.
+            if let Some(previous_block_hash) = previous_block_hash {
+                if block_header.previous_block_hash() != previous_block_hash {
+                    report_issue(block_height, "Block has an incorrect previous block hash".to_string());
+                }
+            }
+            previous_block_hash = Some(block_hash);
+
+            // Ensure the ledger root matches the ledger tree, and is saved with the block height.
+            let previous_ledger_root = block_header.previous_ledger_root();
+            if previous_ledger_root != ledger_tree.root() {
+                report_issue(
+                    block_height,
+                    "Block declares a ledger root that does not match the ledger tree".to_string(),
+                );
+            }
+            match self.ledger_roots.get(&previous_ledger_root)? {
+                Some(candidate_height) if candidate_height == block_height => (),
+                Some(candidate_height) => {
+                    report_issue(block_height, format!("Ledger root is saved with block height {}", candidate_height))
+                }
+                None => report_issue(block_height, "Ledger root is missing from the ledger roots map".to_string()),
+            }
+            ledger_tree.add(&block_hash)?;
+
+            // Retrieve the block transaction IDs.
+            let transaction_ids = match self.blocks.block_transactions.get(&block_hash)? {
+                Some(transaction_ids) => transaction_ids,
+                None => {
+                    report_issue(block_height, "Block is missing from the block transactions map".to_string());
+                    continue;
+                }
+            };
+
+            // Ensure each transaction is indexed with this block.
+            for (index, transaction_id) in transaction_ids.iter().enumerate() {
+                let (transition_ids, metadata) = match self.blocks.transactions.transactions.get(transaction_id)? {
+                    Some((_, transition_ids, metadata)) => (transition_ids, metadata),
+                    None => {
+                        report_issue(
+                            block_height,
+                            format!("Transaction {} is missing from the transactions map", transaction_id),
+                        );
+                        continue;
+                    }
+                };
+                number_of_transactions += 1;
+
+                if metadata != Metadata::new(block_height, block_hash, block_header.timestamp(), index as u16) {
+                    report_issue(block_height, format!("Transaction {} has incorrect metadata", transaction_id));
+                }
+
+                for (transition_index, transition_id) in transition_ids.iter().enumerate() {
+                    let transition = match self.blocks.transactions.transitions.get(transition_id)? {
+                        Some((candidate_id, candidate_index, transition))
+                            if candidate_id == *transaction_id && candidate_index as usize == transition_index =>
+                        {
+                            transition
+                        }
+                        Some(_) => {
+                            report_issue(
+                                block_height,
+                                format!("Transition {} is indexed with the wrong transaction", transition_id),
+                            );
+                            continue;
+                        }
+                        None => {
+                            report_issue(
+                                block_height,
+                                format!("Transition {} is missing from the transitions map", transition_id),
+                            );
+                            continue;
+                        }
+                    };
+                    number_of_transitions += 1;
+
+                    for serial_number in transition.serial_numbers() {
+                        number_of_serial_numbers += 1;
+                        if self.blocks.transactions.serial_numbers.get(serial_number)?.as_ref() != Some(transition_id) {
+                            report_issue(
+                                block_height,
+                                format!("Serial number {} is not indexed with its transition", serial_number),
+                            );
+                        }
+                    }
+                    for commitment in transition.commitments() {
+                        number_of_commitments += 1;
+                        if self.blocks.transactions.commitments.get(commitment)?.as_ref() != Some(transition_id) {
+                            report_issue(
+                                block_height,
+                                format!("Commitment {} is not indexed with its transition", commitment),
+                            );
+                        }
+                    }
+                }
+            }
+
+            if block_height % 10000 == 0 {
+                debug!("Verified the ledger indexes up to block {}", block_height);
+            }
+        }
+
+        // Ensure there are no stale entries in storage, which do not belong to any block.
+        let number_of_entries = [
+            ("ledger roots", self.ledger_roots.keys().count(), latest_block_height as usize + 1),
+            (
+                "block headers",
+                self.blocks.block_headers.keys().count(),
+                latest_block_height as usize + 1,
+            ),
+            (
+                "block transactions",
+                self.blocks.block_transactions.keys().count(),
+                latest_block_height as usize + 1,
+            ),
+            (
+                "transactions",
+                self.blocks.transactions.transactions.keys().count(),
+                number_of_transactions,
+            ),
+            (
+                "transitions",
+                self.blocks.transactions.transitions.keys().count(),
+                number_of_transitions,
+            ),
+            (
+                "serial numbers",
+                self.blocks.transactions.serial_numbers.keys().count(),
+                number_of_serial_numbers,
+            ),
+            (
+                "commitments",
+                self.blocks.transactions.commitments.keys().count(),
+                number_of_commitments,
+            ),
+        ];
+        for (name, candidate, expected) in number_of_entries.iter() {
+            if candidate != expected {
+                report_issue(
+                    latest_block_height,
+                    format!("The {} map contains {} entries, expected {}", name, candidate, expected),
+                );
+            }
+        }
+
+        // Ensure each block is valid, if requested.
+        if is_full {
+            const INCREMENT: u32 = 1000;
+            let mut start_block_height = 0u32;
+            while start_block_height <= latest_block_height {
+                let end_block_height = std::cmp::min(start_block_height.saturating_add(INCREMENT - 1), latest_block_height);
+
+                let issues: Vec<(u32, String)> = (start_block_height..=end_block_height)
+                    .into_par_iter()
+                    .filter_map(|block_height| match self.get_block(block_height) {
+                        Ok(block) => match block.is_valid() {
+                            true => None,
+                            false => Some((block_height, "Block is invalid".to_string())),
+                        },
+                        Err(error) => Some((block_height, format!("Failed to load block: {}", error))),
+                    })
+                    .collect();
+                for (block_height, issue) in issues {
+                    report_issue(block_height, issue);
+                }
+
+                debug!("Validated the ledger blocks up to block {}", end_block_height);
+                start_block_height = end_block_height.saturating_add(1);
+            }
+        }
+
+        Ok(report)
+    }
+
     ///
     /// Returns a ledger proof for the given commitment.
     ///
@@ -1166,6 +1535,52 @@ impl<N: Network> LedgerState<N> {
         }
     }
 
+    ///
+    /// Repairs inconsistent ledger state, including the cases that `try_fixing_inconsistent_state` is unable to resolve.
+    ///
+    /// If a block was saved without its ledger root, for example due to a crash during `add_next_block`,
+    /// every block above the latest ledger root is removed from storage. If the ledger roots are missing
+    /// entirely, the ledger is reverted to the genesis block, and if the blocks are missing entirely,
+    /// the ledger roots are cleared so that the ledger is initialized from the genesis block.
+    ///
+    fn repair_inconsistent_state(&self) -> Result<u32> {
+        // If the storage is in read-only mode, this method cannot be called.
+        if self.is_read_only() {
+            return Err(anyhow!("Ledger must be writable to repair inconsistent state"));
+        }
+
+        match (self.ledger_roots.values().max(), self.blocks.block_heights.keys().max()) {
+            (Some(latest_block_height_0), Some(latest_block_height_1)) if latest_block_height_0 < latest_block_height_1 => {
+                warn!(
+                    "Removing blocks {} to {}, which are missing from the ledger roots map",
+                    latest_block_height_0.saturating_add(1),
+                    latest_block_height_1
+                );
+                self.clear_incompatible_blocks(latest_block_height_1, latest_block_height_0)?;
+            }
+            (None, Some(latest_block_height_1)) => {
+                warn!("Ledger roots are missing, reverting the ledger to the genesis block");
+                self.clear_incompatible_blocks(latest_block_height_1, 0)?;
+                let genesis = N::genesis_block();
+                self.ledger_roots.insert(&genesis.previous_ledger_root(), &genesis.height())?;
+            }
+            (Some(_), None) => {
+                warn!("Blocks are missing, clearing the ledger roots");
+                let ledger_roots = self.ledger_roots.keys().collect::<Vec<_>>();
+                for ledger_root in ledger_roots {
+                    self.ledger_roots.remove(&ledger_root)?;
+                }
+                return Ok(0u32);
+            }
+            _ => (),
+        }
+
+        // Resolve any remaining inconsistent state, in which there are more ledger roots than blocks.
+        let latest_block_height = self.try_fixing_inconsistent_state()?;
+        info!("Successfully repaired the ledger state at block {}", latest_block_height);
+        Ok(latest_block_height)
+    }
+
     /// Attempts to revert from the latest block height to the given revert block height.
     fn clear_incompatible_blocks(&self, latest_block_height: u32, revert_block_height: u32) -> Result<u32> {
         // Acquire the map lock to ensure the following operations aren't interrupted by a shutdown.
@@ -15,10 +15,15 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    storage::{rocksdb::RocksDB, Storage},
+    storage::{rocksdb::RocksDB, Map, MapId, Storage},
+    InvalidBlockError,
     LedgerState,
+    Metadata,
+};
+use snarkvm::{
+    dpc::{prelude::*, testnet2::Testnet2},
+    utilities::ToBytes,
 };
-use snarkvm::dpc::{prelude::*, testnet2::Testnet2};
 
 use rand::thread_rng;
 use std::sync::atomic::AtomicBool;
@@ -101,6 +106,11 @@ fn test_add_next_block() {
         block_locators.get(&block.height())
     );
     assert_eq!(Some(&(genesis.hash(), None)), block_locators.get(&genesis.height()));
+
+    // Ensure a block that fails validation is rejected with an `InvalidBlockError`.
+    let error = ledger.add_next_block(&block).unwrap_err();
+    assert!(error.is::<InvalidBlockError>(), "{}", error);
+    assert_eq!(1, ledger.latest_block_height());
 }
 
 #[test]
@@ -202,6 +212,91 @@ fn test_remove_last_2_blocks() {
     assert_eq!(ledger_tree.root(), ledger.latest_ledger_root());
 }
 
+#[test]
+fn test_rewind_to_block_height() {
+    let rng = &mut thread_rng();
+    let terminator = AtomicBool::new(false);
+
+    // Initialize a new ledger.
+    let ledger = create_new_ledger::<Testnet2, RocksDB>();
+    assert_eq!(0, ledger.latest_block_height());
+
+    // Initialize a new account.
+    let account = Account::<Testnet2>::new(&mut thread_rng());
+    let address = account.address();
+
+    // Mine the next three blocks.
+    let mut blocks = vec![Testnet2::genesis_block().clone()];
+    for _ in 0..3 {
+        let (block, _) = ledger
+            .mine_next_block(address, true, &[], &terminator, rng)
+            .expect("Failed to mine");
+        ledger.add_next_block(&block).expect("Failed to add next block to ledger");
+        blocks.push(block);
+    }
+    assert_eq!(3, ledger.latest_block_height());
+
+    // Ensure the ledger may not be rewound past its latest block.
+    assert!(ledger.rewind_to_block_height(4).is_err());
+
+    // Rewind to the first block.
+    let number_of_blocks = ledger.rewind_to_block_height(1).expect("Failed to rewind the ledger");
+    assert_eq!(2, number_of_blocks);
+
+    // Ensure the ledger is back at the first block.
+    let mut ledger_tree = LedgerTree::<Testnet2>::new().expect("Failed to initialize ledger tree");
+    ledger_tree
+        .add_all(&[blocks[0].hash(), blocks[1].hash()])
+        .expect("Failed to add to ledger tree");
+
+    assert_eq!(1, ledger.latest_block_height());
+    assert_eq!(blocks[1], ledger.latest_block());
+    assert_eq!(ledger_tree.root(), ledger.latest_ledger_root());
+    assert!(!ledger.contains_block_hash(&blocks[2].hash()).unwrap());
+    assert!(ledger.verify(false).expect("Failed to verify ledger").is_empty());
+}
+
+#[test]
+fn test_open_writer_with_repair() {
+    let rng = &mut thread_rng();
+    let terminator = AtomicBool::new(false);
+    let directory = temp_dir();
+
+    // Initialize a new ledger, and mine the next two blocks.
+    {
+        let ledger = LedgerState::<Testnet2>::open_writer::<RocksDB, _>(&directory).expect("Failed to initialize ledger");
+        let address = Account::<Testnet2>::new(&mut thread_rng()).address();
+        for _ in 0..2 {
+            let (block, _) = ledger
+                .mine_next_block(address, true, &[], &terminator, rng)
+                .expect("Failed to mine");
+            ledger.add_next_block(&block).expect("Failed to add next block to ledger");
+        }
+        assert_eq!(2, ledger.latest_block_height());
+    }
+
+    // Remove the ledger root of the latest block, as if the node crashed while adding it.
+    {
+        let storage = RocksDB::open(&directory, Testnet2::NETWORK_ID, false).expect("Failed to open storage");
+        let ledger_roots = storage
+            .open_map::<<Testnet2 as Network>::LedgerRoot, u32>(MapId::LedgerRoots)
+            .expect("Failed to open the ledger roots map");
+        let (ledger_root, _) = ledger_roots
+            .iter()
+            .find(|(_, block_height)| *block_height == 2)
+            .expect("Failed to find the ledger root");
+        ledger_roots.remove(&ledger_root).expect("Failed to remove the ledger root");
+    }
+
+    // Ensure the ledger may not be opened without a repair.
+    assert!(LedgerState::<Testnet2>::open_writer::<RocksDB, _>(&directory).is_err());
+
+    // Ensure the repair reverts the ledger to the last consistent block.
+    let ledger = LedgerState::<Testnet2>::open_writer_with_repair::<RocksDB, _>(&directory).expect("Failed to repair ledger");
+    assert_eq!(1, ledger.latest_block_height());
+    assert!(ledger.verify(false).expect("Failed to verify ledger").is_empty());
+}
+
 #[test]
 fn test_get_block_locators() {
     let rng = &mut thread_rng();
@@ -272,3 +367,196 @@ fn test_get_block_locators() {
             .expect("Failed to check block locators")
     );
 }
+
+#[test]
+fn test_export_and_import_blocks() {
+    let rng = &mut thread_rng();
+    let terminator = AtomicBool::new(false);
+
+    // Initialize a new ledger.
+    let ledger = create_new_ledger::<Testnet2, RocksDB>();
+    assert_eq!(0, ledger.latest_block_height());
+
+    // Initialize a new account.
+    let account = Account::<Testnet2>::new(&mut thread_rng());
+    let address = account.address();
+
+    // Mine the next two blocks.
+    for _ in 0..2 {
+        let (block, _) = ledger
+            .mine_next_block(address, true, &[], &terminator, rng)
+            .expect("Failed to mine");
+        ledger.add_next_block(&block).expect("Failed to add next block to ledger");
+    }
+    assert_eq!(2, ledger.latest_block_height());
+
+    // Export the blocks.
+    let mut buffer = vec![];
+    let number_of_blocks = ledger.export_blocks(&mut buffer, 0).expect("Failed to export blocks");
+    assert_eq!(3, number_of_blocks);
+
+    // Import the blocks into a new ledger.
+    let candidate_ledger = create_new_ledger::<Testnet2, RocksDB>();
+    let number_of_blocks = candidate_ledger.import_blocks(&buffer[..]).expect("Failed to import blocks");
+    assert_eq!(2, number_of_blocks);
+
+    // Ensure the ledgers match.
+    assert_eq!(ledger.latest_block_height(), candidate_ledger.latest_block_height());
+    assert_eq!(ledger.latest_block_hash(), candidate_ledger.latest_block_hash());
+    assert_eq!(ledger.latest_ledger_root(), candidate_ledger.latest_ledger_root());
+
+    // Ensure a repeated import is a no-op.
+    let number_of_blocks = candidate_ledger.import_blocks(&buffer[..]).expect("Failed to import blocks");
+    assert_eq!(0, number_of_blocks);
+}
+
+#[test]
+fn test_verify() {
+    let rng = &mut thread_rng();
+    let terminator = AtomicBool::new(false);
+
+    // Initialize a new ledger.
+    let ledger = create_new_ledger::<Testnet2, RocksDB>();
+    assert!(ledger.verify(true).expect("Failed to verify ledger").is_empty());
+
+    // Initialize a new account.
+    let account = Account::<Testnet2>::new(&mut thread_rng());
+    let address = account.address();
+
+    // Mine the next two blocks.
+    for _ in 0..2 {
+        let (block, _) = ledger
+            .mine_next_block(address, true, &[], &terminator, rng)
+            .expect("Failed to mine");
+        ledger.add_next_block(&block).expect("Failed to add next block to ledger");
+    }
+    assert_eq!(2, ledger.latest_block_height());
+
+    // Ensure the ledger has no inconsistencies.
+    let report = ledger.verify(true).expect("Failed to verify ledger");
+    assert!(report.is_empty(), "{:?}", report);
+}
+
+#[test]
+fn test_verify_reports_corrupted_indexes() {
+    let rng = &mut thread_rng();
+    let terminator = AtomicBool::new(false);
+    let directory = temp_dir();
+
+    // Initialize a new ledger, and mine the next two blocks.
+    let (transaction_1, transaction_2) = {
+        let ledger = LedgerState::<Testnet2>::open_writer::<RocksDB, _>(&directory).expect("Failed to initialize ledger");
+        let address = Account::<Testnet2>::new(&mut thread_rng()).address();
+        for _ in 0..2 {
+            let (block, _) = ledger
+                .mine_next_block(address, true, &[], &terminator, rng)
+                .expect("Failed to mine");
+            ledger.add_next_block(&block).expect("Failed to add next block to ledger");
+        }
+        let coinbase_transaction = |block_height| {
+            let block = ledger.get_block(block_height).expect("Failed to get block");
+            block
+                .transactions()
+                .first()
+                .expect("Failed to get the coinbase transaction")
+                .clone()
+        };
+        (coinbase_transaction(1), coinbase_transaction(2))
+    };
+
+    // Corrupt the indexes of the first mined block.
+    {
+        let storage = RocksDB::open(&directory, Testnet2::NETWORK_ID, false).expect("Failed to open storage");
+
+        // Index the transaction of block 1 with the metadata of block 2.
+        let transactions = storage
+            .open_map::<<Testnet2 as Network>::TransactionID, (
+                <Testnet2 as Network>::LedgerRoot,
+                Vec<<Testnet2 as Network>::TransitionID>,
+                Metadata<Testnet2>,
+            )>(MapId::Transactions)
+            .expect("Failed to open the transactions map");
+        let (_, _, metadata_2) = transactions
+            .get(&transaction_2.transaction_id())
+            .expect("Failed to get the transaction")
+            .expect("Failed to find the transaction");
+        let (ledger_root, transition_ids, _) = transactions
+            .get(&transaction_1.transaction_id())
+            .expect("Failed to get the transaction")
+            .expect("Failed to find the transaction");
+        transactions
+            .insert(&transaction_1.transaction_id(), &(ledger_root, transition_ids, metadata_2))
+            .expect("Failed to update the transaction");
+
+        // Remove a commitment of block 1.
+        let commitments = storage
+            .open_map::<<Testnet2 as Network>::Commitment, <Testnet2 as Network>::TransitionID>(MapId::Commitments)
+            .expect("Failed to open the commitments map");
+        let commitment = transaction_1.commitments().next().expect("Failed to get a commitment");
+        commitments.remove(commitment).expect("Failed to remove the commitment");
+    }
+
+    // Ensure the verification reports both inconsistencies with block 1.
+    let ledger = LedgerState::<Testnet2>::open_reader::<RocksDB, _>(&directory).expect("Failed to open ledger");
+    let report = ledger.verify(false).expect("Failed to verify ledger");
+    let issues = report.get(&1).expect("Failed to report the inconsistencies of block 1");
+    assert!(issues.iter().any(|issue| issue.contains("has incorrect metadata")), "{:?}", issues);
+    assert!(
+        issues.iter().any(|issue| issue.contains("is not indexed with its transition")),
+        "{:?}",
+        issues
+    );
+
+    // Ensure the missing commitment is also reported in the entry counts, at the latest block height.
+    let issues = report.get(&2).expect("Failed to report the entry counts");
+    assert!(
+        issues.iter().any(|issue| issue.contains("The commitments map contains")),
+        "{:?}",
+        issues
+    );
+    assert!(!report.contains_key(&0));
+}
+
+#[test]
+fn test_import_blocks_resumes_after_truncation() {
+    let rng = &mut thread_rng();
+    let terminator = AtomicBool::new(false);
+
+    // Initialize a new ledger.
+    let ledger = create_new_ledger::<Testnet2, RocksDB>();
+
+    // Initialize a new account.
+    let account = Account::<Testnet2>::new(&mut thread_rng());
+    let address = account.address();
+
+    // Mine the next two blocks.
+    for _ in 0..2 {
+        let (block, _) = ledger
+            .mine_next_block(address, true, &[], &terminator, rng)
+            .expect("Failed to mine");
+        ledger.add_next_block(&block).expect("Failed to add next block to ledger");
+    }
+
+    // Export the blocks.
+    let mut buffer = vec![];
+    ledger.export_blocks(&mut buffer, 0).expect("Failed to export blocks");
+
+    // Import a truncated block stream, which ends partway through the last block.
+    let candidate_ledger = create_new_ledger::<Testnet2, RocksDB>();
+    let last_block_size = ledger.latest_block().to_bytes_le().unwrap().len();
+    let truncated = &buffer[..buffer.len() - 12 - last_block_size];
+    assert!(candidate_ledger.import_blocks(truncated).is_err());
+    assert_eq!(1, candidate_ledger.latest_block_height());
+
+    // Resume the import from the full block stream.
+    let number_of_blocks = candidate_ledger.import_blocks(&buffer[..]).expect("Failed to import blocks");
+    assert_eq!(1, number_of_blocks);
+    assert_eq!(ledger.latest_block_hash(), candidate_ledger.latest_block_hash());
+
+    // Ensure a corrupted block stream is rejected.
+    let mut corrupted = buffer.clone();
+    let index = corrupted.len() / 2;
+    corrupted[index] ^= 1;
+    let corrupted_ledger = create_new_ledger::<Testnet2, RocksDB>();
+    assert!(corrupted_ledger.import_blocks(&corrupted[..]).is_err());
+}